@@ -1,12 +1,36 @@
 // Use the utility module from crate root
-use crate::utility::show_loading_spinner_custom;
+use crate::utility::{format_age, now_millis, show_loading_spinner_custom};
 use egui::Id;
-use crate::db::github::{GithubDb, Repository};
-use crate::db::idb::LANGUAGES;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use crate::db::github::{GithubDb, PUBLIC_HOST};
+use crate::db::record::RepoRecord;
+use crate::db::{gitea, gitlab};
+use crate::db::graphql::SyncBackend;
+use crate::db::registry::Registry;
+use crate::db::source::{host_of, normalize_base_url, ForgeConfig, SourceKind};
+use crate::db::favorites::FavoritesStore;
+use crate::db::notes::NotesStore;
+use crate::db::query::QueryContext;
+use crate::db::saved_search::{SavedSearch, SavedSearchStore};
+use crate::db::settings::SettingsStore;
+use crate::db::cloud_sync::{CloudSync, SyncStatus, SyncTarget};
+use crate::db::idb::{LANGUAGES, STARS_STORE};
 use crate::erust::uiux::search::SearchWidget;
 use crate::erust::state::{AppState, WaffleState};
 use crate::erust::uiux::auth::AuthWidget;
+use crate::erust::uiux::backup::BackupWidget;
+use crate::erust::uiux::collections::CollectionsWidget;
+use crate::erust::uiux::detail::DetailPane;
+use crate::erust::uiux::export::ExportWidget;
+use crate::erust::uiux::import::ImportWidget;
+use crate::erust::uiux::sources::forge_settings;
+use crate::erust::uiux::health::{health_badge, weights_editor};
+use crate::erust::uiux::license_policy::{LicensePolicyWidget, license_flag};
+use crate::erust::uiux::saved_searches::SavedSearchesWidget;
+use crate::erust::uiux::stats::StatsWidget;
 use crate::erust::uiux::user::User;
+use crate::erust::uiux::what_changed::WhatChangedWidget;
 
 #[derive(serde::Deserialize, serde::Serialize, Debug, Clone, PartialEq, Eq)]
 pub enum LoadingState {
@@ -29,12 +53,32 @@ pub enum LoadingKind {
     ClearCache,
 }
 
+/// Views in the central panel
+#[derive(serde::Deserialize, serde::Serialize, PartialEq, Eq, Debug, Clone, Copy, Default)]
+pub enum MainTab {
+    #[default]
+    Repositories,
+    Stats,
+}
+
 /// We derive Deserialize/Serialize so we can persist app state on shutdown.
 #[derive(serde::Deserialize, serde::Serialize)]
 #[serde(default)] // if we add new fields, give them default values when deserializing old state
 pub struct TemplateApp {
     // Example stuff:
     label: String,
+    tab: MainTab,
+    /// GitHub personal access token. Kept in app storage rather than IndexedDB so backups never contain it.
+    github_token: String,
+    sync_backend: SyncBackend,
+    sync_source: SourceKind,
+    /// GitLab and Gitea instances and tokens, kept in app storage for the same reason as github_token
+    gitlab: ForgeConfig,
+    gitea: ForgeConfig,
+    /// GitHub Enterprise Server hosts, each with its own token
+    enterprise_hosts: Vec<ForgeConfig>,
+    /// Index into enterprise_hosts that GitHub syncs use; None for github.com
+    github_host: Option<usize>,
     #[serde(skip)]
     db: GithubDb,
     #[serde(skip)]
@@ -57,7 +101,7 @@ pub struct TemplateApp {
     #[serde(skip)]
     waffle_state: WaffleState,
     #[serde(skip)]
-    filtered_repos: Option<Vec<Repository>>,
+    filtered_repos: Option<Arc<[RepoRecord]>>,
     #[serde(skip)]
     filter_loading: bool,
     #[serde(skip)]
@@ -65,6 +109,44 @@ pub struct TemplateApp {
     #[serde(skip)]
     auth_widget: AuthWidget,
     #[serde(skip)]
+    favorites: FavoritesStore,
+    #[serde(skip)]
+    collections_widget: CollectionsWidget,
+    #[serde(skip)]
+    cloud_sync: CloudSync,
+    #[serde(skip)]
+    notes: NotesStore,
+    #[serde(skip)]
+    notes_revision: u64,
+    #[serde(skip)]
+    detail_pane: DetailPane,
+    #[serde(skip)]
+    selected_repo: Option<RepoRecord>,
+    #[serde(skip)]
+    saved_searches: SavedSearchStore,
+    #[serde(skip)]
+    saved_searches_widget: SavedSearchesWidget,
+    #[serde(skip)]
+    pending_view: Option<String>, // Saved search whose results should be marked seen on arrival
+    #[serde(skip)]
+    seen_syncs: u64,
+    #[serde(skip)]
+    what_changed: WhatChangedWidget,
+    #[serde(skip)]
+    stats_widget: StatsWidget,
+    #[serde(skip)]
+    settings: SettingsStore,
+    #[serde(skip)]
+    settings_revision: u64,
+    #[serde(skip)]
+    license_policy_widget: LicensePolicyWidget,
+    #[serde(skip)]
+    export_widget: ExportWidget,
+    #[serde(skip)]
+    import_widget: ImportWidget,
+    #[serde(skip)]
+    backup_widget: BackupWidget,
+    #[serde(skip)]
     user: User,
     #[serde(skip)]
     show_auth_window: bool, // Track if auth window should be shown
@@ -77,6 +159,14 @@ impl Default for TemplateApp {
         Self {
             // Example stuff:
             label: "Hello World!".to_owned(),
+            tab: MainTab::Repositories,
+            github_token: String::new(),
+            sync_backend: SyncBackend::Rest,
+            sync_source: SourceKind::GitHub,
+            gitlab: ForgeConfig::new(gitlab::DEFAULT_BASE_URL),
+            gitea: ForgeConfig::new(gitea::DEFAULT_BASE_URL),
+            enterprise_hosts: Vec::new(),
+            github_host: None,
             value: 2.7,
             db: GithubDb::new(),
             logo_texture: None,
@@ -91,6 +181,25 @@ impl Default for TemplateApp {
             filter_loading: false,
             search_widget: Some(SearchWidget::new()),
             auth_widget: AuthWidget::new(false),
+            favorites: FavoritesStore::new(),
+            collections_widget: CollectionsWidget::new(),
+            cloud_sync: CloudSync::new(),
+            notes: NotesStore::new(),
+            notes_revision: 0,
+            detail_pane: DetailPane::new(),
+            selected_repo: None,
+            saved_searches: SavedSearchStore::new(),
+            saved_searches_widget: SavedSearchesWidget::new(),
+            pending_view: None,
+            seen_syncs: 0,
+            what_changed: WhatChangedWidget::new(),
+            stats_widget: StatsWidget::new(),
+            settings: SettingsStore::new(),
+            settings_revision: 0,
+            license_policy_widget: LicensePolicyWidget::new(),
+            export_widget: ExportWidget::new(),
+            import_widget: ImportWidget::new(),
+            backup_widget: BackupWidget::new(),
             user: User::default(),
             show_auth_window: false,
             show_welcome: true, // Show welcome window by default
@@ -108,59 +217,129 @@ impl TemplateApp {
             Default::default()
         };
         app.app_state = AppState::Init;
+        app.db.set_token(&app.github_token);
+        app.db.set_backend(app.sync_backend);
+        app.db.set_source(app.sync_source);
+        app.db.set_gitlab(app.gitlab.clone());
+        app.db.set_gitea(app.gitea.clone());
+        app.apply_github_host();
         app.db.load_from_indexeddb();
         app.load_filtered_repos_from_idb(&cc.egui_ctx);
+        app.favorites.load();
+        app.notes.load();
+        app.saved_searches.load();
+        app.settings.load();
+        app.cloud_sync.load();
         // --- Call JSRust to request user info when app is ready ---
         crate::erust::uiux::javascript_interop::request_user_from_js();
         app
     }
 
-    /// Load all repos for the current language from IndexedDB and set filtered_repos
+    /// Load the repos for the current language from IndexedDB that match the current query
     pub fn load_filtered_repos_from_idb(&mut self, ctx: &egui::Context) {
         let language = self.db.get_language();
-        let ctx = ctx.clone();
+        let notes = self.notes.get();
+        let pending_view = self.pending_view.take();
+        let saved_searches = self.saved_searches.clone();
+        if let Some(widget) = &mut self.search_widget {
+            widget.health = self.settings.with(|s| s.health);
+            widget.license_policy = self.settings.with(|s| s.license_policy.clone());
+            widget.search_then(&language, &notes, ctx, move |results| {
+                if let Some(name) = pending_view {
+                    saved_searches.update_quiet(|s| s.mark_viewed(&name, results));
+                }
+            });
+        }
+    }
+
+    /// Recount new results for every saved search against freshly synced data, reading each
+    /// language store once for all the searches over it
+    fn refresh_saved_search_badges(&self) {
+        let mut by_language = BTreeMap::<String, Vec<SavedSearch>>::new();
+        for search in self.saved_searches.get().searches {
+            by_language.entry(search.language.clone()).or_default().push(search);
+        }
+        if by_language.is_empty() {
+            return;
+        }
+        let notes = self.notes.get();
+        let health = self.settings.with(|s| s.health);
+        let license_policy = self.settings.with(|s| s.license_policy.clone());
+        let store = self.saved_searches.clone();
         wasm_bindgen_futures::spawn_local(async move {
-            let result = match crate::db::idb::open_waffle_db().await {
-                Ok(db_conn) => crate::db::idb::filter_repos_in_idb::<Repository>(&db_conn, &language, "").await.unwrap_or_default(),
-                Err(_) => vec![],
+            let db_conn = crate::db::idb::open_waffle_db().await.ok();
+            let query_ctx = QueryContext {
+                health,
+                license_policy: Some(&license_policy),
+                ..QueryContext::new(&notes)
             };
-            ctx.data_mut(|d| d.insert_temp(Id::new("waffle_filtered_repos"), result));
-            ctx.request_repaint();
+            for (language, searches) in by_language {
+                let repos = match &db_conn {
+                    Some(db_conn) => crate::db::idb::get_all_records(db_conn, &language).await.unwrap_or_default(),
+                    None => vec![],
+                };
+                for search in searches {
+                    let results = search.spec.run(repos.clone(), &query_ctx);
+                    store.update_quiet(|s| s.refresh_badge(&search.name, &results));
+                }
+            }
         });
     }
 
-    async fn check_empty_and_update_state_async(&mut self) {
-        use crate::db::idb;
-        use crate::db::github::Repository;
-        if let Ok(db) = idb::open_waffle_db().await {
-            let language = self.db.get_language();
-            let key = format!("latest_{}", language.to_lowercase());
-            match idb::get_repo::<Vec<Repository>>(&db, &language, &key).await {
-                Ok(Some(repos)) => {
-                    if repos.is_empty() {
-                        self.pending_app_state = Some(AppState::Empty);
-                    } else {
-                        self.pending_app_state = Some(AppState::Normal);
-                    }
-                },
-                Ok(None) => {
-                    self.pending_app_state = Some(AppState::Empty);
-                },
-                Err(_) => {
-                    self.pending_app_state = Some(AppState::Empty);
-                }
-            }
-        } else {
-            self.pending_app_state = Some(AppState::Empty);
+    /// Point GitHub syncs at the selected Enterprise Server host, or github.com.
+    fn apply_github_host(&mut self) {
+        if self.github_host.is_some_and(|i| i >= self.enterprise_hosts.len()) {
+            self.github_host = None;
+        }
+        self.db.set_github_host(self.github_host.map(|i| self.enterprise_hosts[i].clone()));
+    }
+
+    fn github_host_label(&self, host: Option<usize>) -> String {
+        match host.and_then(|i| self.enterprise_hosts.get(i)) {
+            Some(config) => host_of(&normalize_base_url(&config.base_url)).to_string(),
+            None => PUBLIC_HOST.to_string(),
+        }
+    }
+
+    /// Local data sets that follow the user between browsers through cloud sync
+    fn sync_targets(&self) -> Vec<&dyn SyncTarget> {
+        vec![&self.favorites, &self.notes, &self.saved_searches]
+    }
+
+    /// Files dropped on the window go to exactly one open drop target: Import or Backup & Restore,
+    /// whichever is on top (the one opened or clicked last).
+    fn route_dropped_files(&self, ctx: &egui::Context) {
+        let files = ctx.input(|i| i.raw.dropped_files.clone());
+        if files.is_empty() {
+            return;
         }
+        // Back to front
+        let order = ctx.memory(|m| m.layer_ids().collect::<Vec<_>>());
+        let depth = |layer: Option<egui::LayerId>| layer.and_then(|l| order.iter().position(|o| *o == l));
+        match (depth(self.import_widget.layer()), depth(self.backup_widget.layer())) {
+            (Some(import), Some(backup)) if backup > import => self.backup_widget.drop_files(files),
+            (Some(_), _) => self.import_widget.drop_files(files),
+            (None, Some(_)) => self.backup_widget.drop_files(files),
+            (None, None) => {}
+        }
+    }
+
+    async fn check_empty_and_update_state_async(&mut self) {
+        use crate::db::{idb, sync};
+        let empty = match idb::open_waffle_db().await {
+            Ok(db) => !matches!(sync::status(&db, &self.db.get_language()).await, Ok(status) if !status.is_empty()),
+            Err(_) => true,
+        };
+        self.pending_app_state = Some(if empty { AppState::Empty } else { AppState::Normal });
     }
 
     pub fn filter_repos_async(&mut self, query: &str, ctx: &egui::Context) {
         self.filter_loading = true;
         self.filtered_repos = None;
+        let notes = self.notes.get();
         if let Some(widget) = &mut self.search_widget {
-            widget.query = query.to_string();
-            widget.search(&self.db.get_language(), ctx);
+            widget.spec.query = query.to_string();
+            widget.search(&self.db.get_language(), &notes, ctx);
         }
     }
 
@@ -175,6 +354,7 @@ impl TemplateApp {
         }
         // Loading state machine
         let mut error_to_trigger: Option<String> = None;
+        let mut reload_search = false;
         match &mut self.loading_state {
             LoadingState::Idle => {},
             LoadingState::Loading { kind, message: _, pending_language } => {
@@ -184,37 +364,19 @@ impl TemplateApp {
                             self.db.set_language(&lang);
                             self.db.load_from_indexeddb();
                             // Immediately reload filtered repos for the new language
-                            let language = self.db.get_language();
-                            let ctx = ctx.clone();
-                            wasm_bindgen_futures::spawn_local(async move {
-                                let result = match crate::db::idb::open_waffle_db().await {
-                                    Ok(db_conn) => crate::db::idb::filter_repos_in_idb::<Repository>(&db_conn, &language, "").await.unwrap_or_default(),
-                                    Err(_) => vec![],
-                                };
-                                ctx.data_mut(|d| d.insert_temp(Id::new("waffle_filtered_repos"), result));
-                                ctx.request_repaint();
-                            });
+                            reload_search = true;
                         }
                     },
                     LoadingKind::Sync => {
-                        let db = self.db.clone();
-                        let ctx = ctx.clone();
-                        wasm_bindgen_futures::spawn_local(async move {
-                            db.sync_and_store();
-                            db.load_from_indexeddb();
-                            // After sync, reload filtered repos
-                            let language = db.get_language();
-                            let result = match crate::db::idb::open_waffle_db().await {
-                                Ok(db_conn) => crate::db::idb::filter_repos_in_idb::<Repository>(&db_conn, &language, "").await.unwrap_or_default(),
-                                Err(_) => vec![],
-                            };
-                            ctx.data_mut(|d| d.insert_temp(Id::new("waffle_filtered_repos"), result));
-                            ctx.request_repaint();
-                        });
+                        self.db.sync_and_store();
+                        self.db.load_from_indexeddb();
+                        // After sync, reload filtered repos
+                        reload_search = true;
                     },
                     LoadingKind::ClearCache => {
                         self.db.clear_indexeddb();
                         self.db.load_from_indexeddb();
+                        reload_search = true;
                     },
                 }
                 let message = match kind {
@@ -224,6 +386,10 @@ impl TemplateApp {
                 };
                 self.loading_state = LoadingState::Finishing { message: message.to_string() };
             },
+            // The overlay (and its Cancel button) stays up until the sync's results are stored
+            LoadingState::Finishing { .. } if self.db.is_syncing() => {
+                ctx.request_repaint_after(std::time::Duration::from_millis(100));
+            },
             LoadingState::Finishing { .. } => {
                 let app_ptr = self as *mut TemplateApp;
                 wasm_bindgen_futures::spawn_local(async move {
@@ -245,27 +411,79 @@ impl TemplateApp {
                 self.loading_state = LoadingState::Idle;
             },
         }
+        if reload_search {
+            self.load_filtered_repos_from_idb(ctx);
+        }
         if let Some(err) = error_to_trigger {
             self.toast_message = Some(format!("Error: {}", err));
         }
+        if let Some(err) = self
+            .favorites
+            .take_error()
+            .or_else(|| self.notes.take_error())
+            .or_else(|| self.saved_searches.take_error())
+            .or_else(|| self.settings.take_error())
+        {
+            self.toast_message = Some(format!("Error: {}", err));
+        }
+        // A sync just landed: refresh the result list and the saved search badges
+        if self.db.completed_syncs() != self.seen_syncs {
+            self.seen_syncs = self.db.completed_syncs();
+            self.load_filtered_repos_from_idb(ctx);
+            self.refresh_saved_search_badges();
+            if let Some(names) = self.db.take_starred() {
+                self.favorites.update(|f| f.set_collection(STARS_STORE, names));
+            }
+            if let Some(report) = self.db.take_report() {
+                if report.previous_at.is_some() {
+                    self.toast_message = Some(format!("Repositories synced! {} change(s) since the last sync.", report.change_count()));
+                    self.toast_timer = 4.0;
+                }
+                if !report.is_empty() {
+                    self.what_changed.present(report);
+                }
+            }
+        }
+        // Notes and tags take part in queries, so re-run the search when they change
+        if self.notes.revision() != self.notes_revision {
+            self.notes_revision = self.notes.revision();
+            self.load_filtered_repos_from_idb(ctx);
+        }
+        // Health weights and the license policy feed queries; the policy also decides what a sync keeps
+        if self.settings.revision() != self.settings_revision {
+            self.settings_revision = self.settings.revision();
+            self.db.set_license_policy(self.settings.with(|s| s.license_policy.clone()));
+            self.load_filtered_repos_from_idb(ctx);
+        }
         // Apply pending_app_state if set
         if let Some(new_state) = self.pending_app_state.take() {
             self.app_state = new_state;
         }
         // Show loading spinner overlay if loading or finishing
+        let mut cancel_sync = false;
         match &self.loading_state {
             LoadingState::Loading { message, .. } | LoadingState::Finishing { message } => {
+                let syncing = self.db.is_syncing();
                 egui::Area::new(Id::new("loading_spinner_overlay"))
                     .fixed_pos((ctx.screen_rect().center().x - 100.0, ctx.screen_rect().center().y - 100.0))
                     .show(ctx, |ui| {
                         ui.spacing_mut().item_spacing = egui::vec2(18.0, 18.0);
                         ui.add_space(48.0);
                         show_loading_spinner_custom(ui, message, Some(140.0));
+                        if syncing && ui.button("Cancel").clicked() {
+                            cancel_sync = true;
+                        }
                         ui.add_space(48.0);
                     });
             },
             _ => {}
         }
+        if cancel_sync {
+            self.db.cancel_sync();
+            self.loading_state = LoadingState::Idle;
+            self.toast_message = Some("Sync cancelled".to_owned());
+            self.toast_timer = 3.0;
+        }
         // Show toast if present
         if self.toast_message.is_some() {
             egui::Area::new(Id::new("toast_area"))
@@ -290,14 +508,85 @@ impl TemplateApp {
             }
             ui.separator();
             let is_loading = matches!(self.loading_state, LoadingState::Loading { .. });
-            if ui.button("Sync").clicked() && !is_loading {
+            let sync_hint = match self.db.rate_limit() {
+                Some(limit) => format!(
+                    "{} API requests left{}{}",
+                    limit.remaining,
+                    limit.limit.map(|l| format!(" of {}", l)).unwrap_or_default(),
+                    limit
+                        .reset_at
+                        .map(|t| format!(", resets in {} min", ((t - now_millis()) / 60_000.0).ceil().max(0.0)))
+                        .unwrap_or_default(),
+                ),
+                None => "Fetch the most starred repositories".to_owned(),
+            };
+            if ui.button("Sync").on_hover_text(sync_hint).clicked() && !is_loading {
                 self.loading_state = LoadingState::Loading {
                     kind: LoadingKind::Sync,
                     message: "Syncing repositories...".to_owned(),
                     pending_language: None,
                 };
             }
-            if ui.button("Clear Cache").clicked() && !is_loading {
+            egui::ComboBox::from_id_salt("waffle_sync_source")
+                .selected_text(self.sync_source.label())
+                .show_ui(ui, |ui| {
+                    for source in SourceKind::ALL {
+                        if ui.selectable_value(&mut self.sync_source, *source, source.label()).changed() {
+                            self.db.set_source(self.sync_source);
+                        }
+                    }
+                });
+            if self.sync_source == SourceKind::GitHub && !self.enterprise_hosts.is_empty() {
+                let mut host = self.github_host;
+                egui::ComboBox::from_id_salt("waffle_github_host")
+                    .selected_text(self.github_host_label(host))
+                    .show_ui(ui, |ui| {
+                        ui.selectable_value(&mut host, None, PUBLIC_HOST);
+                        for i in 0..self.enterprise_hosts.len() {
+                            ui.selectable_value(&mut host, Some(i), self.github_host_label(Some(i)));
+                        }
+                    });
+                if host != self.github_host {
+                    self.github_host = host;
+                    self.apply_github_host();
+                }
+            }
+            if self.sync_source == SourceKind::GitHub {
+                egui::ComboBox::from_id_salt("waffle_sync_backend")
+                    .selected_text(self.sync_backend.label())
+                    .show_ui(ui, |ui| {
+                        for backend in SyncBackend::ALL {
+                            if ui.selectable_value(&mut self.sync_backend, *backend, backend.label()).changed() {
+                                self.db.set_backend(self.sync_backend);
+                            }
+                        }
+                    })
+                    .response
+                    .on_hover_text("GraphQL also fetches releases and languages, but needs a token");
+            }
+            match self.db.enrich_progress() {
+                Some((done, total)) => {
+                    ui.add(egui::Spinner::new());
+                    ui.label(format!("Packages {}/{}", done, total));
+                    if ui.small_button("✖").on_hover_text("Stop looking up packages").clicked() {
+                        self.db.cancel_enrich();
+                    }
+                }
+                None => {
+                    if Registry::for_language(&self.db.get_language()).is_some()
+                        && ui.button("Packages").on_hover_text("Fetch versions and downloads from the package registry").clicked()
+                    {
+                        self.db.enrich_packages();
+                    }
+                }
+            }
+            if ui.button("What changed").on_hover_text("Differences between the last two syncs").clicked() {
+                self.what_changed.open_for(&self.db.get_language(), ctx);
+            }
+            if ui.button("Backup & Restore").clicked() {
+                self.backup_widget.open = true;
+            }
+            if ui.button("Clear Cache").on_hover_text("Make a backup first if you may want this data back").clicked() && !is_loading {
                 self.loading_state = LoadingState::Loading {
                     kind: LoadingKind::ClearCache,
                     message: "Clearing cache...".to_owned(),
@@ -306,13 +595,117 @@ impl TemplateApp {
             }
             ui.separator();
             ui.label("Search:");
-            ui.text_edit_singleline(&mut self.label);
-            let filtered = self.filtered_repos.as_ref().cloned().unwrap_or_default();
+            let search_changed = self.search_widget.as_mut().is_some_and(|widget| widget.show_controls(ui));
+            if search_changed {
+                self.load_filtered_repos_from_idb(ctx);
+            }
+            ui.separator();
+            ui.label("Saved searches:");
+            let current_spec = self.search_widget.as_ref().map(|w| w.spec.clone()).unwrap_or_default();
+            let current_language = self.db.get_language();
+            if let Some(search) = self.saved_searches_widget.show(ui, &self.saved_searches, &current_spec, &current_language) {
+                if let Some(widget) = &mut self.search_widget {
+                    widget.spec = search.spec.clone();
+                }
+                self.pending_view = Some(search.name.clone());
+                if search.language != current_language && !is_loading {
+                    self.loading_state = LoadingState::Loading {
+                        kind: LoadingKind::LanguageSwitch,
+                        message: format!("Switching to {}...", search.language),
+                        pending_language: Some(search.language.clone()),
+                    };
+                } else {
+                    self.load_filtered_repos_from_idb(ctx);
+                }
+            }
+            let filtered = self.filtered_repos.clone().unwrap_or_default();
+            ui.separator();
+            ui.horizontal(|ui| {
+                ui.label(format!("Results: {}", filtered.len()));
+                if ui.button("Export...").clicked() {
+                    self.export_widget.open = true;
+                }
+                if ui.button("Import...").clicked() {
+                    self.import_widget.language = self.db.get_language();
+                    self.import_widget.open = true;
+                }
+            });
+            egui::CollapsingHeader::new("Health score weights").id_salt("waffle_health_settings").show(ui, |ui| {
+                weights_editor(ui, &self.settings);
+            });
+            egui::CollapsingHeader::new("GitHub token").id_salt("waffle_github_token").show(ui, |ui| {
+                ui.label("Lets Sync import your starred repositories into My Stars and raises the rate limit.");
+                if ui.add(egui::TextEdit::singleline(&mut self.github_token).password(true)).changed() {
+                    self.db.set_token(&self.github_token);
+                }
+                if self.db.has_token() && ui.button("Sync My Stars").clicked() {
+                    self.db.sync_starred();
+                }
+            });
+            egui::CollapsingHeader::new("GitHub Enterprise").id_salt("waffle_enterprise_settings").show(ui, |ui| {
+                ui.label("Enterprise Server hosts appear next to Sync when GitHub is the source. Each host keeps its own token and its repositories stay apart from github.com's.");
+                let mut changed = false;
+                let mut remove = None;
+                for (i, host) in self.enterprise_hosts.iter_mut().enumerate() {
+                    ui.separator();
+                    changed |= forge_settings(ui, &format!("waffle_enterprise_grid_{}", i), host);
+                    if ui.small_button("Remove host").clicked() {
+                        remove = Some(i);
+                    }
+                }
+                if let Some(i) = remove {
+                    self.enterprise_hosts.remove(i);
+                    self.github_host = match self.github_host {
+                        Some(selected) if selected == i => None,
+                        Some(selected) if selected > i => Some(selected - 1),
+                        other => other,
+                    };
+                    changed = true;
+                }
+                if ui.button("Add host").clicked() {
+                    self.enterprise_hosts.push(ForgeConfig::new("https://github.example.com"));
+                }
+                if changed {
+                    self.apply_github_host();
+                }
+            });
+            egui::CollapsingHeader::new("GitLab").id_salt("waffle_gitlab_settings").show(ui, |ui| {
+                ui.label("Pick GitLab next to Sync to pull projects from this instance into the same stores.");
+                if forge_settings(ui, "waffle_gitlab_grid", &mut self.gitlab) {
+                    self.db.set_gitlab(self.gitlab.clone());
+                }
+            });
+            egui::CollapsingHeader::new("Gitea / Forgejo").id_salt("waffle_gitea_settings").show(ui, |ui| {
+                ui.label("Codeberg by default; any Gitea or Forgejo instance works.");
+                if forge_settings(ui, "waffle_gitea_grid", &mut self.gitea) {
+                    self.db.set_gitea(self.gitea.clone());
+                }
+            });
+            egui::CollapsingHeader::new("License policy").id_salt("waffle_license_settings").show(ui, |ui| {
+                self.license_policy_widget.show(ui, &self.settings);
+            });
             ui.separator();
-            ui.label(format!("Results: {}", filtered.len()));
+            ui.label("Favorites:");
+            self.collections_widget.show(ui, &self.favorites);
             // --- Show app state at the bottom ---
             ui.separator();
             ui.label(format!("App State: {:?}", self.waffle_state.app_state));
+            if self.user.is_logged_in() {
+                ui.separator();
+                let pending = self.cloud_sync.pending_count(&self.sync_targets());
+                let status = match self.cloud_sync.status() {
+                    SyncStatus::SignedOut => "Cloud sync: signed out".to_string(),
+                    SyncStatus::Syncing => "Cloud sync: syncing...".to_string(),
+                    SyncStatus::Synced { .. } => "Cloud sync: up to date".to_string(),
+                    SyncStatus::Offline(err) => format!("Cloud sync: offline ({}), {} change(s) queued", err, pending),
+                };
+                ui.label(status);
+                if ui.button("Sync Favorites").clicked() {
+                    if let Some(id) = &self.user.id {
+                        self.cloud_sync.sync(id);
+                    }
+                }
+            }
             if !self.waffle_state.log.is_empty() {
                 ui.separator();
                 ui.label("App Log:");
@@ -330,6 +723,18 @@ impl TemplateApp {
                 }
             }
         });
+        if let Some(repo) = self.selected_repo.clone() {
+            let repaint = ctx.clone();
+            let readme = self.db.readme(&repo, move || repaint.request_repaint());
+            egui::SidePanel::right("detail_panel").min_width(260.0).show(ctx, |ui| {
+                if ui.button("Close").clicked() {
+                    self.selected_repo = None;
+                }
+                egui::ScrollArea::vertical().show(ui, |ui| {
+                    self.detail_pane.show(ui, &repo, readme.as_deref(), &self.notes);
+                });
+            });
+        }
         egui::CentralPanel::default().show(ctx, |ui| {
             // --- Logo image loading and display using egui_extras loader system ---
             let logo_url = "https://kbve.com/assets/images/brand/letter_logo.png";
@@ -352,19 +757,93 @@ impl TemplateApp {
                 }
                 ui.separator();
             }
+            ui.horizontal(|ui| {
+                ui.selectable_value(&mut self.tab, MainTab::Repositories, "Repositories");
+                ui.selectable_value(&mut self.tab, MainTab::Stats, "Stats");
+            });
+            ui.separator();
+            if self.tab == MainTab::Stats {
+                ui.heading(format!("{} Statistics", self.db.get_language()));
+                let (language, syncs) = (self.db.get_language(), self.db.completed_syncs());
+                egui::ScrollArea::vertical().id_salt("waffle_stats_scroll").show(ui, |ui| {
+                    self.stats_widget.show(ui, &language, syncs);
+                });
+                return;
+            }
             ui.heading("Filtered Repositories");
+            let sort = self.search_widget.as_ref().map(|w| w.spec.sort).unwrap_or_default();
+            let trends = self.search_widget.as_ref().map(|w| w.trends.clone()).unwrap_or_default();
+            if let Some(days) = sort.trend_days() {
+                ui.label(format!("Ranked by stars gained per day over the last {} days.", days));
+            }
             let filtered = self.filtered_repos.clone().unwrap_or_default();
             let current_language = self.db.get_language();
             if filtered.is_empty() {
                 ui.label(format!("There is no data for {}, please sync.", current_language));
             } else {
-                for repo in &filtered {
-                    let name = repo.full_name.as_deref().unwrap_or("<unknown>");
+                let favorites = self.favorites.get();
+                let notes = self.notes.get();
+                let health = self.settings.with(|s| s.health);
+                let license_policy = self.settings.with(|s| s.license_policy.clone());
+                let now = crate::utility::now_millis();
+                for repo in filtered.iter() {
+                    let key = repo.key();
+                    let name = key.as_str();
                     let desc = repo.description.as_deref().unwrap_or("");
-                    let stars = repo.stargazers_count.unwrap_or(0);
+                    let stars = repo.stars;
                     ui.horizontal(|ui| {
+                        let starred = favorites.is_starred(name);
+                        let star_label = if starred { "★" } else { "☆" };
+                        let star_hint = if starred { "Remove from favorites" } else { "Add to favorites" };
+                        if ui.small_button(star_label).on_hover_text(star_hint).clicked() {
+                            self.favorites.update(|f| f.toggle_star(repo));
+                        }
+                        ui.menu_button("📁", |ui| {
+                            if favorites.collections.is_empty() {
+                                ui.label("Create a collection in the side panel first.");
+                            }
+                            for collection in &favorites.collections {
+                                let member = collection.repos.iter().any(|r| r == name);
+                                if ui.selectable_label(member, &collection.name).clicked() {
+                                    if member {
+                                        self.favorites.update(|f| f.remove_from_collection(&collection.name, name));
+                                    } else {
+                                        self.favorites.update(|f| f.add_to_collection(&collection.name, name));
+                                    }
+                                    ui.close_menu();
+                                }
+                            }
+                        });
+                        if ui.small_button("ℹ").on_hover_text("Details, notes and tags").clicked() {
+                            self.selected_repo = Some(repo.clone());
+                        }
                         ui.label(format!("⭐ {}", stars));
-                        ui.hyperlink_to(name, repo.html_url.as_deref().unwrap_or("#"));
+                        health_badge(ui, repo, &health, now);
+                        license_flag(ui, repo, &license_policy);
+                        if let Some(rate) = trends.get(name) {
+                            ui.label(format!("📈 {:+.1}/day", rate));
+                        }
+                        if !repo.is_public_github() {
+                            ui.label(egui::RichText::new(repo.host()).small().weak()).on_hover_text(repo.source.label());
+                        }
+                        ui.hyperlink_to(&repo.full_name, &repo.html_url);
+                        if let Some(package) = &repo.package {
+                            let published = package.published_at.map(|p| format!(" · published {}", format_age(p, now))).unwrap_or_default();
+                            let downloads = package
+                                .downloads
+                                .map(|d| format!("{} downloads in the last {}", d, package.registry.downloads_window()))
+                                .unwrap_or_else(|| "Downloads unknown".to_string());
+                            ui.label(egui::RichText::new(format!("📦 {}{}", package.version, published)).weak())
+                                .on_hover_text(format!("{} {}: {}", package.registry.label(), package.name, downloads));
+                        }
+                        if let Some(note) = repo.node_id.as_deref().and_then(|id| notes.get(id)) {
+                            for tag in &note.tags {
+                                ui.label(egui::RichText::new(format!("#{}", tag)).weak());
+                            }
+                            if !note.text.trim().is_empty() {
+                                ui.label("📝").on_hover_text(&note.text);
+                            }
+                        }
                     });
                     if !desc.is_empty() {
                         ui.label(desc);
@@ -400,6 +879,25 @@ impl TemplateApp {
                 });
         }
 
+        self.what_changed.show(ctx);
+        let results = self.filtered_repos.clone().unwrap_or_default();
+        self.export_widget.show(ctx, &results, &self.db.get_language());
+        self.import_widget.show(ctx);
+        if self.import_widget.take_finished() {
+            self.db.mark_cache_changed();
+        }
+        self.backup_widget.show(ctx);
+        self.route_dropped_files(ctx);
+        if self.backup_widget.take_restored() {
+            // Stores changed underneath every in-memory copy
+            self.favorites.load();
+            self.notes.load();
+            self.saved_searches.load();
+            self.settings.load();
+            self.cloud_sync.load();
+            self.db.mark_cache_changed();
+        }
+
         // --- Always-visible bottom panel with Logout button ---
         egui::TopBottomPanel::bottom("bottom_panel").show(ctx, |ui| {
             ui.horizontal(|ui| {
@@ -411,6 +909,7 @@ impl TemplateApp {
                         "",
                     );
                     self.user = User::default(); // Reset user to blank/default
+                    self.cloud_sync.sign_out();
                     self.toast_message = Some("Sent logout request to JS".to_string());
                     ctx.request_repaint(); // Ensure UI updates
                 }
@@ -434,10 +933,14 @@ impl TemplateApp {
         // --- Check for new Supabase user and update state ---
         if let Some(new_user) = crate::erust::uiux::javascript_interop::take_supabase_user() {
             if new_user.is_authenticated {
+                if let Some(id) = &new_user.id {
+                    self.cloud_sync.sync(id);
+                }
                 self.user = new_user;
                 ctx.request_repaint();
             }
         }
+        self.cloud_sync.poll(&self.sync_targets());
     }
 }
 
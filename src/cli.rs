@@ -0,0 +1,123 @@
+// cli.rs - native command line entry points that work on the local store without opening the UI
+use crate::db::backup::{self, Backup, RestoreMode};
+use crate::db::export::{self, Column, ExportFormat, ExportOptions};
+use crate::db::{idb, import};
+use crate::db::native::NativeStore;
+
+pub const USAGE: &str = "usage:
+  waffle_search export --language <Rust|Python|Javascript> --format <csv|json|ndjson|md> [--columns a,b,c] [--title <title>] --out <path>
+  waffle_search import --language <Rust|Python|Javascript> --file <path>
+  waffle_search backup --out <path>
+  waffle_search restore --file <path> [--replace]";
+
+/// Run a command line invocation. Returns a message for stdout.
+pub fn run(args: &[String]) -> Result<String, String> {
+    match args.first().map(String::as_str) {
+        Some("export") => run_export(&args[1..]),
+        Some("import") => run_import(&args[1..]),
+        Some("backup") => run_backup(&args[1..]),
+        Some("restore") => run_restore(&args[1..]),
+        _ => Err(USAGE.to_string()),
+    }
+}
+
+fn run_export(args: &[String]) -> Result<String, String> {
+    let mut language = None;
+    let mut out = None;
+    let mut options = ExportOptions::default();
+    let mut iter = args.iter();
+    while let Some(flag) = iter.next() {
+        let mut value = || iter.next().cloned().ok_or_else(|| format!("{} needs a value\n{}", flag, USAGE));
+        match flag.as_str() {
+            "--language" => language = Some(value()?),
+            "--out" => out = Some(value()?),
+            "--title" => options.title = value()?,
+            "--format" => {
+                let format = value()?;
+                options.format = ExportFormat::parse(&format).ok_or_else(|| format!("Unknown format {}", format))?;
+            }
+            "--columns" => {
+                options.columns = value()?
+                    .split(',')
+                    .map(|name| {
+                        Column::ALL
+                            .iter()
+                            .copied()
+                            .find(|c| c.header() == name.trim())
+                            .ok_or_else(|| format!("Unknown column {}", name))
+                    })
+                    .collect::<Result<_, _>>()?;
+            }
+            other => return Err(format!("Unknown option {}\n{}", other, USAGE)),
+        }
+    }
+    let language = idb::language_named(&language.ok_or_else(|| USAGE.to_string())?)?;
+    let out = out.unwrap_or_else(|| export::file_name(language, options.format));
+    let repos = NativeStore::open_default()
+        .records(language)
+        .map_err(|e| format!("Failed to read {}: {}", language, e))?;
+    let content = export::export(&repos, &options)?;
+    let done = export::deliver(&out, &content, options.format)?;
+    Ok(format!("{} ({} repositories)", done, repos.len()))
+}
+
+fn run_import(args: &[String]) -> Result<String, String> {
+    let (mut language, mut file) = (None, None);
+    let mut iter = args.iter();
+    while let Some(flag) = iter.next() {
+        let value = iter.next().cloned().ok_or_else(|| format!("{} needs a value\n{}", flag, USAGE));
+        match flag.as_str() {
+            "--language" => language = Some(value?),
+            "--file" => file = Some(value?),
+            other => return Err(format!("Unknown option {}\n{}", other, USAGE)),
+        }
+    }
+    let (Some(language), Some(file)) = (language, file) else {
+        return Err(USAGE.to_string());
+    };
+    let language = idb::language_named(&language)?;
+    let text = std::fs::read_to_string(&file).map_err(|e| format!("Failed to read {}: {}", file, e))?;
+    let parsed = import::parse(&text)?;
+    let store = NativeStore::open_default();
+    let existing = store
+        .records(language)
+        .map_err(|e| format!("Failed to read {}: {}", language, e))?
+        .into_iter()
+        .map(|r| (r.key(), r))
+        .collect();
+    let (writes, summary) = import::merge(&existing, parsed);
+    let entries = writes
+        .into_iter()
+        .map(|(key, repo)| Ok((key, serde_json::to_value(repo).map_err(|e| e.to_string())?)))
+        .collect::<Result<_, String>>()?;
+    store.put_entries(language, &entries).map_err(|e| format!("Failed to write {}: {}", language, e))?;
+    Ok(format!("Imported into {}: {}", language, summary.describe()))
+}
+
+fn run_backup(args: &[String]) -> Result<String, String> {
+    let out = match args {
+        [flag, path] if flag == "--out" => path,
+        _ => return Err(USAGE.to_string()),
+    };
+    let backup = backup::create(&NativeStore::open_default(), crate::utility::now_millis()).map_err(|e| e.to_string())?;
+    std::fs::write(out, backup.to_json()?).map_err(|e| format!("Failed to write {}: {}", out, e))?;
+    let records = backup.manifest.stores.iter().map(|s| s.records).sum::<usize>();
+    Ok(format!("Wrote {}: {} records from {} stores", out, records, backup.manifest.stores.len()))
+}
+
+fn run_restore(args: &[String]) -> Result<String, String> {
+    let (mut file, mut mode) = (None, RestoreMode::Merge);
+    let mut iter = args.iter();
+    while let Some(flag) = iter.next() {
+        match flag.as_str() {
+            "--file" => file = iter.next(),
+            "--replace" => mode = RestoreMode::Replace,
+            other => return Err(format!("Unknown option {}\n{}", other, USAGE)),
+        }
+    }
+    let file = file.ok_or_else(|| USAGE.to_string())?;
+    let text = std::fs::read_to_string(file).map_err(|e| format!("Failed to read {}: {}", file, e))?;
+    let backup = Backup::parse(&text)?;
+    let summary = backup::restore(&NativeStore::open_default(), &backup, mode).map_err(|e| e.to_string())?;
+    Ok(summary.describe())
+}
@@ -0,0 +1,192 @@
+// backup.rs - single-file, versioned archive of every Waffle store, with restore by merge or replace
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use crate::db::idb::{LANGUAGES, USER_STORES};
+
+pub const BACKUP_FORMAT: &str = "waffle-backup";
+/// Bump when the archive layout changes; restore refuses archives newer than this.
+pub const BACKUP_VERSION: u32 = 1;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct StoreManifest {
+    pub name: String,
+    pub records: usize,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Manifest {
+    pub format: String,
+    pub version: u32,
+    pub app_version: String,
+    pub created_at: f64,
+    pub stores: Vec<StoreManifest>,
+}
+
+/// key -> record, exactly as stored
+pub type Records = BTreeMap<String, serde_json::Value>;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Backup {
+    pub manifest: Manifest,
+    pub stores: BTreeMap<String, Records>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum RestoreMode {
+    /// Records in the archive overwrite ones with the same key; everything else stays
+    #[default]
+    Merge,
+    /// Every known store is emptied first, so the result matches the archive
+    Replace,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RestoreSummary {
+    pub stores: usize,
+    pub records: usize,
+    /// Stores in the archive this version does not know about
+    pub ignored: Vec<String>,
+}
+
+impl RestoreSummary {
+    pub fn describe(&self) -> String {
+        let mut out = format!("Restored {} records into {} stores", self.records, self.stores);
+        if !self.ignored.is_empty() {
+            out.push_str(&format!(" (ignored unknown stores: {})", self.ignored.join(", ")));
+        }
+        out
+    }
+}
+
+/// Every store a backup covers: the language caches and all user data.
+pub fn store_names() -> Vec<&'static str> {
+    LANGUAGES.iter().chain(USER_STORES).copied().collect()
+}
+
+impl Backup {
+    pub fn new(stores: BTreeMap<String, Records>, created_at: f64) -> Self {
+        let manifest = Manifest {
+            format: BACKUP_FORMAT.to_string(),
+            version: BACKUP_VERSION,
+            app_version: env!("CARGO_PKG_VERSION").to_string(),
+            created_at,
+            stores: stores
+                .iter()
+                .map(|(name, records)| StoreManifest {
+                    name: name.clone(),
+                    records: records.len(),
+                })
+                .collect(),
+        };
+        Self { manifest, stores }
+    }
+
+    pub fn to_json(&self) -> Result<String, String> {
+        serde_json::to_string(self).map_err(|e| e.to_string())
+    }
+
+    /// Parse and validate an archive: format, version and that the manifest matches the contents.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let backup = serde_json::from_str::<Backup>(text).map_err(|e| format!("Not a Waffle backup: {}", e))?;
+        let manifest = &backup.manifest;
+        if manifest.format != BACKUP_FORMAT {
+            return Err(format!("Not a Waffle backup (format {:?})", manifest.format));
+        }
+        if manifest.version > BACKUP_VERSION {
+            return Err(format!(
+                "Backup version {} is newer than this app supports ({}); update Waffle first",
+                manifest.version, BACKUP_VERSION
+            ));
+        }
+        for store in &manifest.stores {
+            let found = backup.stores.get(&store.name).map(|r| r.len()).unwrap_or(0);
+            if found != store.records {
+                return Err(format!("Backup is damaged: {} should have {} records, found {}", store.name, store.records, found));
+            }
+        }
+        Ok(backup)
+    }
+
+    /// Stores to write, split into known and unknown ones.
+    pub fn known_stores(&self) -> (Vec<(&'static str, &Records)>, Vec<String>) {
+        let known = store_names();
+        let mut write = Vec::new();
+        let mut ignored = Vec::new();
+        for (name, records) in &self.stores {
+            match known.iter().find(|k| **k == name) {
+                Some(k) => write.push((*k, records)),
+                None => ignored.push(name.clone()),
+            }
+        }
+        (write, ignored)
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+mod web {
+    use super::*;
+    use ::idb::{Database, Error};
+    use crate::db::idb;
+
+    pub async fn create(db: &Database, created_at: f64) -> Result<Backup, Error> {
+        let mut stores = BTreeMap::new();
+        for name in store_names() {
+            let entries = idb::get_all_entries(db, name).await?;
+            stores.insert(name.to_string(), entries.into_iter().collect());
+        }
+        Ok(Backup::new(stores, created_at))
+    }
+
+    pub async fn restore(db: &Database, backup: &Backup, mode: RestoreMode) -> Result<RestoreSummary, Error> {
+        let (write, ignored) = backup.known_stores();
+        let replace = mode == RestoreMode::Replace;
+        if replace {
+            for name in store_names().into_iter().filter(|n| write.iter().all(|(w, _)| w != n)) {
+                idb::put_entries(db, name, &[], true).await?;
+            }
+        }
+        let mut summary = RestoreSummary { ignored, ..Default::default() };
+        for (name, records) in write {
+            let entries = records.iter().map(|(k, v)| (k.clone(), v.clone())).collect::<Vec<_>>();
+            idb::put_entries(db, name, &entries, replace).await?;
+            summary.stores += 1;
+            summary.records += entries.len();
+        }
+        Ok(summary)
+    }
+}
+#[cfg(target_arch = "wasm32")]
+pub use web::{create, restore};
+
+#[cfg(not(target_arch = "wasm32"))]
+mod files {
+    use super::*;
+    use std::io;
+    use crate::db::native::NativeStore;
+
+    pub fn create(store: &NativeStore, created_at: f64) -> io::Result<Backup> {
+        let mut stores = BTreeMap::new();
+        for name in store_names() {
+            stores.insert(name.to_string(), store.entries(name)?);
+        }
+        Ok(Backup::new(stores, created_at))
+    }
+
+    pub fn restore(store: &NativeStore, backup: &Backup, mode: RestoreMode) -> io::Result<RestoreSummary> {
+        let (write, ignored) = backup.known_stores();
+        if mode == RestoreMode::Replace {
+            for name in store_names() {
+                store.clear(name)?;
+            }
+        }
+        let mut summary = RestoreSummary { ignored, ..Default::default() };
+        for (name, records) in write {
+            store.put_entries(name, records)?;
+            summary.stores += 1;
+            summary.records += records.len();
+        }
+        Ok(summary)
+    }
+}
+#[cfg(not(target_arch = "wasm32"))]
+pub use files::{create, restore};
@@ -0,0 +1,77 @@
+// cancel.rs - cancellation tokens for syncs and loads, so superseded work never lands
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use crate::db::http::Middleware;
+
+/// Error text for work that stopped because its token was cancelled; never shown to the user.
+pub const CANCELLED: &str = "Cancelled";
+
+/// Shared flag checked before every request, before every store write, and before results are published.
+/// In-flight requests can't be aborted, but whatever they return is thrown away.
+#[derive(Clone, Default, Debug)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::SeqCst)
+    }
+
+    /// Err(CANCELLED) once cancelled, for `?` between steps
+    pub fn check(&self) -> Result<(), String> {
+        if self.is_cancelled() { Err(CANCELLED.to_string()) } else { Ok(()) }
+    }
+
+    pub fn same_as(&self, other: &CancelToken) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+/// Requests made with a cancelled token fail without being sent; responses that arrive after are dropped.
+impl Middleware for CancelToken {
+    fn before(&self, _request: &mut ehttp::Request) -> Result<(), String> {
+        self.check()
+    }
+
+    fn after(&self, _request: &ehttp::Request, result: &mut ehttp::Result<ehttp::Response>) {
+        if self.is_cancelled() {
+            *result = Err(CANCELLED.to_string());
+        }
+    }
+}
+
+/// The one operation of a kind that may run at a time: starting another cancels it.
+#[derive(Clone, Default)]
+pub struct Slot(Arc<Mutex<Option<CancelToken>>>);
+
+impl Slot {
+    /// Cancel whatever was running and hand out a token for its replacement.
+    pub fn start(&self) -> CancelToken {
+        let token = CancelToken::default();
+        if let Some(previous) = self.0.lock().unwrap().replace(token.clone()) {
+            previous.cancel();
+        }
+        token
+    }
+
+    /// The operation holding `token` is done; a later one that already replaced it is left alone.
+    pub fn finish(&self, token: &CancelToken) {
+        let mut current = self.0.lock().unwrap();
+        if current.as_ref().is_some_and(|t| t.same_as(token)) {
+            *current = None;
+        }
+    }
+
+    pub fn cancel(&self) {
+        if let Some(token) = self.0.lock().unwrap().take() {
+            token.cancel();
+        }
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.0.lock().unwrap().is_some()
+    }
+}
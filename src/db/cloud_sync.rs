@@ -0,0 +1,450 @@
+// cloud_sync.rs - last-writer-wins sync of user data to Supabase through the JSRust bridge
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+use crate::db::idb;
+use crate::db::local::{self, DocStore, Document};
+use crate::utility::now_millis;
+
+const SYNC_STATE_KEY: &str = "state";
+
+/// One syncable item (a favorite, a collection, ...) as stored in the `waffle_sync` table.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct SyncRecord {
+    pub kind: String,
+    pub key: String,
+    #[serde(default)]
+    pub payload: serde_json::Value,
+    /// Last-modified time in milliseconds; the newest write wins on conflict.
+    pub updated_at: f64,
+    #[serde(default)]
+    pub deleted: bool,
+    /// Server-assigned change number (the table's `seq` column, taken from a sequence on every
+    /// insert and update). Pulls resume from it because client clocks can't order writes:
+    /// an edit made offline carries an `updated_at` older than rows others pushed meanwhile.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub seq: Option<u64>,
+}
+
+impl SyncRecord {
+    pub fn id(&self) -> String {
+        format!("{}:{}", self.kind, self.key)
+    }
+}
+
+/// Marker left behind when an item is removed, so the deletion can win over older copies.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Tombstone {
+    pub kind: String,
+    pub key: String,
+    pub deleted_at: f64,
+}
+
+impl Tombstone {
+    pub fn to_record(&self) -> SyncRecord {
+        SyncRecord {
+            kind: self.kind.clone(),
+            key: self.key.clone(),
+            payload: serde_json::Value::Null,
+            updated_at: self.deleted_at,
+            deleted: true,
+            seq: None,
+        }
+    }
+}
+
+/// Record a deletion, replacing any older tombstone for the same item.
+pub fn bury(removed: &mut Vec<Tombstone>, kind: &str, key: &str, deleted_at: f64) {
+    unbury(removed, kind, key);
+    removed.push(Tombstone {
+        kind: kind.to_string(),
+        key: key.to_string(),
+        deleted_at,
+    });
+}
+
+/// Forget a deletion because the item exists again.
+pub fn unbury(removed: &mut Vec<Tombstone>, kind: &str, key: &str) {
+    removed.retain(|t| t.kind != kind || t.key != key);
+}
+
+pub fn tombstone_at(removed: &[Tombstone], kind: &str, key: &str) -> Option<f64> {
+    removed.iter().find(|t| t.kind == kind && t.key == key).map(|t| t.deleted_at)
+}
+
+/// Whether a remote record should replace the local copy last modified at `local`.
+pub fn remote_wins(local: Option<f64>, remote: &SyncRecord) -> bool {
+    local.is_none_or(|local| remote.updated_at > local)
+}
+
+/// A persisted document whose items take part in cloud sync.
+pub trait SyncDocument: Document {
+    /// Record kinds this document owns.
+    const KINDS: &'static [&'static str];
+    /// Every local item, including tombstones for deleted ones.
+    fn to_sync_records(&self) -> Vec<SyncRecord>;
+    /// Merge records pulled from the server; the newer side wins per item.
+    fn apply_sync_records(&mut self, records: &[SyncRecord]);
+}
+
+impl<T: SyncDocument> SyncTarget for DocStore<T> {
+    fn kinds(&self) -> &'static [&'static str] {
+        T::KINDS
+    }
+
+    fn local_records(&self) -> Vec<SyncRecord> {
+        self.with(|doc| doc.to_sync_records())
+    }
+
+    fn apply_remote(&self, records: &[SyncRecord]) {
+        self.update_quiet(|doc| doc.apply_sync_records(records));
+    }
+
+    fn revision(&self) -> u64 {
+        DocStore::revision(self)
+    }
+}
+
+/// A local data set that takes part in cloud sync.
+pub trait SyncTarget {
+    /// Record kinds this target owns.
+    fn kinds(&self) -> &'static [&'static str];
+    /// Every local item, including tombstones for deleted ones.
+    fn local_records(&self) -> Vec<SyncRecord>;
+    /// Merge remote records into local state, keeping whichever side is newer.
+    fn apply_remote(&self, records: &[SyncRecord]);
+    /// Counter bumped on every local change, used to notice unsynced edits.
+    fn revision(&self) -> u64;
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SyncRequest {
+    /// Rows written after change number `after`
+    Pull { user_id: String, after: u64 },
+    Push { user_id: String, batch: u64, records: Vec<SyncRecord> },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SyncResponse {
+    Pulled { records: Vec<SyncRecord> },
+    Pushed { batch: u64 },
+    Failed { error: String },
+}
+
+/// Transport to the remote tables. Responses are collected and drained by `CloudSync::poll`.
+pub trait SyncBridge: Send + Sync {
+    fn send(&self, request: SyncRequest);
+    fn take_responses(&self) -> Vec<SyncResponse>;
+}
+
+/// Bridge to the Supabase worker through the `cloud_sync` JSRust action.
+pub struct JsBridge;
+
+impl SyncBridge for JsBridge {
+    fn send(&self, request: SyncRequest) {
+        match serde_json::to_string(&request) {
+            Ok(payload) => crate::erust::uiux::javascript_interop::send_payload_message("cloud_sync", &payload),
+            Err(e) => log::error!("[CloudSync] Failed to encode request: {}", e),
+        }
+    }
+
+    fn take_responses(&self) -> Vec<SyncResponse> {
+        crate::erust::uiux::javascript_interop::take_cloud_sync_responses()
+    }
+}
+
+#[derive(Default)]
+struct LocalBackend {
+    rows: BTreeMap<(String, String), SyncRecord>,
+    responses: Vec<SyncResponse>,
+    offline: bool,
+    last_seq: u64,
+}
+
+/// In-process stand-in for the Supabase tables, answering immediately.
+/// Used natively (where there is no JS bridge) and for exercising the sync flow.
+#[derive(Clone, Default)]
+pub struct LocalBridge {
+    backend: Arc<Mutex<LocalBackend>>,
+}
+
+impl LocalBridge {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Simulate losing the connection: every request fails until set back.
+    pub fn set_offline(&self, offline: bool) {
+        self.backend.lock().unwrap().offline = offline;
+    }
+
+    pub fn rows(&self, user_id: &str) -> Vec<SyncRecord> {
+        self.backend
+            .lock()
+            .unwrap()
+            .rows
+            .iter()
+            .filter(|((user, _), _)| user == user_id)
+            .map(|(_, record)| record.clone())
+            .collect()
+    }
+}
+
+impl SyncBridge for LocalBridge {
+    fn send(&self, request: SyncRequest) {
+        let mut backend = self.backend.lock().unwrap();
+        if backend.offline {
+            backend.responses.push(SyncResponse::Failed { error: "Offline".to_string() });
+            return;
+        }
+        let response = match request {
+            SyncRequest::Pull { user_id, after } => {
+                let mut records = backend
+                    .rows
+                    .iter()
+                    .filter(|((user, _), record)| *user == user_id && record.seq.unwrap_or_default() > after)
+                    .map(|(_, record)| record.clone())
+                    .collect::<Vec<_>>();
+                records.sort_by_key(|record| record.seq);
+                SyncResponse::Pulled { records }
+            }
+            SyncRequest::Push { user_id, batch, records } => {
+                for record in records {
+                    let row = (user_id.clone(), record.id());
+                    let newer = backend.rows.get(&row).is_none_or(|existing| record.updated_at >= existing.updated_at);
+                    if newer {
+                        backend.last_seq += 1;
+                        let seq = Some(backend.last_seq);
+                        backend.rows.insert(row, SyncRecord { seq, ..record });
+                    }
+                }
+                SyncResponse::Pushed { batch }
+            }
+        };
+        backend.responses.push(response);
+    }
+
+    fn take_responses(&self) -> Vec<SyncResponse> {
+        std::mem::take(&mut self.backend.lock().unwrap().responses)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SyncStatus {
+    SignedOut,
+    Syncing,
+    Synced { at: f64 },
+    Offline(String),
+}
+
+/// Persisted bookkeeping: which version of each item the server is known to hold.
+/// Anything whose local `updated_at` differs is queued for the next push.
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(default)]
+struct SyncState {
+    user_id: Option<String>,
+    synced: BTreeMap<String, f64>,
+    /// Highest server change number pulled so far
+    pulled_seq: u64,
+    next_batch: u64,
+}
+
+#[derive(Default)]
+struct Session {
+    signed_in: Option<String>,
+    in_flight: Option<(u64, Vec<SyncRecord>)>,
+    seen_revision: u64,
+}
+
+#[derive(Clone)]
+pub struct CloudSync {
+    bridge: Arc<dyn SyncBridge>,
+    state: Arc<Mutex<SyncState>>,
+    session: Arc<Mutex<Session>>,
+    status: Arc<Mutex<SyncStatus>>,
+}
+
+impl Default for CloudSync {
+    fn default() -> Self {
+        #[cfg(target_arch = "wasm32")]
+        let bridge: Arc<dyn SyncBridge> = Arc::new(JsBridge);
+        #[cfg(not(target_arch = "wasm32"))]
+        let bridge: Arc<dyn SyncBridge> = Arc::new(LocalBridge::new());
+        Self::with_bridge(bridge)
+    }
+}
+
+impl CloudSync {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_bridge(bridge: Arc<dyn SyncBridge>) -> Self {
+        Self {
+            bridge,
+            state: Arc::new(Mutex::new(SyncState::default())),
+            session: Arc::new(Mutex::new(Session::default())),
+            status: Arc::new(Mutex::new(SyncStatus::SignedOut)),
+        }
+    }
+
+    pub fn status(&self) -> SyncStatus {
+        self.status.lock().unwrap().clone()
+    }
+
+    /// Restore the persisted sync bookkeeping.
+    pub fn load(&self) {
+        let state = Arc::clone(&self.state);
+        local::load_doc::<SyncState, _>(idb::SYNC_STORE, SYNC_STATE_KEY, move |result| match result {
+            Ok(stored) => *state.lock().unwrap() = stored.unwrap_or_default(),
+            Err(e) => log::error!("[CloudSync] Failed to load sync state: {}", e),
+        });
+    }
+
+    fn save(&self) {
+        let snapshot = self.state.lock().unwrap().clone();
+        local::save_doc(idb::SYNC_STORE, SYNC_STATE_KEY, snapshot, |result| {
+            if let Err(e) = result {
+                log::error!("[CloudSync] Failed to save sync state: {}", e);
+            }
+        });
+    }
+
+    /// Number of local changes not yet confirmed by the server.
+    pub fn pending_count(&self, targets: &[&dyn SyncTarget]) -> usize {
+        let state = self.state.lock().unwrap();
+        targets
+            .iter()
+            .flat_map(|t| t.local_records())
+            .filter(|r| state.synced.get(&r.id()) != Some(&r.updated_at))
+            .count()
+    }
+
+    /// Start a full round trip for `user_id`: pull remote changes, merge, then push local ones.
+    pub fn sync(&self, user_id: &str) {
+        let after = {
+            let mut state = self.state.lock().unwrap();
+            if state.user_id.as_deref() != Some(user_id) {
+                // Different account: nothing is known to be on its server yet.
+                *state = SyncState {
+                    user_id: Some(user_id.to_string()),
+                    ..SyncState::default()
+                };
+            }
+            state.pulled_seq
+        };
+        self.session.lock().unwrap().signed_in = Some(user_id.to_string());
+        *self.status.lock().unwrap() = SyncStatus::Syncing;
+        self.bridge.send(SyncRequest::Pull {
+            user_id: user_id.to_string(),
+            after,
+        });
+    }
+
+    pub fn sign_out(&self) {
+        let mut session = self.session.lock().unwrap();
+        session.signed_in = None;
+        session.in_flight = None;
+        *self.status.lock().unwrap() = SyncStatus::SignedOut;
+    }
+
+    /// Send every queued local change, unless a push is already in flight.
+    pub fn push_pending(&self, targets: &[&dyn SyncTarget]) {
+        let mut session = self.session.lock().unwrap();
+        let Some(user_id) = session.signed_in.clone() else {
+            return;
+        };
+        if session.in_flight.is_some() {
+            return;
+        }
+        let mut state = self.state.lock().unwrap();
+        let pending = targets
+            .iter()
+            .flat_map(|t| t.local_records())
+            .filter(|r| state.synced.get(&r.id()) != Some(&r.updated_at))
+            .collect::<Vec<_>>();
+        if pending.is_empty() {
+            *self.status.lock().unwrap() = SyncStatus::Synced { at: now_millis() };
+            return;
+        }
+        let batch = state.next_batch;
+        state.next_batch += 1;
+        session.in_flight = Some((batch, pending.clone()));
+        drop(state);
+        drop(session);
+        *self.status.lock().unwrap() = SyncStatus::Syncing;
+        self.bridge.send(SyncRequest::Push {
+            user_id,
+            batch,
+            records: pending,
+        });
+    }
+
+    /// Drain bridge responses and push local edits made since the last call. Call once per frame.
+    pub fn poll(&self, targets: &[&dyn SyncTarget]) {
+        for response in self.bridge.take_responses() {
+            match response {
+                SyncResponse::Pulled { records } => {
+                    for target in targets {
+                        let owned = records
+                            .iter()
+                            .filter(|r| target.kinds().contains(&r.kind.as_str()))
+                            .cloned()
+                            .collect::<Vec<_>>();
+                        if !owned.is_empty() {
+                            target.apply_remote(&owned);
+                        }
+                    }
+                    {
+                        let mut state = self.state.lock().unwrap();
+                        for record in &records {
+                            state.synced.insert(record.id(), record.updated_at);
+                            state.pulled_seq = state.pulled_seq.max(record.seq.unwrap_or_default());
+                        }
+                    }
+                    self.save();
+                    self.push_pending(targets);
+                }
+                SyncResponse::Pushed { batch } => {
+                    let confirmed = {
+                        let mut session = self.session.lock().unwrap();
+                        match session.in_flight.take() {
+                            Some((in_flight, records)) if in_flight == batch => records,
+                            other => {
+                                session.in_flight = other;
+                                continue;
+                            }
+                        }
+                    };
+                    {
+                        let mut state = self.state.lock().unwrap();
+                        for record in &confirmed {
+                            state.synced.insert(record.id(), record.updated_at);
+                        }
+                    }
+                    self.save();
+                    // Pick up anything edited while the batch was in flight.
+                    self.push_pending(targets);
+                }
+                SyncResponse::Failed { error } => {
+                    log::error!("[CloudSync] Sync failed: {}", error);
+                    self.session.lock().unwrap().in_flight = None;
+                    *self.status.lock().unwrap() = SyncStatus::Offline(error);
+                }
+            }
+        }
+
+        let revision = targets.iter().map(|t| t.revision()).sum::<u64>();
+        let changed = {
+            let mut session = self.session.lock().unwrap();
+            let changed = session.seen_revision != revision;
+            session.seen_revision = revision;
+            changed
+        };
+        if changed {
+            self.push_pending(targets);
+        }
+    }
+}
@@ -0,0 +1,254 @@
+// export.rs - render repositories as CSV, JSON, NDJSON or a Markdown awesome list
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use crate::db::record::RepoRecord;
+use crate::db::license_policy;
+use crate::utility::format_timestamp;
+
+/// Heading for repositories without topics in the Markdown list
+pub const UNCATEGORIZED: &str = "Miscellaneous";
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ExportFormat {
+    #[default]
+    Csv,
+    Json,
+    Ndjson,
+    Markdown,
+}
+
+impl ExportFormat {
+    pub const ALL: &'static [ExportFormat] = &[ExportFormat::Csv, ExportFormat::Json, ExportFormat::Ndjson, ExportFormat::Markdown];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "CSV",
+            ExportFormat::Json => "JSON",
+            ExportFormat::Ndjson => "NDJSON",
+            ExportFormat::Markdown => "Markdown list",
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "csv",
+            ExportFormat::Json => "json",
+            ExportFormat::Ndjson => "ndjson",
+            ExportFormat::Markdown => "md",
+        }
+    }
+
+    pub fn mime(&self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "text/csv",
+            ExportFormat::Json => "application/json",
+            ExportFormat::Ndjson => "application/x-ndjson",
+            ExportFormat::Markdown => "text/markdown",
+        }
+    }
+
+    /// Accepts the extension or the label, e.g. `csv` or `markdown`.
+    pub fn parse(s: &str) -> Option<Self> {
+        let s = s.to_lowercase();
+        ExportFormat::ALL
+            .iter()
+            .copied()
+            .find(|f| f.extension() == s || f.label().to_lowercase().starts_with(&s))
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Column {
+    FullName,
+    Url,
+    Description,
+    Stars,
+    Forks,
+    OpenIssues,
+    Language,
+    License,
+    Topics,
+    CreatedAt,
+    PushedAt,
+    Archived,
+    Fork,
+}
+
+impl Column {
+    pub const ALL: &'static [Column] = &[
+        Column::FullName,
+        Column::Url,
+        Column::Description,
+        Column::Stars,
+        Column::Forks,
+        Column::OpenIssues,
+        Column::Language,
+        Column::License,
+        Column::Topics,
+        Column::CreatedAt,
+        Column::PushedAt,
+        Column::Archived,
+        Column::Fork,
+    ];
+    pub const DEFAULT: &'static [Column] = &[Column::FullName, Column::Url, Column::Description, Column::Stars, Column::License];
+
+    /// CSV header
+    pub fn header(&self) -> &'static str {
+        match self {
+            Column::FullName => "full_name",
+            Column::Url => "html_url",
+            Column::Description => "description",
+            Column::Stars => "stars",
+            Column::Forks => "forks",
+            Column::OpenIssues => "open_issues",
+            Column::Language => "language",
+            Column::License => "license",
+            Column::Topics => "topics",
+            Column::CreatedAt => "created_at",
+            Column::PushedAt => "pushed_at",
+            Column::Archived => "archived",
+            Column::Fork => "fork",
+        }
+    }
+
+    pub fn value(&self, repo: &RepoRecord) -> String {
+        let text = |s: &Option<String>| s.clone().unwrap_or_default();
+        let time = |t: Option<f64>| t.map(format_timestamp).unwrap_or_default();
+        match self {
+            Column::FullName => repo.full_name.clone(),
+            Column::Url => repo.html_url.clone(),
+            Column::Description => text(&repo.description),
+            Column::Stars => repo.stars.to_string(),
+            Column::Forks => repo.forks.to_string(),
+            Column::OpenIssues => repo.open_issues.to_string(),
+            Column::Language => text(&repo.language),
+            Column::License => license_policy::license_id(repo).unwrap_or_default(),
+            Column::Topics => repo.topics.join(";"),
+            Column::CreatedAt => time(repo.created_at),
+            Column::PushedAt => time(repo.pushed_at),
+            Column::Archived => repo.archived.to_string(),
+            Column::Fork => repo.fork.to_string(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct ExportOptions {
+    pub format: ExportFormat,
+    /// CSV only; the other formats carry every field
+    pub columns: Vec<Column>,
+    /// Title of the Markdown list
+    pub title: String,
+}
+
+impl Default for ExportOptions {
+    fn default() -> Self {
+        Self {
+            format: ExportFormat::Csv,
+            columns: Column::DEFAULT.to_vec(),
+            title: "Awesome repositories".to_string(),
+        }
+    }
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+pub fn to_csv(repos: &[RepoRecord], columns: &[Column]) -> String {
+    let mut out = columns.iter().map(|c| c.header()).collect::<Vec<_>>().join(",");
+    out.push('\n');
+    for repo in repos {
+        let row = columns.iter().map(|c| csv_field(&c.value(repo))).collect::<Vec<_>>();
+        out.push_str(&row.join(","));
+        out.push('\n');
+    }
+    out
+}
+
+pub fn to_ndjson(repos: &[RepoRecord]) -> Result<String, serde_json::Error> {
+    let mut out = String::new();
+    for repo in repos {
+        out.push_str(&serde_json::to_string(repo)?);
+        out.push('\n');
+    }
+    Ok(out)
+}
+
+/// Awesome-list style: one section per topic (a repository's first topic), best starred first.
+pub fn to_markdown(repos: &[RepoRecord], title: &str) -> String {
+    let mut groups: BTreeMap<String, Vec<&RepoRecord>> = BTreeMap::new();
+    for repo in repos {
+        let topic = repo.topics.first().cloned().unwrap_or_else(|| UNCATEGORIZED.to_string());
+        groups.entry(topic).or_default().push(repo);
+    }
+    let mut out = format!("# {}\n\n", title);
+    for topic in groups.keys() {
+        out.push_str(&format!("- [{}](#{})\n", topic, topic.to_lowercase().replace(' ', "-")));
+    }
+    for (topic, mut members) in groups {
+        members.sort_by_key(|r| std::cmp::Reverse(r.stars));
+        out.push_str(&format!("\n## {}\n\n", topic));
+        for repo in members {
+            let link = format!("[{}]({})", repo.full_name, repo.html_url);
+            let desc = repo.description.as_deref().map(|d| format!(" - {}", d.replace('\n', " "))).unwrap_or_default();
+            out.push_str(&format!("- {} ⭐ {}{}\n", link, repo.stars, desc));
+        }
+    }
+    out
+}
+
+pub fn export(repos: &[RepoRecord], options: &ExportOptions) -> Result<String, String> {
+    match options.format {
+        ExportFormat::Csv => Ok(to_csv(repos, &options.columns)),
+        ExportFormat::Json => serde_json::to_string_pretty(repos).map_err(|e| e.to_string()),
+        ExportFormat::Ndjson => to_ndjson(repos).map_err(|e| e.to_string()),
+        ExportFormat::Markdown => Ok(to_markdown(repos, &options.title)),
+    }
+}
+
+/// e.g. `waffle_rust.csv`
+pub fn file_name(scope: &str, format: ExportFormat) -> String {
+    let scope = scope
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_lowercase() } else { '_' })
+        .collect::<String>();
+    format!("waffle_{}.{}", scope, format.extension())
+}
+
+/// Hand the file to the user: a browser download on the web, a file at `path` natively.
+/// Returns where it went.
+pub fn deliver(path: &str, content: &str, format: ExportFormat) -> Result<String, String> {
+    #[cfg(target_arch = "wasm32")]
+    {
+        download(path, content, format.mime()).map_err(|e| format!("{:?}", e))?;
+        Ok(format!("Downloaded {}", path))
+    }
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        let _ = format;
+        std::fs::write(path, content).map_err(|e| format!("Failed to write {}: {}", path, e))?;
+        Ok(format!("Wrote {}", path))
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+fn download(file_name: &str, content: &str, mime: &str) -> Result<(), wasm_bindgen::JsValue> {
+    use wasm_bindgen::JsCast;
+    let parts = web_sys::js_sys::Array::of1(&wasm_bindgen::JsValue::from_str(content));
+    let options = web_sys::BlobPropertyBag::new();
+    options.set_type(mime);
+    let blob = web_sys::Blob::new_with_str_sequence_and_options(&parts, &options)?;
+    let url = web_sys::Url::create_object_url_with_blob(&blob)?;
+    let document = web_sys::window().and_then(|w| w.document()).ok_or("no document")?;
+    let anchor = document.create_element("a")?.dyn_into::<web_sys::HtmlAnchorElement>()?;
+    anchor.set_href(&url);
+    anchor.set_download(file_name);
+    anchor.click();
+    web_sys::Url::revoke_object_url(&url)
+}
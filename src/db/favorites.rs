@@ -0,0 +1,258 @@
+// favorites.rs - starred repositories and named collections, stored apart from the language caches
+use serde::{Deserialize, Serialize};
+use crate::db::cloud_sync::{self, SyncDocument, SyncRecord, Tombstone, remote_wins};
+use crate::db::record::RepoRecord;
+use crate::db::idb;
+use crate::db::local::{DocStore, Document};
+use crate::utility::now_millis;
+
+pub const FAVORITE_KIND: &str = "favorite";
+pub const COLLECTION_KIND: &str = "collection";
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Favorite {
+    /// `RepoRecord::key`: the full name for GitHub, host/path for other forges
+    pub full_name: String,
+    pub html_url: Option<String>,
+    pub description: Option<String>,
+    pub language: Option<String>,
+    pub starred_at: f64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Collection {
+    pub name: String,
+    /// Repository keys (see `Favorite::full_name`), in the order the user arranged them
+    pub repos: Vec<String>,
+    pub updated_at: f64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(default)]
+pub struct Favorites {
+    pub starred: Vec<Favorite>,
+    pub collections: Vec<Collection>,
+    /// Deleted favorites and collections, kept so the deletion syncs to other devices
+    pub removed: Vec<Tombstone>,
+}
+
+impl Favorites {
+    pub fn is_starred(&self, full_name: &str) -> bool {
+        self.starred.iter().any(|f| f.full_name == full_name)
+    }
+
+    pub fn star(&mut self, repo: &RepoRecord) {
+        let key = repo.key();
+        let full_name = key.as_str();
+        if self.is_starred(full_name) {
+            return;
+        }
+        self.unbury(FAVORITE_KIND, full_name);
+        self.starred.push(Favorite {
+            full_name: full_name.to_string(),
+            html_url: Some(repo.html_url.clone()),
+            description: repo.description.clone(),
+            language: repo.language.clone(),
+            starred_at: now_millis(),
+        });
+    }
+
+    pub fn unstar(&mut self, full_name: &str) {
+        if self.is_starred(full_name) {
+            self.starred.retain(|f| f.full_name != full_name);
+            self.bury(FAVORITE_KIND, full_name, now_millis());
+        }
+    }
+
+    /// Flip the starred flag for a repository, returning whether it is now starred.
+    pub fn toggle_star(&mut self, repo: &RepoRecord) -> bool {
+        let key = repo.key();
+        let full_name = key.as_str();
+        if self.is_starred(full_name) {
+            self.unstar(full_name);
+            false
+        } else {
+            self.star(repo);
+            true
+        }
+    }
+
+    pub fn collection(&self, name: &str) -> Option<&Collection> {
+        self.collections.iter().find(|c| c.name == name)
+    }
+
+    fn collection_mut(&mut self, name: &str) -> Option<&mut Collection> {
+        self.collections.iter_mut().find(|c| c.name == name)
+    }
+
+    /// Create an empty collection. Returns false if the name is blank or already taken.
+    pub fn create_collection(&mut self, name: &str) -> bool {
+        let name = name.trim();
+        if name.is_empty() || self.collection(name).is_some() {
+            return false;
+        }
+        self.unbury(COLLECTION_KIND, name);
+        self.collections.push(Collection {
+            name: name.to_string(),
+            repos: Vec::new(),
+            updated_at: now_millis(),
+        });
+        true
+    }
+
+    pub fn delete_collection(&mut self, name: &str) {
+        if self.collection(name).is_some() {
+            self.collections.retain(|c| c.name != name);
+            self.bury(COLLECTION_KIND, name, now_millis());
+        }
+    }
+
+    /// Append a repository to a collection. Returns false if it was already there.
+    pub fn add_to_collection(&mut self, name: &str, full_name: &str) -> bool {
+        match self.collection_mut(name) {
+            Some(collection) if !collection.repos.iter().any(|r| r == full_name) => {
+                collection.repos.push(full_name.to_string());
+                collection.updated_at = now_millis();
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Make a collection hold exactly `repos`, creating it if needed. Returns true if anything changed.
+    pub fn set_collection(&mut self, name: &str, repos: Vec<String>) -> bool {
+        if self.collection(name).is_none() && !self.create_collection(name) {
+            return false;
+        }
+        match self.collection_mut(name) {
+            Some(collection) if collection.repos != repos => {
+                collection.repos = repos;
+                collection.updated_at = now_millis();
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Take a repository out of a collection. Returns false if it wasn't there.
+    pub fn remove_from_collection(&mut self, name: &str, full_name: &str) -> bool {
+        match self.collection_mut(name) {
+            Some(collection) if collection.repos.iter().any(|r| r == full_name) => {
+                collection.repos.retain(|r| r != full_name);
+                collection.updated_at = now_millis();
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Move the entry at `from` to position `to` within a collection.
+    pub fn move_in_collection(&mut self, name: &str, from: usize, to: usize) {
+        if let Some(collection) = self.collection_mut(name) {
+            if from < collection.repos.len() && to < collection.repos.len() && from != to {
+                let entry = collection.repos.remove(from);
+                collection.repos.insert(to, entry);
+                collection.updated_at = now_millis();
+            }
+        }
+    }
+
+    pub fn collections_containing(&self, full_name: &str) -> Vec<&str> {
+        self.collections
+            .iter()
+            .filter(|c| c.repos.iter().any(|r| r == full_name))
+            .map(|c| c.name.as_str())
+            .collect()
+    }
+
+    fn bury(&mut self, kind: &str, key: &str, deleted_at: f64) {
+        cloud_sync::bury(&mut self.removed, kind, key, deleted_at);
+    }
+
+    fn unbury(&mut self, kind: &str, key: &str) {
+        cloud_sync::unbury(&mut self.removed, kind, key);
+    }
+
+    fn tombstone_at(&self, kind: &str, key: &str) -> Option<f64> {
+        cloud_sync::tombstone_at(&self.removed, kind, key)
+    }
+}
+
+impl Document for Favorites {
+    const STORE: &'static str = idb::FAVORITES_STORE;
+    const KEY: &'static str = "favorites";
+    const LABEL: &'static str = "favorites";
+}
+
+impl SyncDocument for Favorites {
+    const KINDS: &'static [&'static str] = &[FAVORITE_KIND, COLLECTION_KIND];
+
+    fn to_sync_records(&self) -> Vec<SyncRecord> {
+        let favorites = self.starred.iter().map(|f| SyncRecord {
+            kind: FAVORITE_KIND.to_string(),
+            key: f.full_name.clone(),
+            payload: serde_json::to_value(f).unwrap_or_default(),
+            updated_at: f.starred_at,
+            deleted: false,
+            seq: None,
+        });
+        let collections = self.collections.iter().map(|c| SyncRecord {
+            kind: COLLECTION_KIND.to_string(),
+            key: c.name.clone(),
+            payload: serde_json::to_value(c).unwrap_or_default(),
+            updated_at: c.updated_at,
+            deleted: false,
+            seq: None,
+        });
+        favorites
+            .chain(collections)
+            .chain(self.removed.iter().map(Tombstone::to_record))
+            .collect()
+    }
+
+    fn apply_sync_records(&mut self, records: &[SyncRecord]) {
+        for record in records {
+            let key = record.key.as_str();
+            match record.kind.as_str() {
+                FAVORITE_KIND => {
+                    let local = self
+                        .starred
+                        .iter()
+                        .find(|f| f.full_name == key)
+                        .map(|f| f.starred_at)
+                        .or_else(|| self.tombstone_at(FAVORITE_KIND, key));
+                    if !remote_wins(local, record) {
+                        continue;
+                    }
+                    self.starred.retain(|f| f.full_name != key);
+                    if record.deleted {
+                        self.bury(FAVORITE_KIND, key, record.updated_at);
+                    } else if let Ok(favorite) = serde_json::from_value::<Favorite>(record.payload.clone()) {
+                        self.unbury(FAVORITE_KIND, key);
+                        self.starred.push(favorite);
+                    }
+                }
+                COLLECTION_KIND => {
+                    let local = self
+                        .collection(key)
+                        .map(|c| c.updated_at)
+                        .or_else(|| self.tombstone_at(COLLECTION_KIND, key));
+                    if !remote_wins(local, record) {
+                        continue;
+                    }
+                    self.collections.retain(|c| c.name != key);
+                    if record.deleted {
+                        self.bury(COLLECTION_KIND, key, record.updated_at);
+                    } else if let Ok(collection) = serde_json::from_value::<Collection>(record.payload.clone()) {
+                        self.unbury(COLLECTION_KIND, key);
+                        self.collections.push(collection);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+/// Shared handle to the user's favorites, persisted to its own store on every change.
+pub type FavoritesStore = DocStore<Favorites>;
@@ -0,0 +1,192 @@
+// gitea.rs - the Gitea-compatible repository search (Gitea, Forgejo, Codeberg) as a repository source
+use serde::Deserialize;
+#[cfg(target_arch = "wasm32")]
+use ehttp::Mode;
+use crate::db::http::Credential;
+use crate::db::record::{LicenseInfo, RepoRecord, Visibility};
+use crate::db::source::{host_of, normalize_base_url, ForgeConfig, RepoSource, SourceKind};
+use crate::utility::{encode_query, parse_timestamp};
+
+pub const DEFAULT_BASE_URL: &str = "https://codeberg.org";
+/// Largest page most instances allow
+const PAGE_SIZE: usize = 50;
+/// Stop paging once this many repositories in the language are found, as many as one GitHub search returns
+pub const LANGUAGE_QUOTA: usize = 100;
+/// ...or after this many pages, on instances where the language is rare
+pub const MAX_PAGES: usize = 20;
+
+#[derive(Deserialize, Debug, Default)]
+#[serde(default)]
+struct GiteaOwner {
+    login: Option<String>,
+}
+
+#[derive(Deserialize, Debug, Default)]
+#[serde(default)]
+struct GiteaRepo {
+    id: u64,
+    owner: Option<GiteaOwner>,
+    name: Option<String>,
+    full_name: Option<String>,
+    description: Option<String>,
+    html_url: Option<String>,
+    website: Option<String>,
+    stars_count: u64,
+    forks_count: u64,
+    watchers_count: u64,
+    open_issues_count: u64,
+    /// Kilobytes
+    size: u64,
+    fork: bool,
+    archived: bool,
+    private: bool,
+    internal: bool,
+    template: bool,
+    has_issues: bool,
+    default_branch: Option<String>,
+    language: Option<String>,
+    topics: Option<Vec<String>>,
+    /// SPDX ids, on instances that detect licenses (Gitea 1.22+, recent Forgejo)
+    licenses: Option<Vec<String>>,
+    created_at: Option<String>,
+    updated_at: Option<String>,
+}
+
+#[derive(Deserialize, Debug, Default)]
+#[serde(default)]
+struct SearchResults {
+    ok: bool,
+    data: Vec<GiteaRepo>,
+}
+
+impl GiteaRepo {
+    fn into_record(self) -> Option<RepoRecord> {
+        let html_url = self.html_url?;
+        let full_name = self.full_name?;
+        let updated = self.updated_at.as_deref().and_then(parse_timestamp);
+        Some(RepoRecord {
+            id: self.id,
+            node_id: Some(format!("gitea:{}:{}", host_of(&html_url), self.id)),
+            name: self.name.unwrap_or_else(|| full_name.rsplit('/').next().unwrap_or_default().to_string()),
+            owner: self.owner.and_then(|o| o.login).unwrap_or_default(),
+            html_url,
+            description: self.description.filter(|d| !d.is_empty()),
+            homepage: self.website.filter(|w| !w.is_empty()),
+            language: self.language.filter(|l| !l.is_empty()),
+            topics: self.topics.unwrap_or_default(),
+            license: self.licenses.and_then(|l| l.into_iter().next()).map(|spdx| LicenseInfo {
+                key: Some(spdx.to_lowercase()),
+                name: spdx.clone(),
+                spdx_id: Some(spdx),
+            }),
+            stars: self.stars_count,
+            forks: self.forks_count,
+            watchers: self.watchers_count,
+            open_issues: self.open_issues_count,
+            size: self.size,
+            fork: self.fork,
+            archived: self.archived,
+            is_template: self.template,
+            has_issues: self.has_issues,
+            visibility: if self.private {
+                Visibility::Private
+            } else if self.internal {
+                Visibility::Internal
+            } else {
+                Visibility::Public
+            },
+            default_branch: self.default_branch,
+            created_at: self.created_at.as_deref().and_then(parse_timestamp),
+            updated_at: updated,
+            // The search API has no push time; updated_at moves on every push
+            pushed_at: updated,
+            source: SourceKind::Gitea,
+            full_name,
+            ..Default::default()
+        })
+    }
+}
+
+/// The search endpoint has no language filter, so the most starred repositories are fetched page
+/// by page and narrowed to the language here, until `LANGUAGE_QUOTA` match or the results run out.
+pub struct GiteaSource {
+    pub config: ForgeConfig,
+}
+
+impl GiteaSource {
+    fn page_request(&self, page: usize) -> ehttp::Request {
+        let mut url = format!(
+            "{}/api/v1/repos/search?sort=stars&order=desc&limit={}&page={}",
+            normalize_base_url(&self.config.base_url),
+            PAGE_SIZE,
+            page
+        );
+        let topic = self.config.topic.trim();
+        if !topic.is_empty() {
+            url.push_str(&format!("&q={}&topic=true", encode_query(topic)));
+        }
+        ehttp::Request {
+            method: String::from("GET"),
+            url,
+            body: vec![],
+            headers: ehttp::Headers::new(&[("Accept", "application/json")]),
+            #[cfg(target_arch = "wasm32")]
+            mode: Mode::Cors,
+        }
+    }
+}
+
+impl RepoSource for GiteaSource {
+    fn kind(&self) -> SourceKind {
+        SourceKind::Gitea
+    }
+
+    fn host(&self) -> String {
+        host_of(&normalize_base_url(&self.config.base_url)).to_string()
+    }
+
+    fn search_request(&self, _language: &str) -> Result<ehttp::Request, String> {
+        Ok(self.page_request(1))
+    }
+
+    fn next_request(&self, _language: &str, response: &ehttp::Response, collected: usize) -> Option<ehttp::Request> {
+        let page = response
+            .url
+            .split(['?', '&'])
+            .find_map(|param| param.strip_prefix("page="))
+            .and_then(|page| page.parse::<usize>().ok())
+            .unwrap_or(1);
+        if collected >= LANGUAGE_QUOTA || page >= MAX_PAGES {
+            return None;
+        }
+        // X-Total-Count when the instance sends it, otherwise a full page means there may be more
+        let more = match response.headers.get("x-total-count").and_then(|total| total.trim().parse::<usize>().ok()) {
+            Some(total) => page * PAGE_SIZE < total,
+            None => response.json::<SearchResults>().is_ok_and(|results| results.data.len() == PAGE_SIZE),
+        };
+        more.then(|| self.page_request(page + 1))
+    }
+
+    fn parse(&self, language: &str, response: &ehttp::Response) -> Result<Vec<RepoRecord>, String> {
+        let results = response.json::<SearchResults>().map_err(|e| format!("Failed to parse JSON: {}", e))?;
+        if !results.ok {
+            return Err("Repository search failed on the server".to_string());
+        }
+        Ok(results
+            .data
+            .into_iter()
+            .filter_map(GiteaRepo::into_record)
+            .filter(|r| r.language.as_deref().is_some_and(|l| l.eq_ignore_ascii_case(language)))
+            .collect())
+    }
+
+    /// Older instances don't report licenses
+    fn reports_licenses(&self) -> bool {
+        false
+    }
+
+    fn credential(&self) -> Option<Credential> {
+        let host = host_of(&normalize_base_url(&self.config.base_url)).to_string();
+        self.config.token().map(|token| Credential { host, header: "Authorization", value: format!("token {}", token) })
+    }
+}
@@ -3,21 +3,45 @@ use ehttp::{self};
 use ehttp::{Mode};
 use wasm_bindgen_futures::spawn_local;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
-use crate::db::idb;
+use crate::db::{graphql, idb, registry, sync, sync_report};
+use crate::db::http::{Auth, Credential, EhttpClient, Http, HttpClient, Logging, RateLimit, RateLimits, ResponseCache};
+use crate::db::cancel::{CancelToken, Slot, CANCELLED};
+use crate::db::sync::{RepoCache, SyncMeta};
+use crate::db::registry::Registry;
+use crate::db::graphql::SyncBackend;
+use crate::db::gitea::{self, GiteaSource};
+use crate::db::gitlab::{self, GitlabSource};
+use crate::db::license_policy::LicensePolicy;
+use crate::db::record::{self, RepoRecord};
+use crate::db::source::{host_of, normalize_base_url, ForgeConfig, RepoSource, SourceKind};
+use crate::db::sync_report::SyncReport;
+use crate::utility::{encode_query, now_millis};
 
-#[derive(Serialize, Deserialize, Clone, Debug)]
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
 pub struct Owner {
     pub site_admin: Option<bool>,
 }
 
-#[derive(Serialize, Deserialize, Clone, Debug)]
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
 pub struct License {
     pub node_id: Option<String>,
+    pub key: Option<String>,
     pub name: Option<String>,
+    pub spdx_id: Option<String>,
 }
 
-#[derive(Serialize, Deserialize, Clone, Debug)]
+/// Only filled in by the GraphQL backend; REST search does not return releases.
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct Release {
+    pub tag_name: Option<String>,
+    pub name: Option<String>,
+    pub published_at: Option<String>,
+    pub html_url: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
 pub struct Repository {
     pub id: Option<u64>,
     pub node_id: Option<String>,
@@ -99,6 +123,14 @@ pub struct Repository {
     pub watchers: Option<u64>,
     pub default_branch: Option<String>,
     pub score: Option<f64>,
+    // GraphQL backend only
+    pub latest_release: Option<Release>,
+    /// Languages by size, largest first
+    pub languages: Option<Vec<String>>,
+    /// Users who can be mentioned in the repository, the closest GraphQL has to contributors
+    pub contributors_count: Option<u64>,
+    /// README.md at the default branch, as markdown
+    pub readme: Option<String>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -108,12 +140,201 @@ pub struct SearchResponse {
     pub items: Vec<Repository>,
 }
 
+/// First page of the signed-in user's stars; later pages come from the Link header
+const STARRED_URL: &str = "https://api.github.com/user/starred?per_page=100";
+/// Stop paging after this many pages (10,000 stars)
+const MAX_STARRED_PAGES: usize = 100;
+
+/// GET against the GitHub REST API; the `Auth` middleware adds the token.
+fn github_request(url: &str) -> ehttp::Request {
+    ehttp::Request {
+        method: String::from("GET"),
+        url: url.to_string(),
+        body: vec![],
+        headers: ehttp::Headers::new(&[("User-Agent", "rust-egui-ehttp-app"), ("Accept", "application/vnd.github+json")]),
+        #[cfg(target_arch = "wasm32")]
+        mode: Mode::Cors,
+    }
+}
+
+/// The `rel="next"` URL of a GitHub Link header.
+fn next_page(link: &str) -> Option<String> {
+    link.split(',')
+        .find(|part| part.contains("rel=\"next\""))
+        .and_then(|part| Some(part[part.find('<')? + 1..part.find('>')?].to_string()))
+}
+
+/// Every repository starred by the token's user, following pages from `url`.
+pub fn fetch_starred<F>(http: Http, url: String, done: F)
+where
+    F: FnOnce(Result<Vec<Repository>, String>) + Send + 'static,
+{
+    fetch_starred_page(http, url, Vec::new(), 0, done);
+}
+
+/// Follow the starred pages, collecting repositories, then hand them to `done`.
+fn fetch_starred_page<F>(http: Http, url: String, mut acc: Vec<Repository>, page: usize, done: F)
+where
+    F: FnOnce(Result<Vec<Repository>, String>) + Send + 'static,
+{
+    http.clone().fetch(github_request(&url), move |result: ehttp::Result<ehttp::Response>| {
+        let response = match result {
+            Ok(response) => response,
+            Err(e) if e == CANCELLED => return done(Err(e)),
+            Err(e) => return done(Err(format!("Request failed: {}", e))),
+        };
+        if let Err(e) = sync::check_response(&response) {
+            return done(Err(e));
+        }
+        match response.json::<Vec<Repository>>() {
+            Ok(repos) => acc.extend(repos),
+            Err(e) => return done(Err(format!("Failed to parse JSON: {}", e))),
+        }
+        match response.headers.get("link").and_then(next_page) {
+            Some(next) if page + 1 < MAX_STARRED_PAGES => fetch_starred_page(http, next, acc, page + 1, done),
+            _ => done(Ok(acc)),
+        }
+    });
+}
+
+/// The README of `full_name` as markdown, trimmed to an excerpt; Ok(None) when the repository has none.
+pub fn fetch_readme<F>(http: &Http, api_base: &str, full_name: &str, done: F)
+where
+    F: FnOnce(Result<Option<String>, String>) + Send + 'static,
+{
+    let mut request = github_request(&format!("{}/repos/{}/readme", api_base, full_name));
+    request.headers = ehttp::Headers::new(&[("User-Agent", "rust-egui-ehttp-app"), ("Accept", "application/vnd.github.raw")]);
+    http.fetch(request, move |result: ehttp::Result<ehttp::Response>| {
+        let response = match result {
+            Ok(response) => response,
+            Err(e) if e == CANCELLED => return done(Err(e)),
+            Err(e) => return done(Err(format!("Request failed: {}", e))),
+        };
+        if response.status == 404 {
+            return done(Ok(None));
+        }
+        if let Err(e) = sync::check_response(&response) {
+            return done(Err(e));
+        }
+        let text = String::from_utf8_lossy(&response.bytes);
+        done(Ok(Some(record::readme_excerpt(&text)).filter(|t| !t.is_empty())))
+    });
+}
+
+pub const PUBLIC_HOST: &str = "github.com";
+
+/// REST API root for a GitHub web URL: api.github.com for github.com, `/api/v3` on Enterprise Server.
+pub fn api_base(base_url: &str) -> String {
+    let base = normalize_base_url(base_url);
+    if host_of(&base) == PUBLIC_HOST {
+        "https://api.github.com".to_string()
+    } else {
+        format!("{}/api/v3", base)
+    }
+}
+
+/// GraphQL endpoint for a GitHub web URL
+pub fn graphql_url(base_url: &str) -> String {
+    let base = normalize_base_url(base_url);
+    if host_of(&base) == PUBLIC_HOST {
+        "https://api.github.com/graphql".to_string()
+    } else {
+        format!("{}/api/graphql", base)
+    }
+}
+
+/// GitHub's repository search, over REST or GraphQL, on github.com or an Enterprise Server.
+pub struct GithubSource {
+    pub backend: SyncBackend,
+    /// Web URL of the host, e.g. https://github.com or https://github.example.com
+    pub base_url: String,
+    pub token: Option<String>,
+    /// Only repositories with this topic, when not blank
+    pub topic: String,
+}
+
+impl GithubSource {
+    fn query(&self, language: &str) -> String {
+        let topic = self.topic.trim();
+        if topic.is_empty() {
+            format!("language:{}", language)
+        } else {
+            format!("language:{} topic:{}", language, topic)
+        }
+    }
+}
+
+impl RepoSource for GithubSource {
+    fn kind(&self) -> SourceKind {
+        SourceKind::GitHub
+    }
+
+    fn host(&self) -> String {
+        host_of(&normalize_base_url(&self.base_url)).to_string()
+    }
+
+    fn search_request(&self, language: &str) -> Result<ehttp::Request, String> {
+        match (self.backend, self.token.as_deref()) {
+            (SyncBackend::GraphQl, Some(_)) => Ok(graphql::search_request(&graphql_url(&self.base_url), &self.query(language))),
+            (SyncBackend::GraphQl, None) => Err(format!("GraphQL sync needs a token for {}", host_of(&self.base_url))),
+            (SyncBackend::Rest, _) => Ok(github_request(&format!(
+                "{}/search/repositories?q={}&sort=stars&order=desc&per_page=100",
+                api_base(&self.base_url),
+                encode_query(&self.query(language))
+            ))),
+        }
+    }
+
+    fn parse(&self, _language: &str, response: &ehttp::Response) -> Result<Vec<RepoRecord>, String> {
+        let repos = match self.backend {
+            SyncBackend::GraphQl => graphql::parse_search(&response.bytes)?,
+            SyncBackend::Rest => {
+                response
+                    .json::<SearchResponse>()
+                    .map_err(|e| format!("Failed to parse JSON: {}", e))?
+                    .items
+            }
+        };
+        Ok(record::from_wire_all(repos))
+    }
+
+    fn credential(&self) -> Option<Credential> {
+        let token = self.token.as_deref()?;
+        Some(Credential::bearer(host_of(&api_base(&self.base_url)), token))
+    }
+}
+
 #[derive(Clone)]
 pub struct GithubDb {
-    repos: Arc<Mutex<Vec<Repository>>>,
+    repos: Arc<Mutex<Vec<RepoRecord>>>,
     error: Arc<Mutex<Option<String>>>,
     is_loading: Arc<Mutex<bool>>,
     pub language: Arc<Mutex<String>>, // Add language selection
+    completed_syncs: Arc<Mutex<u64>>, // Bumped once a sync's results are stored
+    last_report: Arc<Mutex<Option<SyncReport>>>, // Diff against the previous sync, until the UI takes it
+    license_policy: Arc<Mutex<LicensePolicy>>, // Decides which unlicensed repos are kept on sync
+    token: Arc<Mutex<Option<String>>>, // Personal access token for authenticated requests
+    starred: Arc<Mutex<Option<Vec<String>>>>, // full_names from the last stars sync, until the UI takes them
+    backend: Arc<Mutex<SyncBackend>>, // GitHub API used by sync_and_store
+    source: Arc<Mutex<SourceKind>>, // Forge sync_and_store pulls from
+    gitlab: Arc<Mutex<ForgeConfig>>,
+    gitea: Arc<Mutex<ForgeConfig>>,
+    enrich_progress: Arc<Mutex<Option<(usize, usize)>>>, // (looked up, total) while packages are being fetched
+    enterprise: Arc<Mutex<Option<ForgeConfig>>>, // GitHub Enterprise Server to sync instead of github.com
+    client: Arc<dyn HttpClient>, // Transport under the middleware; ehttp outside tests
+    rate_limits: RateLimits, // Last rate-limit headers per API host
+    responses: ResponseCache, // ETags and bodies for conditional requests
+    sync: Slot, // The running sync, cancelled by Cancel or a language switch
+    load: Slot, // The running load, superseded by the next one
+    enrich: Slot, // The running package lookup
+    readmes: Arc<Mutex<HashMap<String, Option<String>>>>, // README excerpts by record key; None while fetching or when missing
+}
+
+/// Record an error unless the operation it belongs to was cancelled.
+fn fail(error: &Arc<Mutex<Option<String>>>, cancel: &CancelToken, message: String) {
+    if !cancel.is_cancelled() {
+        *error.lock().unwrap() = Some(message);
+    }
 }
 
 impl GithubDb {
@@ -123,25 +344,140 @@ impl GithubDb {
             error: Arc::new(Mutex::new(None)),
             is_loading: Arc::new(Mutex::new(false)),
             language: Arc::new(Mutex::new("Rust".to_string())),
+            completed_syncs: Arc::new(Mutex::new(0)),
+            last_report: Arc::new(Mutex::new(None)),
+            license_policy: Arc::new(Mutex::new(LicensePolicy::default())),
+            token: Arc::new(Mutex::new(None)),
+            starred: Arc::new(Mutex::new(None)),
+            backend: Arc::new(Mutex::new(SyncBackend::Rest)),
+            source: Arc::new(Mutex::new(SourceKind::GitHub)),
+            gitlab: Arc::new(Mutex::new(ForgeConfig::new(gitlab::DEFAULT_BASE_URL))),
+            gitea: Arc::new(Mutex::new(ForgeConfig::new(gitea::DEFAULT_BASE_URL))),
+            enrich_progress: Arc::new(Mutex::new(None)),
+            enterprise: Arc::new(Mutex::new(None)),
+            client: Arc::new(EhttpClient),
+            rate_limits: RateLimits::default(),
+            responses: ResponseCache::default(),
+            sync: Slot::default(),
+            load: Slot::default(),
+            enrich: Slot::default(),
+            readmes: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// The network stack with every configured token, for the source syncs use right now.
+    fn http(&self) -> Http {
+        let mut credentials = self.current_source().credential().into_iter().collect::<Vec<_>>();
+        // Stars always come from github.com, whatever the source
+        if let Some(token) = self.token.lock().unwrap().as_deref() {
+            credentials.push(Credential::bearer(host_of(&api_base(PUBLIC_HOST)), token));
         }
+        Http::new(Arc::clone(&self.client))
+            .with(Auth(credentials))
+            .with(self.rate_limits.clone())
+            .with(self.responses.clone())
+            .with(Logging)
     }
 
+    /// Send requests through `client` instead of the network, e.g. from tests.
+    pub fn set_http_client(&mut self, client: Arc<dyn HttpClient>) {
+        self.client = client;
+    }
+
+    /// What the current source's API host last said about its rate limit.
+    pub fn rate_limit(&self) -> Option<RateLimit> {
+        let request = self.current_source().search_request(&self.get_language()).ok()?;
+        self.rate_limits.get(host_of(&request.url))
+    }
+
+    /// Switching language supersedes whatever was running for the old one.
     pub fn set_language(&self, lang: &str) {
-        *self.language.lock().unwrap() = lang.to_string();
+        let mut language = self.language.lock().unwrap();
+        if *language != lang {
+            self.cancel_sync();
+            self.cancel_enrich();
+        }
+        *language = lang.to_string();
     }
 
     pub fn get_language(&self) -> String {
         self.language.lock().unwrap().clone()
     }
 
+    pub fn set_license_policy(&self, policy: LicensePolicy) {
+        *self.license_policy.lock().unwrap() = policy;
+    }
+
+    /// Use a personal access token for GitHub requests; blank clears it.
+    pub fn set_token(&self, token: &str) {
+        let token = token.trim();
+        *self.token.lock().unwrap() = (!token.is_empty()).then(|| token.to_string());
+    }
+
+    pub fn set_backend(&self, backend: SyncBackend) {
+        *self.backend.lock().unwrap() = backend;
+    }
+
+    pub fn set_source(&self, source: SourceKind) {
+        *self.source.lock().unwrap() = source;
+    }
+
+    pub fn set_gitlab(&self, config: ForgeConfig) {
+        *self.gitlab.lock().unwrap() = config;
+    }
+
+    /// Sync GitHub from an Enterprise Server host with its own token; None means github.com.
+    pub fn set_github_host(&self, host: Option<ForgeConfig>) {
+        *self.enterprise.lock().unwrap() = host;
+    }
+
+    pub fn set_gitea(&self, config: ForgeConfig) {
+        *self.gitea.lock().unwrap() = config;
+    }
+
+    /// The source the next sync will use, configured as it is right now
+    fn current_source(&self) -> Box<dyn RepoSource> {
+        match *self.source.lock().unwrap() {
+            SourceKind::GitHub => {
+                let backend = *self.backend.lock().unwrap();
+                match self.enterprise.lock().unwrap().clone() {
+                    Some(host) => Box::new(GithubSource {
+                        backend,
+                        base_url: host.base_url,
+                        token: Some(host.token.trim().to_string()).filter(|t| !t.is_empty()),
+                        topic: host.topic,
+                    }),
+                    None => Box::new(GithubSource {
+                        backend,
+                        base_url: format!("https://{}", PUBLIC_HOST),
+                        token: self.token.lock().unwrap().clone(),
+                        topic: String::new(),
+                    }),
+                }
+            }
+            SourceKind::GitLab => Box::new(GitlabSource {
+                config: self.gitlab.lock().unwrap().clone(),
+            }),
+            SourceKind::Gitea => Box::new(GiteaSource {
+                config: self.gitea.lock().unwrap().clone(),
+            }),
+        }
+    }
+
+    pub fn has_token(&self) -> bool {
+        self.token.lock().unwrap().is_some()
+    }
+
+    /// Empty the current language's store and its sync metadata.
     pub fn clear_indexeddb(&self) {
         let language = self.get_language();
-        let key = format!("latest_{}", language.to_lowercase());
         let error = Arc::clone(&self.error);
+        // A sync still in flight would refill the store
+        self.cancel_sync();
         wasm_bindgen_futures::spawn_local(async move {
             match idb::open_waffle_db().await {
                 Ok(db) => {
-                    if let Err(e) = idb::delete_repo(&db, &language, &key).await {
+                    if let Err(e) = db.clear(&language).await {
                         *error.lock().unwrap() = Some(format!("Failed to clear IndexedDB: {}", e));
                     }
                 }
@@ -153,89 +489,241 @@ impl GithubDb {
     }
 
     pub fn sync_and_store(&self) {
+        if self.get_language() == idb::STARS_STORE {
+            self.sync_starred();
+            return;
+        }
+        let source = self.current_source();
+        let cancel = self.sync.start();
+        // The sync rewrites the store enrichment would write back to
+        self.cancel_enrich();
+        // Keep the stars current whenever github.com syncs
+        let public_github = source.kind() == SourceKind::GitHub && self.enterprise.lock().unwrap().is_none();
+        if public_github && self.has_token() {
+            self.fetch_stars(cancel.clone(), false);
+        }
         let repos = Arc::clone(&self.repos);
         let error = Arc::clone(&self.error);
         let is_loading = Arc::clone(&self.is_loading);
+        let completed_syncs = Arc::clone(&self.completed_syncs);
+        let last_report = Arc::clone(&self.last_report);
+        let slot = self.sync.clone();
+        let policy = self.license_policy.lock().unwrap().clone();
         let language = self.get_language();
+        let meta = SyncMeta::new(&language, source.as_ref());
         *is_loading.lock().unwrap() = true;
-        let request = ehttp::Request {
-            method: String::from("GET"),
-            url: format!("https://api.github.com/search/repositories?q=language:{}&sort=stars&order=desc&per_page=100", language),
-            body: vec![],
-            headers: ehttp::Headers::new(&[("User-Agent", "rust-egui-ehttp-app")]),
-            #[cfg(target_arch = "wasm32")]
-            mode:  Mode::Cors,
+        let http = self.http().with(cancel.clone());
+        sync::fetch(&http, source, language.clone(), policy, move |result| {
+            if !cancel.is_cancelled() {
+                *is_loading.lock().unwrap() = false;
+            }
+            let records = match result.and_then(|records| cancel.check().map(|_| records)) {
+                Ok(records) => records,
+                Err(e) => {
+                    fail(&error, &cancel, e);
+                    return slot.finish(&cancel);
+                }
+            };
+            *repos.lock().unwrap() = records.clone();
+            wasm_bindgen_futures::spawn_local(async move {
+                let db = match idb::open_waffle_db().await {
+                    Ok(db) => db,
+                    Err(e) => {
+                        fail(&error, &cancel, format!("Failed to open IndexedDB: {}", e));
+                        return slot.finish(&cancel);
+                    }
+                };
+                let synced = meta.clone();
+                let records = match sync::store(&db, meta, records, &cancel).await {
+                    Ok(records) => records,
+                    Err(e) => {
+                        fail(&error, &cancel, format!("Failed to store in IndexedDB: {}", e));
+                        return slot.finish(&cancel);
+                    }
+                };
+                match sync_report::record_report(&db, &synced, &records).await {
+                    Ok(report) => *last_report.lock().unwrap() = Some(report),
+                    Err(e) => fail(&error, &cancel, format!("Failed to build sync report: {}", e)),
+                }
+                *completed_syncs.lock().unwrap() += 1;
+                slot.finish(&cancel);
+            });
+        });
+    }
+
+    /// Replace the "My Stars" store with the signed-in user's starred repositories.
+    pub fn sync_starred(&self) {
+        if !self.has_token() {
+            *self.error.lock().unwrap() = Some("Add a GitHub token in the side panel to sync your stars".to_string());
+            return;
+        }
+        self.fetch_stars(self.sync.start(), true);
+    }
+
+    /// The stars half of a sync; `owns_sync` when it is the whole sync rather than riding along with a language.
+    fn fetch_stars(&self, cancel: CancelToken, owns_sync: bool) {
+        let error = Arc::clone(&self.error);
+        let completed_syncs = Arc::clone(&self.completed_syncs);
+        let starred = Arc::clone(&self.starred);
+        let slot = self.sync.clone();
+        let finish = move |cancel: &CancelToken| {
+            if owns_sync {
+                slot.finish(cancel);
+            }
         };
-        ehttp::fetch(request, move |result: ehttp::Result<ehttp::Response>| {
-            *is_loading.lock().unwrap() = false;
-            match result {
-                Ok(response) => {
-                    if response.ok {
-                        match response.json::<crate::db::github::SearchResponse>() {
-                            Ok(search_response) => {
-                                let filtered_repos = search_response
-                                    .items
-                                    .into_iter()
-                                    .filter(|repo| repo.license.is_some())
-                                    .collect::<Vec<_>>();
-                                let filtered_repos_for_mutex = filtered_repos.clone();
-                                let filtered_repos_for_async = filtered_repos_for_mutex.clone();
-                                *repos.lock().unwrap() = filtered_repos_for_mutex;
-                                wasm_bindgen_futures::spawn_local(async move {
-                                    match idb::open_waffle_db().await {
-                                        Ok(db) => {
-                                            // Store each repo individually
-                                            for repo in &filtered_repos_for_async {
-                                                let key = repo.full_name.clone().unwrap_or_else(|| repo.id.map(|id| id.to_string()).unwrap_or_else(|| "unknown".to_string()));
-                                                if let Err(e) = idb::add_repo(&db, &language, &key, repo).await {
-                                                    *error.lock().unwrap() = Some(format!("Failed to store repo {} in IndexedDB: {}", key, e));
-                                                }
-                                            }
-                                        }
-                                        Err(e) => {
-                                            *error.lock().unwrap() = Some(format!("Failed to open IndexedDB: {}", e));
-                                        }
-                                    }
-                                });
-                            }
-                            Err(e) => {
-                                *error.lock().unwrap() = Some(format!("Failed to parse JSON: {}", e));
-                            }
-                        }
-                    } else {
-                        *error.lock().unwrap() = Some(format!("HTTP Error: {} - {}", response.status, response.status_text));
+        fetch_starred(self.http().with(cancel.clone()), STARRED_URL.to_string(), move |result| {
+            let repos = match result {
+                Ok(repos) => record::from_wire_all(repos),
+                Err(e) => {
+                    fail(&error, &cancel, format!("Failed to fetch stars: {}", e));
+                    return finish(&cancel);
+                }
+            };
+            wasm_bindgen_futures::spawn_local(async move {
+                let db = match idb::open_waffle_db().await {
+                    Ok(db) => db,
+                    Err(e) => {
+                        fail(&error, &cancel, format!("Failed to open IndexedDB: {}", e));
+                        return finish(&cancel);
                     }
+                };
+                let names = repos.iter().map(|r| r.full_name.clone()).collect::<Vec<_>>();
+                let meta = SyncMeta {
+                    language: idb::STARS_STORE.to_string(),
+                    source: SourceKind::GitHub,
+                    host: PUBLIC_HOST.to_string(),
+                    synced_at: now_millis(),
+                    ..Default::default()
+                }
+                .written(&repos);
+                if cancel.is_cancelled() {
+                    return finish(&cancel);
+                }
+                // Unstarred on GitHub: the snapshot drops them from the store too
+                if let Err(e) = idb::put_snapshot(&db, idb::STARS_STORE, &repos, &meta).await {
+                    fail(&error, &cancel, format!("Failed to store stars: {}", e));
+                    return finish(&cancel);
                 }
+                *starred.lock().unwrap() = Some(names);
+                *completed_syncs.lock().unwrap() += 1;
+                finish(&cancel);
+            });
+        });
+    }
+
+    /// Whether a sync is still fetching or storing.
+    pub fn is_syncing(&self) -> bool {
+        self.sync.is_running()
+    }
+
+    /// Stop the running sync; whatever it has not stored yet is discarded.
+    pub fn cancel_sync(&self) {
+        self.sync.cancel();
+        *self.is_loading.lock().unwrap() = false;
+    }
+
+    /// Look up the current language's repositories in its package registry, skipping ones checked recently.
+    pub fn enrich_packages(&self) {
+        if self.enrich_progress().is_some() {
+            return;
+        }
+        let language = self.get_language();
+        let Some(registry) = Registry::for_language(&language) else {
+            *self.error.lock().unwrap() = Some(format!("{} has no package registry", language));
+            return;
+        };
+        let error = Arc::clone(&self.error);
+        let completed_syncs = Arc::clone(&self.completed_syncs);
+        let progress = Arc::clone(&self.enrich_progress);
+        let cancel = self.enrich.start();
+        let http = self.http().with(cancel.clone());
+        *progress.lock().unwrap() = Some((0, 0));
+        wasm_bindgen_futures::spawn_local(async move {
+            let now = now_millis();
+            let queue = match idb::open_waffle_db().await {
+                Ok(db) => idb::get_all_records(&db, &language).await.unwrap_or_default(),
                 Err(e) => {
-                    *error.lock().unwrap() = Some(format!("Request failed: {}", e));
+                    fail(&error, &cancel, format!("Failed to open IndexedDB: {}", e));
+                    *progress.lock().unwrap() = None;
+                    return;
                 }
+            };
+            let queue = queue.into_iter().filter(|r| registry::needs_refresh(r, now)).collect();
+            registry::enrich(http, cancel.clone(), registry, queue, now, progress, move |updated| {
+                wasm_bindgen_futures::spawn_local(async move {
+                    let result = match idb::open_waffle_db().await {
+                        Ok(db) => sync::store_packages(&db, &language, updated, &cancel).await,
+                        Err(e) => Err(e.to_string()),
+                    };
+                    if let Err(e) = result {
+                        fail(&error, &cancel, format!("Failed to store package data: {}", e));
+                    }
+                    *completed_syncs.lock().unwrap() += 1;
+                });
+            });
+        });
+    }
+
+    /// Stop looking up packages; lookups already made are thrown away.
+    pub fn cancel_enrich(&self) {
+        self.enrich.cancel();
+        *self.enrich_progress.lock().unwrap() = None;
+    }
+
+    /// (looked up, total) while packages are being fetched
+    pub fn enrich_progress(&self) -> Option<(usize, usize)> {
+        *self.enrich_progress.lock().unwrap()
+    }
+
+    /// README excerpt for the detail pane. GraphQL syncs store one on the record; other GitHub records
+    /// fetch it once per session, calling `ready` when it lands. Other forges have none.
+    pub fn readme(&self, repo: &RepoRecord, ready: impl FnOnce() + Send + 'static) -> Option<String> {
+        if repo.readme.is_some() || repo.source != SourceKind::GitHub {
+            return repo.readme.clone();
+        }
+        let key = repo.key();
+        if let Some(readme) = self.readmes.lock().unwrap().get(&key) {
+            return readme.clone();
+        }
+        self.readmes.lock().unwrap().insert(key.clone(), None);
+        let readmes = Arc::clone(&self.readmes);
+        let api = api_base(&format!("https://{}", repo.host()));
+        fetch_readme(&self.http(), &api, &repo.full_name, move |result| {
+            match result {
+                Ok(readme) => {
+                    readmes.lock().unwrap().insert(key, readme);
+                }
+                Err(e) => log::warn!("README for {} not fetched: {}", key, e),
             }
+            ready();
         });
+        None
+    }
+
+    /// full_names from the latest stars sync, once per sync.
+    pub fn take_starred(&self) -> Option<Vec<String>> {
+        self.starred.lock().unwrap().take()
     }
 
     pub fn load_from_indexeddb(&self) {
         let repos = Arc::clone(&self.repos);
         let error = Arc::clone(&self.error);
         let language = self.get_language();
-        let key = format!("latest_{}", language.to_lowercase());
+        // A load for a language the user has already left must not overwrite the newer one
+        let cancel = self.load.start();
+        let slot = self.load.clone();
         wasm_bindgen_futures::spawn_local(async move {
-            match idb::open_waffle_db().await {
-                Ok(db) => {
-                    match idb::get_repo::<Vec<crate::db::github::Repository>>(&db, &language, &key).await {
-                        Ok(Some(cached_repos)) => {
-                            *repos.lock().unwrap() = cached_repos;
-                        }
-                        Ok(None) => {
-                            *repos.lock().unwrap() = vec![];
-                        }
-                        Err(e) => {
-                            *error.lock().unwrap() = Some(format!("Failed to load from IndexedDB: {}", e));
-                        }
-                    }
-                }
-                Err(e) => {
-                    *error.lock().unwrap() = Some(format!("Failed to open IndexedDB: {}", e));
-                }
+            let loaded = match idb::open_waffle_db().await {
+                Ok(db) => db.records(&language).await.map_err(|e| format!("Failed to load from IndexedDB: {}", e)),
+                Err(e) => Err(format!("Failed to open IndexedDB: {}", e)),
+            };
+            slot.finish(&cancel);
+            if cancel.is_cancelled() {
+                return;
+            }
+            match loaded {
+                Ok(records) => *repos.lock().unwrap() = records,
+                Err(e) => *error.lock().unwrap() = Some(e),
             }
         });
     }
@@ -248,73 +736,61 @@ impl GithubDb {
         let repos = Arc::clone(&self.repos);
         let error = Arc::clone(&self.error);
         let is_loading = Arc::clone(&self.is_loading);
+        let policy = self.license_policy.lock().unwrap().clone();
+        let source = Box::new(GithubSource {
+            backend: SyncBackend::Rest,
+            base_url: format!("https://{}", PUBLIC_HOST),
+            token: None,
+            topic: String::new(),
+        });
+        let meta = SyncMeta::new("Rust", source.as_ref());
 
-        // Build request, with mode only on wasm32
-        let request = {
-                ehttp::Request {
-                    method: String::from("GET"),
-                    url: String::from("https://api.github.com/search/repositories?q=language:rust&sort=stars&order=desc&per_page=100"),
-                    body: vec![],
-                    headers: ehttp::Headers::new(&[("User-Agent", "rust-egui-ehttp-app")]),
-                    #[cfg(target_arch = "wasm32")]
-                    mode:  Mode::Cors,
+        sync::fetch(&self.http(), source, "Rust".to_string(), policy, move |result| {
+            *is_loading.lock().unwrap() = false;
+            let filtered_repos = match result {
+                Ok(records) => records,
+                Err(e) => {
+                    *error.lock().unwrap() = Some(e);
+                    return;
                 }
-        };
+            };
+            *repos.lock().unwrap() = filtered_repos.clone();
 
-        ehttp::fetch(request, move |result: ehttp::Result<ehttp::Response>| {
-            *is_loading.lock().unwrap() = false;
-            match result {
-                Ok(response) => {
-                    if response.ok {
-                        match response.json::<SearchResponse>() {
-                            Ok(search_response) => {
-                                let filtered_repos = search_response
-                                    .items
-                                    .into_iter()
-                                    .filter(|repo| repo.license.is_some())
-                                    .collect::<Vec<_>>();
-
-                                *repos.lock().unwrap() = filtered_repos.clone();
-
-                                // Store in IndexedDB asynchronously (WASM only)
-                                {
-                                    let filtered_repos_for_async = filtered_repos.clone();
-                                    let key = "latest_rust".to_string();
-                                    let language = "rust".to_string();
-                                    spawn_local(async move {
-                                        // Use idb.rs public API for storing repos
-                                        match idb::open_waffle_db().await {
-                                            Ok(db) => {
-                                                if let Err(e) = idb::add_repo(&db, &language, &key, &filtered_repos_for_async).await {
-                                                    *error.lock().unwrap() = Some(format!("Failed to store in IndexedDB: {}", e));
-                                                }
-                                            }
-                                            Err(e) => {
-                                                *error.lock().unwrap() = Some(format!("Failed to open IndexedDB: {}", e));
-                                            }
-                                        }
-                                    });
-                                }
-                            }
-                            Err(e) => {
-                                *error.lock().unwrap() = Some(format!("Failed to parse JSON: {}", e));
-                            }
+            // Store in IndexedDB asynchronously, laid out exactly as a sync would
+            spawn_local(async move {
+                match idb::open_waffle_db().await {
+                    Ok(db) => {
+                        if let Err(e) = sync::store(&db, meta, filtered_repos, &CancelToken::default()).await {
+                            *error.lock().unwrap() = Some(format!("Failed to store in IndexedDB: {}", e));
                         }
-                    } else {
-                        *error.lock().unwrap() = Some(format!("HTTP Error: {} - {}", response.status, response.status_text));
+                    }
+                    Err(e) => {
+                        *error.lock().unwrap() = Some(format!("Failed to open IndexedDB: {}", e));
                     }
                 }
-                Err(e) => {
-                    *error.lock().unwrap() = Some(format!("Request failed: {}", e));
-                }
-            }
+            });
         });
     }
 
-    pub fn get_repos(&self) -> Arc<Mutex<Vec<Repository>>> {
+    pub fn get_repos(&self) -> Arc<Mutex<Vec<RepoRecord>>> {
         Arc::clone(&self.repos)
     }
 
+    /// Number of syncs whose results have finished landing in IndexedDB, plus other cache writes.
+    pub fn completed_syncs(&self) -> u64 {
+        *self.completed_syncs.lock().unwrap()
+    }
+
+    /// Something other than a sync (e.g. an import) wrote to a language store; views reload as after a sync.
+    pub fn mark_cache_changed(&self) {
+        *self.completed_syncs.lock().unwrap() += 1;
+    }
+
+    /// The report for the most recent sync, once per sync.
+    pub fn take_report(&self) -> Option<SyncReport> {
+        self.last_report.lock().unwrap().take()
+    }
+
     pub fn is_loading(&self) -> bool {
         *self.is_loading.lock().unwrap()
     }
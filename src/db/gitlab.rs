@@ -0,0 +1,144 @@
+// gitlab.rs - GitLab projects API as a repository source, for gitlab.com or self-hosted instances
+use serde::Deserialize;
+#[cfg(target_arch = "wasm32")]
+use ehttp::Mode;
+use crate::db::http::Credential;
+use crate::db::record::{LicenseInfo, RepoRecord, Visibility};
+use crate::db::source::{host_of, normalize_base_url, ForgeConfig, RepoSource, SourceKind};
+use crate::utility::{encode_query, parse_timestamp};
+
+pub const DEFAULT_BASE_URL: &str = "https://gitlab.com";
+
+#[derive(Deserialize, Debug, Default)]
+#[serde(default)]
+struct Namespace {
+    full_path: Option<String>,
+}
+
+#[derive(Deserialize, Debug, Default)]
+#[serde(default)]
+struct GitlabLicense {
+    key: Option<String>,
+    name: Option<String>,
+}
+
+#[derive(Deserialize, Debug, Default)]
+#[serde(default)]
+struct Project {
+    id: u64,
+    name: Option<String>,
+    path_with_namespace: Option<String>,
+    namespace: Option<Namespace>,
+    web_url: Option<String>,
+    description: Option<String>,
+    star_count: u64,
+    forks_count: u64,
+    open_issues_count: Option<u64>,
+    topics: Vec<String>,
+    created_at: Option<String>,
+    last_activity_at: Option<String>,
+    archived: bool,
+    visibility: Option<String>,
+    default_branch: Option<String>,
+    issues_enabled: Option<bool>,
+    license: Option<GitlabLicense>,
+    /// Only present for authenticated requests
+    forked_from_project: Option<serde_json::Value>,
+}
+
+impl Project {
+    fn into_record(self) -> Option<RepoRecord> {
+        let full_name = self.path_with_namespace?;
+        let owner = self
+            .namespace
+            .and_then(|n| n.full_path)
+            .or_else(|| full_name.rsplit_once('/').map(|(owner, _)| owner.to_string()))
+            .unwrap_or_default();
+        let last_activity = self.last_activity_at.as_deref().and_then(parse_timestamp);
+        Some(RepoRecord {
+            id: self.id,
+            // Notes are keyed by node_id; project ids are stable across renames like GitHub's node ids
+            node_id: self.web_url.as_deref().map(|url| format!("gitlab:{}:{}", host_of(url), self.id)),
+            name: self.name.unwrap_or_else(|| full_name.rsplit('/').next().unwrap_or_default().to_string()),
+            owner,
+            html_url: self.web_url?,
+            description: self.description.filter(|d| !d.is_empty()),
+            topics: self.topics,
+            license: self.license.and_then(|l| {
+                Some(LicenseInfo {
+                    name: l.name.or_else(|| l.key.clone())?,
+                    spdx_id: None,
+                    key: l.key,
+                })
+            }),
+            stars: self.star_count,
+            forks: self.forks_count,
+            open_issues: self.open_issues_count.unwrap_or(0),
+            fork: self.forked_from_project.is_some(),
+            archived: self.archived,
+            has_issues: self.issues_enabled.unwrap_or(false),
+            visibility: match self.visibility.as_deref() {
+                Some("private") => Visibility::Private,
+                Some("internal") => Visibility::Internal,
+                _ => Visibility::Public,
+            },
+            default_branch: self.default_branch,
+            created_at: self.created_at.as_deref().and_then(parse_timestamp),
+            updated_at: last_activity,
+            pushed_at: last_activity,
+            source: SourceKind::GitLab,
+            full_name,
+            ..Default::default()
+        })
+    }
+}
+
+/// Token needs the read_api scope.
+pub struct GitlabSource {
+    pub config: ForgeConfig,
+}
+
+impl RepoSource for GitlabSource {
+    fn kind(&self) -> SourceKind {
+        SourceKind::GitLab
+    }
+
+    fn host(&self) -> String {
+        host_of(&normalize_base_url(&self.config.base_url)).to_string()
+    }
+
+    fn search_request(&self, language: &str) -> Result<ehttp::Request, String> {
+        let mut url = format!(
+            "{}/api/v4/projects?order_by=star_count&sort=desc&per_page=100&with_programming_language={}",
+            normalize_base_url(&self.config.base_url),
+            encode_query(language)
+        );
+        let topic = self.config.topic.trim();
+        if !topic.is_empty() {
+            url.push_str(&format!("&topic={}", encode_query(topic)));
+        }
+        Ok(ehttp::Request {
+            method: String::from("GET"),
+            url,
+            body: vec![],
+            headers: ehttp::Headers::new(&[("Accept", "application/json")]),
+            #[cfg(target_arch = "wasm32")]
+            mode: Mode::Cors,
+        })
+    }
+
+    fn parse(&self, _language: &str, response: &ehttp::Response) -> Result<Vec<RepoRecord>, String> {
+        let projects = response.json::<Vec<Project>>().map_err(|e| format!("Failed to parse JSON: {}", e))?;
+        Ok(projects.into_iter().filter_map(Project::into_record).collect())
+    }
+
+    /// The projects list only includes licenses on some instances
+    fn reports_licenses(&self) -> bool {
+        false
+    }
+
+    fn credential(&self) -> Option<Credential> {
+        let host = host_of(&normalize_base_url(&self.config.base_url)).to_string();
+        self.config.token().map(|token| Credential { host, header: "PRIVATE-TOKEN", value: token.to_string() })
+    }
+}
@@ -0,0 +1,242 @@
+// graphql.rs - GitHub GraphQL search that asks for exactly the fields we show, plus releases, languages and READMEs
+use serde::{Deserialize, Serialize};
+#[cfg(target_arch = "wasm32")]
+use ehttp::Mode;
+use crate::db::github::{License, Release, Repository};
+
+
+/// One page of the top repositories for a search query. Open issues and open pull requests are
+/// added together to match REST's `open_issues_count`; `mentionableUsers` stands in for the
+/// contributor count, which GraphQL doesn't expose.
+pub const SEARCH_QUERY: &str = r#"query($q: String!) {
+  search(query: $q, type: REPOSITORY, first: 100) {
+    nodes {
+      ... on Repository {
+        databaseId id name nameWithOwner url description homepageUrl
+        isFork isArchived isDisabled isPrivate isTemplate visibility
+        createdAt updatedAt pushedAt
+        stargazerCount forkCount
+        issues(states: OPEN) { totalCount }
+        pullRequests(states: OPEN) { totalCount }
+        mentionableUsers { totalCount }
+        hasIssuesEnabled
+        primaryLanguage { name }
+        licenseInfo { id key name spdxId }
+        repositoryTopics(first: 20) { nodes { topic { name } } }
+        defaultBranchRef { name }
+        latestRelease { tagName name publishedAt url }
+        languages(first: 10, orderBy: {field: SIZE, direction: DESC}) { nodes { name } }
+        readme: object(expression: "HEAD:README.md") { ... on Blob { text } }
+      }
+    }
+  }
+}"#;
+
+/// Which GitHub API a sync goes through.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SyncBackend {
+    #[default]
+    Rest,
+    /// Needs a token; GitHub does not allow anonymous GraphQL
+    GraphQl,
+}
+
+impl SyncBackend {
+    pub const ALL: &'static [SyncBackend] = &[SyncBackend::Rest, SyncBackend::GraphQl];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            SyncBackend::Rest => "REST",
+            SyncBackend::GraphQl => "GraphQL",
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Default)]
+#[serde(default)]
+struct Count {
+    #[serde(rename = "totalCount")]
+    total_count: u64,
+}
+
+#[derive(Deserialize, Debug, Default)]
+#[serde(default)]
+struct Named {
+    name: String,
+}
+
+#[derive(Deserialize, Debug, Default)]
+#[serde(default)]
+struct Nodes<T> {
+    nodes: Vec<T>,
+}
+
+#[derive(Deserialize, Debug, Default)]
+#[serde(default)]
+struct TopicNode {
+    topic: Named,
+}
+
+#[derive(Deserialize, Debug, Default)]
+#[serde(default, rename_all = "camelCase")]
+struct GqlLicense {
+    id: Option<String>,
+    key: Option<String>,
+    name: Option<String>,
+    spdx_id: Option<String>,
+}
+
+#[derive(Deserialize, Debug, Default)]
+#[serde(default, rename_all = "camelCase")]
+struct GqlRelease {
+    tag_name: Option<String>,
+    name: Option<String>,
+    published_at: Option<String>,
+    url: Option<String>,
+}
+
+#[derive(Deserialize, Debug, Default)]
+#[serde(default)]
+struct Blob {
+    text: Option<String>,
+}
+
+#[derive(Deserialize, Debug, Default)]
+#[serde(default, rename_all = "camelCase")]
+struct GqlRepository {
+    database_id: Option<u64>,
+    id: Option<String>,
+    name: Option<String>,
+    name_with_owner: Option<String>,
+    url: Option<String>,
+    description: Option<String>,
+    homepage_url: Option<String>,
+    is_fork: bool,
+    is_archived: bool,
+    is_disabled: bool,
+    is_private: bool,
+    is_template: bool,
+    visibility: Option<String>,
+    created_at: Option<String>,
+    updated_at: Option<String>,
+    pushed_at: Option<String>,
+    stargazer_count: u64,
+    fork_count: u64,
+    issues: Count,
+    pull_requests: Count,
+    mentionable_users: Count,
+    has_issues_enabled: bool,
+    primary_language: Option<Named>,
+    license_info: Option<GqlLicense>,
+    repository_topics: Nodes<TopicNode>,
+    default_branch_ref: Option<Named>,
+    latest_release: Option<GqlRelease>,
+    languages: Nodes<Named>,
+    readme: Option<Blob>,
+}
+
+#[derive(Deserialize, Debug, Default)]
+#[serde(default)]
+struct SearchData {
+    search: Nodes<GqlRepository>,
+}
+
+#[derive(Deserialize, Debug, Default)]
+#[serde(default)]
+struct GqlError {
+    message: String,
+}
+
+#[derive(Deserialize, Debug, Default)]
+#[serde(default)]
+struct GqlResponse {
+    data: Option<SearchData>,
+    errors: Vec<GqlError>,
+}
+
+impl GqlRepository {
+    fn into_repository(self) -> Repository {
+        let topics = self.repository_topics.nodes.into_iter().map(|n| n.topic.name).collect();
+        let open_issues = self.issues.total_count + self.pull_requests.total_count;
+        Repository {
+            id: self.database_id,
+            node_id: self.id,
+            name: self.name,
+            full_name: self.name_with_owner,
+            private: Some(self.is_private),
+            html_url: self.url,
+            description: self.description,
+            fork: Some(self.is_fork),
+            created_at: self.created_at,
+            updated_at: self.updated_at,
+            pushed_at: self.pushed_at,
+            homepage: self.homepage_url,
+            stargazers_count: Some(self.stargazer_count),
+            language: self.primary_language.map(|l| l.name),
+            has_issues: Some(self.has_issues_enabled),
+            forks_count: Some(self.fork_count),
+            archived: Some(self.is_archived),
+            disabled: Some(self.is_disabled),
+            open_issues_count: Some(open_issues),
+            license: self.license_info.map(|l| License {
+                node_id: l.id,
+                key: l.key,
+                name: l.name,
+                spdx_id: l.spdx_id,
+            }),
+            is_template: Some(self.is_template),
+            topics: Some(topics),
+            visibility: self.visibility.map(|v| v.to_lowercase()),
+            forks: Some(self.fork_count),
+            open_issues: Some(open_issues),
+            default_branch: self.default_branch_ref.map(|b| b.name),
+            latest_release: self.latest_release.map(|r| Release {
+                tag_name: r.tag_name,
+                name: r.name,
+                published_at: r.published_at,
+                html_url: r.url,
+            }),
+            languages: Some(self.languages.nodes.into_iter().map(|l| l.name).collect()),
+            contributors_count: Some(self.mentionable_users.total_count),
+            readme: self.readme.and_then(|b| b.text),
+            ..Default::default()
+        }
+    }
+}
+
+/// POST the top repositories for a search `query` (e.g. `language:rust`) to `url`, mirroring the REST search.
+pub fn search_request(url: &str, query: &str) -> ehttp::Request {
+    let body = serde_json::json!({
+        "query": SEARCH_QUERY,
+        "variables": { "q": format!("{} sort:stars-desc", query) },
+    });
+    ehttp::Request {
+        method: String::from("POST"),
+        url: url.to_string(),
+        body: serde_json::to_vec(&body).unwrap_or_default(),
+        headers: ehttp::Headers::new(&[
+            ("User-Agent", "rust-egui-ehttp-app"),
+            ("Content-Type", "application/json"),
+        ]),
+        #[cfg(target_arch = "wasm32")]
+        mode: Mode::Cors,
+    }
+}
+
+/// Map a GraphQL search response onto `Repository`; GraphQL errors come back as Err.
+pub fn parse_search(bytes: &[u8]) -> Result<Vec<Repository>, String> {
+    let response = serde_json::from_slice::<GqlResponse>(bytes).map_err(|e| format!("Failed to parse JSON: {}", e))?;
+    if !response.errors.is_empty() {
+        let messages = response.errors.into_iter().map(|e| e.message).collect::<Vec<_>>();
+        return Err(format!("GraphQL error: {}", messages.join("; ")));
+    }
+    let data = response.data.ok_or("GraphQL response had no data")?;
+    Ok(data
+        .search
+        .nodes
+        .into_iter()
+        // Non-repository nodes decode as empty objects
+        .filter(|r| r.name_with_owner.is_some())
+        .map(GqlRepository::into_repository)
+        .collect())
+}
@@ -0,0 +1,128 @@
+// health.rs - a 0-100 health score per repository from the fields we already cache
+use serde::{Deserialize, Serialize};
+use crate::db::record::RepoRecord;
+use crate::db::history::DAY_MS;
+
+/// Pushes within this many days count as fully active
+pub const FRESH_DAYS: f64 = 30.0;
+/// ...and the recency factor falls to zero by this age.
+pub const STALE_DAYS: f64 = 365.0;
+/// Open issues per star at which the issue factor bottoms out
+pub const ISSUE_RATIO_LIMIT: f64 = 0.1;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Factor {
+    Recency,
+    Issues,
+    Maintained,
+    License,
+    IssueTracker,
+    Original,
+}
+
+impl Factor {
+    pub const ALL: &'static [Factor] = &[
+        Factor::Recency,
+        Factor::Issues,
+        Factor::Maintained,
+        Factor::License,
+        Factor::IssueTracker,
+        Factor::Original,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Factor::Recency => "Recent pushes",
+            Factor::Issues => "Few open issues per star",
+            Factor::Maintained => "Not archived or disabled",
+            Factor::License => "Has a license",
+            Factor::IssueTracker => "Issues enabled",
+            Factor::Original => "Not a fork",
+        }
+    }
+
+    /// How well `repo` does on this factor, from 0 to 1.
+    pub fn value(&self, repo: &RepoRecord, now: f64) -> f64 {
+        let flag = |b: bool| if b { 1.0 } else { 0.0 };
+        match self {
+            Factor::Recency => repo
+                .pushed_at
+                .map(|pushed| {
+                    let age = (now - pushed) / DAY_MS;
+                    (1.0 - (age - FRESH_DAYS) / (STALE_DAYS - FRESH_DAYS)).clamp(0.0, 1.0)
+                })
+                .unwrap_or(0.0),
+            Factor::Issues => {
+                let stars = repo.stars.max(1) as f64;
+                let ratio = repo.open_issues as f64 / stars;
+                1.0 - (ratio / ISSUE_RATIO_LIMIT).min(1.0)
+            }
+            Factor::Maintained => flag(!repo.archived && !repo.disabled),
+            Factor::License => flag(repo.license.is_some()),
+            Factor::IssueTracker => flag(repo.has_issues),
+            Factor::Original => flag(!repo.fork),
+        }
+    }
+}
+
+/// Relative importance of each factor; only the ratios matter.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(default)]
+pub struct HealthWeights {
+    pub recency: f64,
+    pub issues: f64,
+    pub maintained: f64,
+    pub license: f64,
+    pub issue_tracker: f64,
+    pub original: f64,
+}
+
+impl Default for HealthWeights {
+    fn default() -> Self {
+        Self {
+            recency: 30.0,
+            issues: 20.0,
+            maintained: 20.0,
+            license: 10.0,
+            issue_tracker: 10.0,
+            original: 10.0,
+        }
+    }
+}
+
+impl HealthWeights {
+    pub fn weight_mut(&mut self, factor: Factor) -> &mut f64 {
+        match factor {
+            Factor::Recency => &mut self.recency,
+            Factor::Issues => &mut self.issues,
+            Factor::Maintained => &mut self.maintained,
+            Factor::License => &mut self.license,
+            Factor::IssueTracker => &mut self.issue_tracker,
+            Factor::Original => &mut self.original,
+        }
+    }
+
+    pub fn weight(&self, factor: Factor) -> f64 {
+        match factor {
+            Factor::Recency => self.recency,
+            Factor::Issues => self.issues,
+            Factor::Maintained => self.maintained,
+            Factor::License => self.license,
+            Factor::IssueTracker => self.issue_tracker,
+            Factor::Original => self.original,
+        }
+    }
+
+    /// Weighted average of the factors, scaled to 0-100. All-zero weights score 0.
+    pub fn score(&self, repo: &RepoRecord, now: f64) -> f64 {
+        let total = Factor::ALL.iter().map(|f| self.weight(*f).max(0.0)).sum::<f64>();
+        if total <= 0.0 {
+            return 0.0;
+        }
+        let weighted = Factor::ALL
+            .iter()
+            .map(|f| self.weight(*f).max(0.0) * f.value(repo, now))
+            .sum::<f64>();
+        100.0 * weighted / total
+    }
+}
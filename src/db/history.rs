@@ -0,0 +1,148 @@
+// history.rs - compact per-sync snapshots of each repository's numbers, for trends over time
+use ::idb::{Database, Error};
+use serde::{Deserialize, Serialize};
+use crate::db::record::RepoRecord;
+use crate::db::idb;
+
+pub const DAY_MS: f64 = 24.0 * 60.0 * 60.0 * 1000.0;
+/// Samples kept per repository; at one sync a day this is about a year of history.
+pub const MAX_SAMPLES: usize = 400;
+
+/// One sync's numbers for a repository, stored as a bare array
+/// `[time_ms, stars, forks, open_issues, watchers]` to keep the store small.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct Sample(pub f64, pub u64, pub u64, pub u64, pub u64);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Metric {
+    Stars,
+    Forks,
+    OpenIssues,
+    Watchers,
+}
+
+impl Metric {
+    /// Metrics offered in the detail pane chart.
+    pub const CHARTED: &'static [Metric] = &[Metric::Stars, Metric::Forks, Metric::OpenIssues];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Metric::Stars => "Stars",
+            Metric::Forks => "Forks",
+            Metric::OpenIssues => "Open issues",
+            Metric::Watchers => "Watchers",
+        }
+    }
+}
+
+impl Sample {
+    pub fn from_repo(repo: &RepoRecord, at: f64) -> Self {
+        Sample(
+            at,
+            repo.stars,
+            repo.forks,
+            repo.open_issues,
+            repo.watchers,
+        )
+    }
+
+    pub fn at(&self) -> f64 {
+        self.0
+    }
+
+    pub fn get(&self, metric: Metric) -> u64 {
+        match metric {
+            Metric::Stars => self.1,
+            Metric::Forks => self.2,
+            Metric::OpenIssues => self.3,
+            Metric::Watchers => self.4,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct RepoHistory {
+    pub full_name: String,
+    /// Oldest first
+    pub samples: Vec<Sample>,
+}
+
+impl RepoHistory {
+    pub fn new(full_name: &str) -> Self {
+        Self {
+            full_name: full_name.to_string(),
+            samples: Vec::new(),
+        }
+    }
+
+    /// Append a sample, keeping the list ordered and bounded.
+    pub fn push(&mut self, sample: Sample) {
+        let index = self.samples.partition_point(|s| s.at() <= sample.at());
+        self.samples.insert(index, sample);
+        if self.samples.len() > MAX_SAMPLES {
+            let excess = self.samples.len() - MAX_SAMPLES;
+            self.samples.drain(..excess);
+        }
+    }
+
+    pub fn latest(&self) -> Option<Sample> {
+        self.samples.last().copied()
+    }
+
+    /// The newest sample taken at or before `at`, falling back to the oldest one.
+    pub fn at_or_before(&self, at: f64) -> Option<Sample> {
+        let index = self.samples.partition_point(|s| s.at() <= at);
+        if index == 0 {
+            self.samples.first().copied()
+        } else {
+            Some(self.samples[index - 1])
+        }
+    }
+
+    /// Change in `metric` between `since` and the latest sample. None until there are two samples.
+    pub fn change_since(&self, metric: Metric, since: f64) -> Option<i64> {
+        if self.samples.len() < 2 {
+            return None;
+        }
+        let latest = self.latest()?;
+        let base = self.at_or_before(since)?;
+        Some(latest.get(metric) as i64 - base.get(metric) as i64)
+    }
+
+    /// e.g. "stars gained this week": `stars_gained(now, 7.0)`
+    pub fn stars_gained(&self, now: f64, days: f64) -> Option<i64> {
+        self.change_since(Metric::Stars, now - days * DAY_MS)
+    }
+
+    /// Gain per day over the window, using the actual time span covered by samples.
+    pub fn velocity(&self, metric: Metric, now: f64, days: f64) -> Option<f64> {
+        let latest = self.latest()?;
+        let base = self.at_or_before(now - days * DAY_MS)?;
+        let span_days = (latest.at() - base.at()) / DAY_MS;
+        if span_days <= 0.0 {
+            return None;
+        }
+        Some((latest.get(metric) as f64 - base.get(metric) as f64) / span_days)
+    }
+
+    /// (time_ms, value) points for plotting.
+    pub fn series(&self, metric: Metric) -> Vec<(f64, f64)> {
+        self.samples.iter().map(|s| (s.at(), s.get(metric) as f64)).collect()
+    }
+}
+
+/// `stored` (or a new history for `repo`) with this sync's sample appended. Syncs write it in the
+/// same transaction as their records, see `RepoCache::put_snapshot`.
+pub fn with_sample(stored: Option<RepoHistory>, repo: &RepoRecord, at: f64) -> RepoHistory {
+    let mut history = stored.unwrap_or_else(|| RepoHistory::new(&repo.key()));
+    history.push(Sample::from_repo(repo, at));
+    history
+}
+
+pub async fn load_history(db: &Database, full_name: &str) -> Result<Option<RepoHistory>, Error> {
+    idb::get_repo::<RepoHistory>(db, idb::HISTORY_STORE, full_name).await
+}
+
+pub async fn load_all_histories(db: &Database) -> Result<Vec<RepoHistory>, Error> {
+    idb::get_all_repos::<RepoHistory>(db, idb::HISTORY_STORE).await
+}
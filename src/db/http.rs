@@ -0,0 +1,219 @@
+// http.rs - the one network stack every request goes through: a swappable transport plus middleware
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use crate::db::source::host_of;
+use crate::utility::{format_timestamp, now_millis};
+
+pub type Callback = Box<dyn FnOnce(ehttp::Result<ehttp::Response>) + Send>;
+
+/// Sends a request and calls back with the response. `EhttpClient` in the app; tests swap in their own.
+pub trait HttpClient: Send + Sync {
+    fn fetch(&self, request: ehttp::Request, done: Callback);
+}
+
+/// The real network, through ehttp (browser fetch on the web, ureq natively).
+pub struct EhttpClient;
+
+impl HttpClient for EhttpClient {
+    fn fetch(&self, request: ehttp::Request, done: Callback) {
+        ehttp::fetch(request, done);
+    }
+}
+
+/// Runs around every request. `before` sees requests in the order middleware was added
+/// and may refuse to send one; `after` sees results in reverse order and may rewrite them.
+pub trait Middleware: Send + Sync {
+    fn before(&self, _request: &mut ehttp::Request) -> Result<(), String> {
+        Ok(())
+    }
+
+    fn after(&self, _request: &ehttp::Request, _result: &mut ehttp::Result<ehttp::Response>) {}
+}
+
+/// A client plus the middleware wrapped around it. Cheap to clone.
+#[derive(Clone)]
+pub struct Http {
+    client: Arc<dyn HttpClient>,
+    middleware: Vec<Arc<dyn Middleware>>,
+}
+
+impl Default for Http {
+    fn default() -> Self {
+        Self::new(Arc::new(EhttpClient))
+    }
+}
+
+impl Http {
+    pub fn new(client: Arc<dyn HttpClient>) -> Self {
+        Self { client, middleware: Vec::new() }
+    }
+
+    pub fn with(mut self, middleware: impl Middleware + 'static) -> Self {
+        self.middleware.push(Arc::new(middleware));
+        self
+    }
+
+    pub fn fetch<F>(&self, mut request: ehttp::Request, done: F)
+    where
+        F: FnOnce(ehttp::Result<ehttp::Response>) + Send + 'static,
+    {
+        for middleware in &self.middleware {
+            if let Err(e) = middleware.before(&mut request) {
+                return done(Err(e));
+            }
+        }
+        let middleware = self.middleware.clone();
+        let sent = request.clone();
+        self.client.fetch(
+            request,
+            Box::new(move |mut result| {
+                for middleware in middleware.iter().rev() {
+                    middleware.after(&sent, &mut result);
+                }
+                done(result);
+            }),
+        );
+    }
+}
+
+/// A token and the header it goes in, for one host.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Credential {
+    /// Host (and port, if any) the token may be sent to
+    pub host: String,
+    pub header: &'static str,
+    pub value: String,
+}
+
+impl Credential {
+    pub fn bearer(host: &str, token: &str) -> Self {
+        Self {
+            host: host.to_string(),
+            header: "Authorization",
+            value: format!("Bearer {}", token),
+        }
+    }
+}
+
+/// Adds each credential to requests for its host only, so tokens never leak to registries or other forges.
+pub struct Auth(pub Vec<Credential>);
+
+impl Middleware for Auth {
+    fn before(&self, request: &mut ehttp::Request) -> Result<(), String> {
+        let host = host_of(&request.url);
+        for credential in self.0.iter().filter(|c| c.host.eq_ignore_ascii_case(host)) {
+            if request.headers.get(credential.header).is_none() {
+                request.headers.insert(credential.header, &credential.value);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The last rate-limit headers a host sent.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RateLimit {
+    pub limit: Option<u64>,
+    pub remaining: u64,
+    /// When the window resets, in milliseconds
+    pub reset_at: Option<f64>,
+}
+
+/// Tracks rate limits per host from GitHub's `x-ratelimit-*` and GitLab's `ratelimit-*` headers,
+/// and refuses requests to a host that is known to be exhausted until it resets.
+#[derive(Clone, Default)]
+pub struct RateLimits(Arc<Mutex<HashMap<String, RateLimit>>>);
+
+impl RateLimits {
+    pub fn get(&self, host: &str) -> Option<RateLimit> {
+        self.0.lock().unwrap().get(host).copied()
+    }
+}
+
+fn rate_header<'a>(headers: &'a ehttp::Headers, name: &str) -> Option<&'a str> {
+    headers.get(&format!("x-ratelimit-{}", name)).or_else(|| headers.get(&format!("ratelimit-{}", name)))
+}
+
+impl Middleware for RateLimits {
+    fn before(&self, request: &mut ehttp::Request) -> Result<(), String> {
+        let host = host_of(&request.url);
+        match self.get(host) {
+            Some(RateLimit { remaining: 0, reset_at: Some(reset_at), .. }) if reset_at > now_millis() => {
+                Err(format!("Rate limited by {}, resets at {}", host, format_timestamp(reset_at)))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn after(&self, request: &ehttp::Request, result: &mut ehttp::Result<ehttp::Response>) {
+        let Ok(response) = result else { return };
+        let number = |name| rate_header(&response.headers, name).and_then(|s| s.trim().parse::<u64>().ok());
+        if let Some(remaining) = number("remaining") {
+            let limit = RateLimit {
+                limit: number("limit"),
+                remaining,
+                reset_at: number("reset").map(|secs| secs as f64 * 1000.0),
+            };
+            self.0.lock().unwrap().insert(host_of(&request.url).to_string(), limit);
+        }
+    }
+}
+
+/// Logs every request's outcome.
+pub struct Logging;
+
+impl Middleware for Logging {
+    fn after(&self, request: &ehttp::Request, result: &mut ehttp::Result<ehttp::Response>) {
+        match result {
+            Ok(response) if response.ok => log::debug!("{} {} -> {}", request.method, request.url, response.status),
+            Ok(response) => log::warn!("{} {} -> {} {}", request.method, request.url, response.status, response.status_text),
+            Err(e) => log::warn!("{} {} failed: {}", request.method, request.url, e),
+        }
+    }
+}
+
+/// Stop remembering responses past this many URLs
+const MAX_CACHED: usize = 256;
+
+/// Makes GETs conditional on the ETag of the last response for the same URL. A 304 keeps its
+/// status, so callers can tell nothing changed, but gets the remembered body filled back in.
+#[derive(Clone, Default)]
+pub struct ResponseCache(Arc<Mutex<HashMap<String, ehttp::Response>>>);
+
+impl ResponseCache {
+    /// Forget every remembered response.
+    pub fn clear(&self) {
+        self.0.lock().unwrap().clear();
+    }
+}
+
+impl Middleware for ResponseCache {
+    fn before(&self, request: &mut ehttp::Request) -> Result<(), String> {
+        if request.method != "GET" || request.headers.get("if-none-match").is_some() {
+            return Ok(());
+        }
+        if let Some(etag) = self.0.lock().unwrap().get(&request.url).and_then(|r| r.headers.get("etag")) {
+            request.headers.insert("If-None-Match", etag);
+        }
+        Ok(())
+    }
+
+    fn after(&self, request: &ehttp::Request, result: &mut ehttp::Result<ehttp::Response>) {
+        let Ok(response) = result else { return };
+        if request.method != "GET" {
+            return;
+        }
+        let mut cache = self.0.lock().unwrap();
+        if response.status == 304 {
+            if let Some(cached) = cache.get(&request.url) {
+                response.ok = true;
+                response.bytes = cached.bytes.clone();
+            }
+        } else if response.ok && response.headers.get("etag").is_some() {
+            if cache.len() >= MAX_CACHED && !cache.contains_key(&request.url) {
+                cache.clear();
+            }
+            cache.insert(request.url.clone(), response.clone());
+        }
+    }
+}
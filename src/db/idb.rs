@@ -1,24 +1,59 @@
 use idb::{Database, DatabaseEvent, Error, Factory, ObjectStoreParams, TransactionMode};
 use serde::{Serialize, de::DeserializeOwned};
 use wasm_bindgen::JsValue;
+use crate::db::history::{self, RepoHistory};
+use crate::db::record::RepoRecord;
+use crate::db::sync::SyncMeta;
 
 const DB_NAME: &str = "WaffleDB";
-const DB_VERSION: u32 = 1;
+const DB_VERSION: u32 = 10;
 
 // Define all supported languages here (must match UI radio button options)
-pub const LANGUAGES: &[&str] = &["Rust", "Python", "Javascript"];
+pub const LANGUAGES: &[&str] = &["Rust", "Python", "Javascript", STARS_STORE];
+/// Not a language: the signed-in GitHub user's starred repositories, searchable like one
+pub const STARS_STORE: &str = "My Stars";
+
+/// The store `name` refers to, ignoring case: `rust` → `Rust`.
+pub fn language_named(name: &str) -> Result<&'static str, String> {
+    LANGUAGES
+        .iter()
+        .copied()
+        .find(|language| language.eq_ignore_ascii_case(name.trim()))
+        .ok_or_else(|| format!("Unknown language {}; expected one of {}", name, LANGUAGES.join(", ")))
+}
+
+// User data and sync history live in their own stores so clearing a language cache never touches them
+pub const FAVORITES_STORE: &str = "favorites";
+pub const SYNC_STORE: &str = "cloud_sync";
+pub const NOTES_STORE: &str = "notes";
+pub const SAVED_SEARCHES_STORE: &str = "saved_searches";
+pub const HISTORY_STORE: &str = "history";
+pub const REPORTS_STORE: &str = "sync_reports";
+pub const SETTINGS_STORE: &str = "settings";
+/// One `SyncMeta` per language store, source and host, written in the same transaction as the records it describes
+pub const SYNC_META_STORE: &str = "sync_meta";
+pub const USER_STORES: &[&str] = &[
+    FAVORITES_STORE,
+    SYNC_STORE,
+    NOTES_STORE,
+    SAVED_SEARCHES_STORE,
+    HISTORY_STORE,
+    REPORTS_STORE,
+    SETTINGS_STORE,
+    SYNC_META_STORE,
+];
 
 pub async fn open_waffle_db() -> Result<Database, Error> {
     let factory = Factory::new()?;
     let mut open_request = factory.open(DB_NAME, Some(DB_VERSION))?;
-    let langs = LANGUAGES.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+    let stores = LANGUAGES.iter().chain(USER_STORES).map(|s| s.to_string()).collect::<Vec<_>>();
     open_request.on_upgrade_needed(move |event| {
         let db = event.database().unwrap();
-        for lang in &langs {
-            if db.store_names().iter().all(|n| n != lang) {
+        for store in &stores {
+            if db.store_names().iter().all(|n| n != store) {
                 let mut store_params = ObjectStoreParams::new();
                 store_params.auto_increment(false);
-                db.create_object_store(lang, store_params).unwrap();
+                db.create_object_store(store, store_params).unwrap();
             }
         }
     });
@@ -89,3 +124,100 @@ pub async fn filter_repos_in_idb<T: DeserializeOwned + Serialize + Clone>(db: &D
     tx.await?;
     Ok(results)
 }
+
+/// Every record of a store as raw JSON, keyed by its (string) key.
+pub async fn get_all_entries(db: &Database, store_name: &str) -> Result<Vec<(String, serde_json::Value)>, Error> {
+    let tx = db.transaction(&[store_name], TransactionMode::ReadOnly)?;
+    let store = tx.object_store(store_name).unwrap();
+    let mut results = Vec::new();
+    let mut cursor = store.open_cursor(None, None)?.await?;
+    while let Some(cur) = cursor {
+        let key = cur.key()?.as_string().unwrap_or_default();
+        let value: serde_json::Value = serde_wasm_bindgen::from_value(cur.value()?).unwrap_or_default();
+        results.push((key, value));
+        cursor = cur.next(None)?.await?;
+    }
+    tx.await?;
+    Ok(results)
+}
+
+/// Write raw JSON records in one transaction, optionally emptying the store first.
+pub async fn put_entries(db: &Database, store_name: &str, entries: &[(String, serde_json::Value)], replace: bool) -> Result<(), Error> {
+    let tx = db.transaction(&[store_name], TransactionMode::ReadWrite)?;
+    let store = tx.object_store(store_name).unwrap();
+    if replace {
+        store.clear()?.await?;
+    }
+    // Plain objects rather than JS Maps, which is what the typed readers expect
+    let serializer = serde_wasm_bindgen::Serializer::json_compatible();
+    for (key, value) in entries {
+        let js_value = value.serialize(&serializer).unwrap();
+        store.put(&js_value, Some(&JsValue::from_str(key)))?;
+    }
+    tx.await?;
+    Ok(())
+}
+
+/// Every repository in a language store, whichever layout it was written in; unreadable records are skipped.
+pub async fn get_all_records(db: &Database, language: &str) -> Result<Vec<RepoRecord>, Error> {
+    Ok(get_all_entries(db, language)
+        .await?
+        .into_iter()
+        .filter_map(|(_, value)| RepoRecord::from_value(value))
+        .collect())
+}
+
+/// Write a sync's records, its metadata and a history sample per record in one transaction, deleting the
+/// records the previous sync from the same source and host wrote but this one no longer returns.
+/// Readers see the old snapshot or the new one, never a mix.
+pub async fn put_snapshot(db: &Database, language: &str, records: &[RepoRecord], meta: &SyncMeta) -> Result<(), Error> {
+    let tx = db.transaction(&[language, SYNC_META_STORE, HISTORY_STORE], TransactionMode::ReadWrite)?;
+    let store = tx.object_store(language).unwrap();
+    let meta_store = tx.object_store(SYNC_META_STORE).unwrap();
+    let meta_key = JsValue::from_str(&meta.store_key());
+    let previous = match meta_store.get(meta_key.clone())?.await? {
+        Some(value) => serde_wasm_bindgen::from_value::<SyncMeta>(value).ok(),
+        None => None,
+    };
+    for key in meta.stale(previous.as_ref()) {
+        store.delete(JsValue::from_str(&key))?;
+    }
+    let serializer = serde_wasm_bindgen::Serializer::json_compatible();
+    for repo in records {
+        store.put(&repo.serialize(&serializer).unwrap(), Some(&JsValue::from_str(&repo.key())))?;
+    }
+    meta_store.put(&meta.serialize(&serializer).unwrap(), Some(&meta_key))?;
+    let history_store = tx.object_store(HISTORY_STORE).unwrap();
+    for repo in records {
+        let key = JsValue::from_str(&repo.key());
+        let stored = match history_store.get(key.clone())?.await? {
+            Some(value) => serde_wasm_bindgen::from_value::<RepoHistory>(value).ok(),
+            None => None,
+        };
+        let history = history::with_sample(stored, repo, meta.synced_at);
+        history_store.put(&history.serialize(&serializer).unwrap(), Some(&key))?;
+    }
+    tx.await?;
+    Ok(())
+}
+
+/// Empty a language store and delete its `SyncMeta`s in one transaction.
+pub async fn clear_language(db: &Database, language: &str) -> Result<(), Error> {
+    let tx = db.transaction(&[language, SYNC_META_STORE], TransactionMode::ReadWrite)?;
+    tx.object_store(language).unwrap().clear()?;
+    let meta_store = tx.object_store(SYNC_META_STORE).unwrap();
+    let mut stale = Vec::new();
+    let mut cursor = meta_store.open_cursor(None, None)?.await?;
+    while let Some(cur) = cursor {
+        let meta = serde_wasm_bindgen::from_value::<SyncMeta>(cur.value()?).unwrap_or_default();
+        if meta.language == language {
+            stale.push(cur.key()?);
+        }
+        cursor = cur.next(None)?.await?;
+    }
+    for key in stale {
+        meta_store.delete(key)?;
+    }
+    tx.await?;
+    Ok(())
+}
@@ -0,0 +1,128 @@
+// import.rs - load repositories from GitHub search dumps or Waffle exports into a language store
+use std::collections::{HashMap, HashSet};
+use crate::db::record::RepoRecord;
+use crate::db::sync::RepoCache;
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ImportSummary {
+    pub inserted: usize,
+    pub updated: usize,
+    /// Invalid, duplicated within the file, or identical to what is stored
+    pub skipped: usize,
+    /// Why records were skipped as invalid, capped to keep the message readable
+    pub problems: Vec<String>,
+}
+
+impl ImportSummary {
+    pub const MAX_PROBLEMS: usize = 5;
+
+    fn problem(&mut self, message: String) {
+        self.skipped += 1;
+        if self.problems.len() < Self::MAX_PROBLEMS {
+            self.problems.push(message);
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        let mut out = format!("{} inserted, {} updated, {} skipped", self.inserted, self.updated, self.skipped);
+        for problem in &self.problems {
+            out.push_str(&format!("\n  {}", problem));
+        }
+        out
+    }
+}
+
+/// Parsed file: records that decoded, plus a summary already counting the ones that didn't.
+pub struct Parsed {
+    pub repos: Vec<RepoRecord>,
+    pub summary: ImportSummary,
+}
+
+/// Accepts a GitHub `SearchResponse`, a JSON array of repositories (Waffle's JSON export)
+/// or NDJSON with one repository per line (Waffle's NDJSON export). Each repository may be
+/// a raw GitHub payload or a Waffle record.
+pub fn parse(text: &str) -> Result<Parsed, String> {
+    let trimmed = text.trim_start();
+    let mut summary = ImportSummary::default();
+    let records = |values: Vec<serde_json::Value>, summary: &mut ImportSummary| {
+        let mut repos = Vec::new();
+        for (i, value) in values.into_iter().enumerate() {
+            match RepoRecord::from_value(value) {
+                Some(repo) => repos.push(repo),
+                None => summary.problem(format!("item {}: not a repository with a full_name", i + 1)),
+            }
+        }
+        repos
+    };
+    let repos = if trimmed.starts_with('[') {
+        let values = serde_json::from_str::<Vec<serde_json::Value>>(trimmed).map_err(|e| format!("Not a JSON array of repositories: {}", e))?;
+        records(values, &mut summary)
+    } else if let Some(value) = serde_json::from_str::<serde_json::Value>(trimmed).ok().filter(|v| v.is_object()) {
+        match value.get("items") {
+            Some(serde_json::Value::Array(items)) => records(items.clone(), &mut summary),
+            Some(_) => return Err("Not a GitHub search response: items is not an array".to_string()),
+            // A single repository object is just one-line NDJSON
+            None => vec![RepoRecord::from_value(value).ok_or("Not a repository")?],
+        }
+    } else {
+        let mut repos = Vec::new();
+        for (line_no, line) in text.lines().enumerate().filter(|(_, l)| !l.trim().is_empty()) {
+            match serde_json::from_str::<serde_json::Value>(line).map_err(|e| e.to_string()).and_then(|v| {
+                RepoRecord::from_value(v).ok_or_else(|| "not a repository with a full_name".to_string())
+            }) {
+                Ok(repo) => repos.push(repo),
+                Err(e) => summary.problem(format!("line {}: {}", line_no + 1, e)),
+            }
+        }
+        if repos.is_empty() && summary.skipped > 0 {
+            return Err(format!("Not NDJSON of repositories: {}", summary.describe()));
+        }
+        repos
+    };
+    Ok(Parsed { repos, summary })
+}
+
+/// Decide what to write given what the store already has. Returns records to put, by key.
+/// Updated records keep the package data enrichment found for them unless the file brings its own.
+pub fn merge(existing: &HashMap<String, RepoRecord>, parsed: Parsed) -> (Vec<(String, RepoRecord)>, ImportSummary) {
+    let Parsed { repos, mut summary } = parsed;
+    let mut seen = HashSet::new();
+    let mut writes = Vec::new();
+    for mut repo in repos {
+        let key = repo.key();
+        if !seen.insert(key.clone()) {
+            summary.problem(format!("{} appears more than once", key));
+            continue;
+        }
+        if let Some(current) = existing.get(&key).filter(|_| repo.package_checked_at.is_none()) {
+            repo.package = current.package.clone();
+            repo.package_checked_at = current.package_checked_at;
+        }
+        match existing.get(&key) {
+            Some(current) if *current == repo => summary.skipped += 1,
+            Some(_) => {
+                summary.updated += 1;
+                writes.push((key, repo));
+            }
+            None => {
+                summary.inserted += 1;
+                writes.push((key, repo));
+            }
+        }
+    }
+    (writes, summary)
+}
+
+/// Merge `parsed` into a language store, writing every new or changed record in one transaction.
+pub async fn import_into<C: RepoCache>(cache: &C, language: &str, parsed: Parsed) -> Result<ImportSummary, String> {
+    let existing = cache
+        .records(language)
+        .await?
+        .into_iter()
+        .map(|r| (r.key(), r))
+        .collect::<HashMap<_, _>>();
+    let (writes, summary) = merge(&existing, parsed);
+    let records = writes.into_iter().map(|(_, repo)| repo).collect::<Vec<_>>();
+    cache.put_records(language, &records).await?;
+    Ok(summary)
+}
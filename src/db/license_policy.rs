@@ -0,0 +1,125 @@
+// license_policy.rs - classify repositories against a team's allowed / review / denied license lists
+use serde::{Deserialize, Serialize};
+use crate::db::record::RepoRecord;
+
+/// GitHub reports licenses it cannot identify with this SPDX id
+pub const NOASSERTION: &str = "NOASSERTION";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Verdict {
+    Allowed,
+    Review,
+    Denied,
+    /// Has a license that none of the lists mention
+    Unlisted,
+    Unlicensed,
+}
+
+impl Verdict {
+    pub const ALL: &'static [Verdict] = &[Verdict::Allowed, Verdict::Review, Verdict::Denied, Verdict::Unlisted, Verdict::Unlicensed];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Verdict::Allowed => "allowed",
+            Verdict::Review => "review",
+            Verdict::Denied => "denied",
+            Verdict::Unlisted => "unlisted",
+            Verdict::Unlicensed => "unlicensed",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        Verdict::ALL.iter().copied().find(|v| v.label().eq_ignore_ascii_case(s))
+    }
+
+    pub fn is_compliant(&self) -> bool {
+        *self == Verdict::Allowed
+    }
+}
+
+/// What a sync does with repositories that have no license at all.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum UnlicensedHandling {
+    Keep,
+    Flag,
+    /// The historical behaviour: unlicensed repositories never reach the cache
+    #[default]
+    Drop,
+}
+
+impl UnlicensedHandling {
+    pub const ALL: &'static [UnlicensedHandling] = &[UnlicensedHandling::Keep, UnlicensedHandling::Flag, UnlicensedHandling::Drop];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            UnlicensedHandling::Keep => "Keep",
+            UnlicensedHandling::Flag => "Keep and flag",
+            UnlicensedHandling::Drop => "Drop on sync",
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct LicensePolicy {
+    /// SPDX identifiers, matched case-insensitively
+    pub allowed: Vec<String>,
+    pub review: Vec<String>,
+    pub denied: Vec<String>,
+    pub unlicensed: UnlicensedHandling,
+}
+
+impl Default for LicensePolicy {
+    fn default() -> Self {
+        let ids = |ids: &[&str]| ids.iter().map(|s| s.to_string()).collect();
+        Self {
+            allowed: ids(&["MIT", "Apache-2.0", "BSD-2-Clause", "BSD-3-Clause", "ISC", "Zlib", "0BSD", "Unlicense", "CC0-1.0"]),
+            review: ids(&["MPL-2.0", "LGPL-2.1", "LGPL-3.0", "EPL-2.0", "GPL-2.0", "GPL-3.0"]),
+            denied: ids(&["AGPL-3.0", "SSPL-1.0", "BUSL-1.1"]),
+            unlicensed: UnlicensedHandling::Drop,
+        }
+    }
+}
+
+/// The SPDX id GitHub detected, or the license key/name when it has none.
+pub fn license_id(repo: &RepoRecord) -> Option<String> {
+    let license = repo.license.as_ref()?;
+    license
+        .spdx_id
+        .clone()
+        .or_else(|| license.key.clone())
+        .or_else(|| Some(license.name.clone()).filter(|n| !n.is_empty()))
+        .or_else(|| Some(NOASSERTION.to_string()))
+}
+
+impl LicensePolicy {
+    pub fn classify(&self, repo: &RepoRecord) -> Verdict {
+        let Some(id) = license_id(repo) else {
+            return Verdict::Unlicensed;
+        };
+        let listed = |list: &[String]| list.iter().any(|l| l.eq_ignore_ascii_case(&id));
+        if listed(&self.denied) {
+            Verdict::Denied
+        } else if listed(&self.review) {
+            Verdict::Review
+        } else if listed(&self.allowed) {
+            Verdict::Allowed
+        } else {
+            Verdict::Unlisted
+        }
+    }
+
+    /// Whether a synced repository goes into the cache at all.
+    pub fn keeps(&self, repo: &RepoRecord) -> bool {
+        self.unlicensed != UnlicensedHandling::Drop || repo.license.is_some()
+    }
+
+    /// Whether the list should call this repository out.
+    pub fn flags(&self, verdict: Verdict) -> bool {
+        match verdict {
+            Verdict::Allowed => false,
+            Verdict::Unlicensed => self.unlicensed == UnlicensedHandling::Flag,
+            _ => true,
+        }
+    }
+}
@@ -0,0 +1,142 @@
+// local.rs - load/save single documents in a user store on either IndexedDB or the native store
+use serde::{Serialize, de::DeserializeOwned};
+use std::sync::{Arc, Mutex};
+#[cfg(target_arch = "wasm32")]
+use crate::db::idb;
+
+/// Read one record and hand the result to `done` (asynchronously on the web).
+pub fn load_doc<T, F>(store: &str, key: &str, done: F)
+where
+    T: DeserializeOwned + 'static,
+    F: FnOnce(Result<Option<T>, String>) + 'static,
+{
+    let store = store.to_string();
+    let key = key.to_string();
+    #[cfg(target_arch = "wasm32")]
+    wasm_bindgen_futures::spawn_local(async move {
+        let result = match idb::open_waffle_db().await {
+            Ok(db) => idb::get_repo::<T>(&db, &store, &key).await.map_err(|e| e.to_string()),
+            Err(e) => Err(format!("Failed to open IndexedDB: {}", e)),
+        };
+        done(result);
+    });
+    #[cfg(not(target_arch = "wasm32"))]
+    done(crate::db::native::NativeStore::open_default().get::<T>(&store, &key).map_err(|e| e.to_string()));
+}
+
+/// Write one record, reporting the outcome to `done`.
+pub fn save_doc<T, F>(store: &str, key: &str, value: T, done: F)
+where
+    T: Serialize + 'static,
+    F: FnOnce(Result<(), String>) + 'static,
+{
+    let store = store.to_string();
+    let key = key.to_string();
+    #[cfg(target_arch = "wasm32")]
+    wasm_bindgen_futures::spawn_local(async move {
+        let result = match idb::open_waffle_db().await {
+            Ok(db) => idb::add_repo(&db, &store, &key, &value).await.map_err(|e| e.to_string()),
+            Err(e) => Err(format!("Failed to open IndexedDB: {}", e)),
+        };
+        done(result);
+    });
+    #[cfg(not(target_arch = "wasm32"))]
+    done(crate::db::native::NativeStore::open_default().put(&store, &key, &value).map_err(|e| e.to_string()));
+}
+
+/// A user data set persisted as a single record in its own store.
+pub trait Document: Default + Clone + Serialize + DeserializeOwned + 'static {
+    const STORE: &'static str;
+    const KEY: &'static str;
+    /// Name used in error messages, e.g. "favorites".
+    const LABEL: &'static str;
+}
+
+/// Shared handle to a `Document`, persisted on every change.
+pub struct DocStore<T: Document> {
+    value: Arc<Mutex<T>>,
+    error: Arc<Mutex<Option<String>>>,
+    revision: Arc<Mutex<u64>>,
+}
+
+impl<T: Document> Clone for DocStore<T> {
+    fn clone(&self) -> Self {
+        Self {
+            value: Arc::clone(&self.value),
+            error: Arc::clone(&self.error),
+            revision: Arc::clone(&self.revision),
+        }
+    }
+}
+
+impl<T: Document> Default for DocStore<T> {
+    fn default() -> Self {
+        Self {
+            value: Arc::new(Mutex::new(T::default())),
+            error: Arc::new(Mutex::new(None)),
+            revision: Arc::new(Mutex::new(0)),
+        }
+    }
+}
+
+impl<T: Document> DocStore<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn get(&self) -> T {
+        self.value.lock().unwrap().clone()
+    }
+
+    /// Read without cloning the whole document.
+    pub fn with<R>(&self, f: impl FnOnce(&T) -> R) -> R {
+        f(&self.value.lock().unwrap())
+    }
+
+    /// Apply a change and persist the result.
+    pub fn update<R>(&self, f: impl FnOnce(&mut T) -> R) -> R {
+        let result = f(&mut self.value.lock().unwrap());
+        *self.revision.lock().unwrap() += 1;
+        self.save();
+        result
+    }
+
+    /// Replace the in-memory value without bumping the revision (used for remote merges).
+    pub fn update_quiet<R>(&self, f: impl FnOnce(&mut T) -> R) -> R {
+        let result = f(&mut self.value.lock().unwrap());
+        self.save();
+        result
+    }
+
+    /// Counter bumped on every change and when the stored copy finishes loading.
+    pub fn revision(&self) -> u64 {
+        *self.revision.lock().unwrap()
+    }
+
+    pub fn take_error(&self) -> Option<String> {
+        self.error.lock().unwrap().take()
+    }
+
+    pub fn load(&self) {
+        let value = Arc::clone(&self.value);
+        let error = Arc::clone(&self.error);
+        let revision = Arc::clone(&self.revision);
+        load_doc::<T, _>(T::STORE, T::KEY, move |result| match result {
+            Ok(stored) => {
+                *value.lock().unwrap() = stored.unwrap_or_default();
+                // Let views that depend on this document refresh once it arrives
+                *revision.lock().unwrap() += 1;
+            }
+            Err(e) => *error.lock().unwrap() = Some(format!("Failed to load {}: {}", T::LABEL, e)),
+        });
+    }
+
+    pub fn save(&self) {
+        let error = Arc::clone(&self.error);
+        save_doc(T::STORE, T::KEY, self.get(), move |result| {
+            if let Err(e) = result {
+                *error.lock().unwrap() = Some(format!("Failed to save {}: {}", T::LABEL, e));
+            }
+        });
+    }
+}
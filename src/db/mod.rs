@@ -1,2 +1,28 @@
+pub mod gitea;
 pub mod github;
-pub mod idb;
\ No newline at end of file
+pub mod gitlab;
+pub mod graphql;
+pub mod idb;
+pub mod import;
+pub mod local;
+pub mod favorites;
+pub mod notes;
+pub mod query;
+pub mod record;
+pub mod registry;
+pub mod history;
+pub mod http;
+pub mod health;
+pub mod license_policy;
+pub mod saved_search;
+pub mod settings;
+pub mod source;
+pub mod stats;
+pub mod sync;
+pub mod sync_report;
+pub mod backup;
+pub mod cancel;
+pub mod cloud_sync;
+pub mod export;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod native;
@@ -0,0 +1,106 @@
+// native.rs - file-backed key/value store used when IndexedDB is not available
+use serde::{Serialize, de::DeserializeOwned};
+use std::collections::BTreeMap;
+use std::io;
+use std::path::PathBuf;
+use crate::db::record::RepoRecord;
+
+const APP_ID: &str = "waffle_search";
+
+/// Mirrors the IndexedDB layout natively: one JSON file per object store,
+/// each holding a map of key -> record.
+#[derive(Clone, Debug)]
+pub struct NativeStore {
+    root: PathBuf,
+}
+
+impl NativeStore {
+    /// Store rooted at a specific directory (used by tests and tooling).
+    pub fn at(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    /// Store rooted in the platform data directory, falling back to the temp dir.
+    pub fn open_default() -> Self {
+        if let Some(dir) = eframe::storage_dir(APP_ID) {
+            return Self::at(dir.join("stores"));
+        }
+        Self::at(std::env::temp_dir().join(APP_ID))
+    }
+
+    fn path(&self, store: &str) -> PathBuf {
+        self.root.join(format!("{}.json", store))
+    }
+
+    fn read_store(&self, store: &str) -> io::Result<BTreeMap<String, serde_json::Value>> {
+        match std::fs::read(self.path(store)) {
+            Ok(bytes) => serde_json::from_slice(&bytes).map_err(io::Error::other),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(BTreeMap::new()),
+            Err(e) => Err(e),
+        }
+    }
+
+    fn write_store(&self, store: &str, records: &BTreeMap<String, serde_json::Value>) -> io::Result<()> {
+        std::fs::create_dir_all(&self.root)?;
+        let bytes = serde_json::to_vec(records).map_err(io::Error::other)?;
+        // Write to a sibling file and rename so a crash never leaves a truncated store.
+        let tmp = self.path(&format!("{}.tmp", store));
+        std::fs::write(&tmp, bytes)?;
+        std::fs::rename(tmp, self.path(store))
+    }
+
+    pub fn put<T: Serialize>(&self, store: &str, key: &str, value: &T) -> io::Result<()> {
+        let mut records = self.read_store(store)?;
+        records.insert(key.to_string(), serde_json::to_value(value).map_err(io::Error::other)?);
+        self.write_store(store, &records)
+    }
+
+    pub fn get<T: DeserializeOwned>(&self, store: &str, key: &str) -> io::Result<Option<T>> {
+        let records = self.read_store(store)?;
+        match records.get(key) {
+            Some(value) => Ok(Some(serde_json::from_value(value.clone()).map_err(io::Error::other)?)),
+            None => Ok(None),
+        }
+    }
+
+    pub fn get_all<T: DeserializeOwned>(&self, store: &str) -> io::Result<Vec<T>> {
+        self.read_store(store)?
+            .into_values()
+            .map(|value| serde_json::from_value(value).map_err(io::Error::other))
+            .collect()
+    }
+
+    /// Repositories of a language store in either stored layout; unreadable records are skipped.
+    pub fn records(&self, store: &str) -> io::Result<Vec<RepoRecord>> {
+        Ok(self.read_store(store)?.into_values().filter_map(RepoRecord::from_value).collect())
+    }
+
+    /// Every record of a store as raw JSON, by key.
+    pub fn entries(&self, store: &str) -> io::Result<BTreeMap<String, serde_json::Value>> {
+        self.read_store(store)
+    }
+
+    /// Write many raw records in one go.
+    pub fn put_entries(&self, store: &str, entries: &BTreeMap<String, serde_json::Value>) -> io::Result<()> {
+        let mut records = self.read_store(store)?;
+        records.extend(entries.iter().map(|(k, v)| (k.clone(), v.clone())));
+        self.write_store(store, &records)
+    }
+
+    /// Replace a store's whole contents in one write.
+    pub fn replace_entries(&self, store: &str, entries: &BTreeMap<String, serde_json::Value>) -> io::Result<()> {
+        self.write_store(store, entries)
+    }
+
+    pub fn delete(&self, store: &str, key: &str) -> io::Result<()> {
+        let mut records = self.read_store(store)?;
+        if records.remove(key).is_some() {
+            self.write_store(store, &records)?;
+        }
+        Ok(())
+    }
+
+    pub fn clear(&self, store: &str) -> io::Result<()> {
+        self.write_store(store, &BTreeMap::new())
+    }
+}
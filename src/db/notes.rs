@@ -0,0 +1,140 @@
+// notes.rs - private notes and custom tags attached to repositories by node_id
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use crate::db::cloud_sync::{self, SyncDocument, SyncRecord, Tombstone, remote_wins};
+use crate::db::idb;
+use crate::db::local::{DocStore, Document};
+use crate::utility::now_millis;
+
+pub const NOTE_KIND: &str = "note";
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(default)]
+pub struct RepoNote {
+    pub node_id: String,
+    /// Kept alongside the id so notes stay readable when the repo is not cached
+    pub full_name: Option<String>,
+    pub text: String,
+    pub tags: Vec<String>,
+    pub updated_at: f64,
+}
+
+impl RepoNote {
+    pub fn is_empty(&self) -> bool {
+        self.text.trim().is_empty() && self.tags.is_empty()
+    }
+
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags.iter().any(|t| t.eq_ignore_ascii_case(tag))
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(default)]
+pub struct Notes {
+    pub entries: BTreeMap<String, RepoNote>,
+    pub removed: Vec<Tombstone>,
+}
+
+impl Notes {
+    pub fn get(&self, node_id: &str) -> Option<&RepoNote> {
+        self.entries.get(node_id)
+    }
+
+    fn entry(&mut self, node_id: &str, full_name: Option<&str>) -> &mut RepoNote {
+        cloud_sync::unbury(&mut self.removed, NOTE_KIND, node_id);
+        let note = self.entries.entry(node_id.to_string()).or_insert_with(|| RepoNote {
+            node_id: node_id.to_string(),
+            ..RepoNote::default()
+        });
+        if let Some(full_name) = full_name {
+            note.full_name = Some(full_name.to_string());
+        }
+        note.updated_at = now_millis();
+        note
+    }
+
+    /// Drop a note that no longer carries any text or tags.
+    fn prune(&mut self, node_id: &str) {
+        if self.entries.get(node_id).is_some_and(RepoNote::is_empty) {
+            self.entries.remove(node_id);
+            cloud_sync::bury(&mut self.removed, NOTE_KIND, node_id, now_millis());
+        }
+    }
+
+    pub fn set_text(&mut self, node_id: &str, full_name: Option<&str>, text: &str) {
+        self.entry(node_id, full_name).text = text.to_string();
+        self.prune(node_id);
+    }
+
+    /// Attach a tag, ignoring blanks and case-insensitive duplicates.
+    pub fn add_tag(&mut self, node_id: &str, full_name: Option<&str>, tag: &str) -> bool {
+        let tag = tag.trim();
+        if tag.is_empty() || self.get(node_id).is_some_and(|n| n.has_tag(tag)) {
+            return false;
+        }
+        self.entry(node_id, full_name).tags.push(tag.to_string());
+        true
+    }
+
+    pub fn remove_tag(&mut self, node_id: &str, tag: &str) {
+        if self.get(node_id).is_some_and(|n| n.has_tag(tag)) {
+            self.entry(node_id, None).tags.retain(|t| !t.eq_ignore_ascii_case(tag));
+            self.prune(node_id);
+        }
+    }
+
+    /// Every tag in use, for suggestions in the detail pane.
+    pub fn all_tags(&self) -> BTreeSet<String> {
+        self.entries.values().flat_map(|n| n.tags.iter().cloned()).collect()
+    }
+}
+
+impl Document for Notes {
+    const STORE: &'static str = idb::NOTES_STORE;
+    const KEY: &'static str = "notes";
+    const LABEL: &'static str = "notes";
+}
+
+impl SyncDocument for Notes {
+    const KINDS: &'static [&'static str] = &[NOTE_KIND];
+
+    fn to_sync_records(&self) -> Vec<SyncRecord> {
+        self.entries
+            .values()
+            .map(|n| SyncRecord {
+                kind: NOTE_KIND.to_string(),
+                key: n.node_id.clone(),
+                payload: serde_json::to_value(n).unwrap_or_default(),
+                updated_at: n.updated_at,
+                deleted: false,
+                seq: None,
+            })
+            .chain(self.removed.iter().map(Tombstone::to_record))
+            .collect()
+    }
+
+    fn apply_sync_records(&mut self, records: &[SyncRecord]) {
+        for record in records.iter().filter(|r| r.kind == NOTE_KIND) {
+            let key = record.key.as_str();
+            let local = self
+                .entries
+                .get(key)
+                .map(|n| n.updated_at)
+                .or_else(|| cloud_sync::tombstone_at(&self.removed, NOTE_KIND, key));
+            if !remote_wins(local, record) {
+                continue;
+            }
+            if record.deleted {
+                self.entries.remove(key);
+                cloud_sync::bury(&mut self.removed, NOTE_KIND, key, record.updated_at);
+            } else if let Ok(note) = serde_json::from_value::<RepoNote>(record.payload.clone()) {
+                cloud_sync::unbury(&mut self.removed, NOTE_KIND, key);
+                self.entries.insert(key.to_string(), note);
+            }
+        }
+    }
+}
+
+/// Shared handle to the user's notes and tags.
+pub type NotesStore = DocStore<Notes>;
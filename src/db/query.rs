@@ -0,0 +1,364 @@
+// query.rs - the search box query language
+//
+// Free words match name, description and private notes. Qualifiers narrow further:
+//   tag:"license risk"   note:deprecated   has:note   has:tag
+//   stars:>1000   forks:10..500   downloads:>10000   health:>70   topic:cli   language:rust   archived:false
+//   license:mit   policy:denied (allowed, review, denied, unlisted, unlicensed)   source:gitlab (github, gitlab, gitea)   host:github.example.com
+// Prefix any term with `-` to negate it; quote values containing spaces.
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use crate::db::record::RepoRecord;
+use crate::db::health::HealthWeights;
+use crate::db::license_policy::{self, LicensePolicy, Verdict};
+use crate::db::history::{Metric, RepoHistory};
+use crate::db::notes::Notes;
+use crate::db::source::SourceKind;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Cmp {
+    Eq(f64),
+    Gt(f64),
+    Ge(f64),
+    Lt(f64),
+    Le(f64),
+    Between(f64, f64),
+}
+
+impl Cmp {
+    /// Parse `>10`, `>=10`, `<10`, `<=10`, `10` or `10..20`.
+    pub fn parse(s: &str) -> Option<Self> {
+        let s = s.trim();
+        if let Some((lo, hi)) = s.split_once("..") {
+            return Some(Cmp::Between(lo.parse().ok()?, hi.parse().ok()?));
+        }
+        if let Some(v) = s.strip_prefix(">=") {
+            return v.parse().ok().map(Cmp::Ge);
+        }
+        if let Some(v) = s.strip_prefix("<=") {
+            return v.parse().ok().map(Cmp::Le);
+        }
+        if let Some(v) = s.strip_prefix('>') {
+            return v.parse().ok().map(Cmp::Gt);
+        }
+        if let Some(v) = s.strip_prefix('<') {
+            return v.parse().ok().map(Cmp::Lt);
+        }
+        s.parse().ok().map(Cmp::Eq)
+    }
+
+    pub fn matches(&self, value: f64) -> bool {
+        match *self {
+            Cmp::Eq(v) => value == v,
+            Cmp::Gt(v) => value > v,
+            Cmp::Ge(v) => value >= v,
+            Cmp::Lt(v) => value < v,
+            Cmp::Le(v) => value <= v,
+            Cmp::Between(lo, hi) => value >= lo && value <= hi,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Filter {
+    Text(String),
+    Tag(String),
+    Note(String),
+    HasNote,
+    HasTag,
+    Stars(Cmp),
+    Forks(Cmp),
+    Downloads(Cmp),
+    Health(Cmp),
+    Topic(String),
+    Language(String),
+    Archived(bool),
+    License(String),
+    Policy(Verdict),
+    Source(SourceKind),
+    Host(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Clause {
+    pub negated: bool,
+    pub filter: Filter,
+}
+
+/// Per-user data a query can refer to besides the repository itself.
+pub struct QueryContext<'a> {
+    pub notes: &'a Notes,
+    /// Sync history by full_name, only loaded when a trending sort needs it
+    pub histories: Option<&'a HashMap<String, RepoHistory>>,
+    pub health: HealthWeights,
+    /// Falls back to the default policy when unset
+    pub license_policy: Option<&'a LicensePolicy>,
+    pub now: f64,
+}
+
+impl<'a> QueryContext<'a> {
+    pub fn new(notes: &'a Notes) -> Self {
+        Self {
+            notes,
+            histories: None,
+            health: HealthWeights::default(),
+            license_policy: None,
+            now: crate::utility::now_millis(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Query {
+    pub clauses: Vec<Clause>,
+}
+
+/// Split on whitespace, keeping `"quoted phrases"` (also after `key:`) together.
+fn tokenize(input: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut current = String::new();
+    let mut in_quotes = false;
+    for c in input.chars() {
+        match c {
+            '"' => in_quotes = !in_quotes,
+            c if c.is_whitespace() && !in_quotes => {
+                if !current.is_empty() {
+                    tokens.push(std::mem::take(&mut current));
+                }
+            }
+            c => current.push(c),
+        }
+    }
+    if !current.is_empty() {
+        tokens.push(current);
+    }
+    tokens
+}
+
+fn parse_filter(token: &str) -> Filter {
+    let Some((key, value)) = token.split_once(':') else {
+        return Filter::Text(token.to_lowercase());
+    };
+    let value_lower = value.to_lowercase();
+    let parsed = match key.to_lowercase().as_str() {
+        "tag" => Some(Filter::Tag(value_lower)),
+        "note" => Some(Filter::Note(value_lower)),
+        "has" => match value_lower.as_str() {
+            "note" | "notes" => Some(Filter::HasNote),
+            "tag" | "tags" => Some(Filter::HasTag),
+            _ => None,
+        },
+        "stars" => Cmp::parse(value).map(Filter::Stars),
+        "forks" => Cmp::parse(value).map(Filter::Forks),
+        "downloads" => Cmp::parse(value).map(Filter::Downloads),
+        "health" => Cmp::parse(value).map(Filter::Health),
+        "topic" => Some(Filter::Topic(value_lower)),
+        "lang" | "language" => Some(Filter::Language(value_lower)),
+        "archived" => value_lower.parse().ok().map(Filter::Archived),
+        "license" => Some(Filter::License(value_lower)),
+        "policy" => Verdict::parse(value).map(Filter::Policy),
+        "source" => SourceKind::parse(value).map(Filter::Source),
+        "host" => Some(Filter::Host(value_lower)),
+        _ => None,
+    };
+    // Unknown or malformed qualifiers fall back to plain text so nothing is silently dropped.
+    parsed.unwrap_or_else(|| Filter::Text(token.to_lowercase()))
+}
+
+impl Query {
+    pub fn parse(input: &str) -> Self {
+        let clauses = tokenize(input)
+            .into_iter()
+            .filter_map(|token| {
+                let (negated, body) = match token.strip_prefix('-') {
+                    Some(rest) if !rest.is_empty() => (true, rest),
+                    _ => (false, token.as_str()),
+                };
+                if body.is_empty() {
+                    return None;
+                }
+                Some(Clause {
+                    negated,
+                    filter: parse_filter(body),
+                })
+            })
+            .collect();
+        Self { clauses }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.clauses.is_empty()
+    }
+
+    pub fn matches(&self, repo: &RepoRecord, ctx: &QueryContext<'_>) -> bool {
+        self.clauses.iter().all(|clause| clause_matches(&clause.filter, repo, ctx) != clause.negated)
+    }
+
+    pub fn filter(&self, repos: Vec<RepoRecord>, ctx: &QueryContext<'_>) -> Vec<RepoRecord> {
+        repos.into_iter().filter(|repo| self.matches(repo, ctx)).collect()
+    }
+}
+
+fn contains(haystack: Option<&str>, needle: &str) -> bool {
+    haystack.is_some_and(|h| h.to_lowercase().contains(needle))
+}
+
+fn downloads(repo: &RepoRecord) -> Option<u64> {
+    repo.package.as_ref()?.downloads
+}
+
+fn clause_matches(filter: &Filter, repo: &RepoRecord, ctx: &QueryContext<'_>) -> bool {
+    let note = repo.node_id.as_deref().and_then(|id| ctx.notes.get(id));
+    match filter {
+        Filter::Text(text) => {
+            contains(Some(&repo.full_name), text)
+                || contains(repo.description.as_deref(), text)
+                || note.is_some_and(|n| contains(Some(&n.text), text) || n.tags.iter().any(|t| t.to_lowercase().contains(text)))
+        }
+        Filter::Tag(tag) => note.is_some_and(|n| n.has_tag(tag)),
+        Filter::Note(text) => note.is_some_and(|n| contains(Some(&n.text), text)),
+        Filter::HasNote => note.is_some_and(|n| !n.text.trim().is_empty()),
+        Filter::HasTag => note.is_some_and(|n| !n.tags.is_empty()),
+        Filter::Stars(cmp) => cmp.matches(repo.stars as f64),
+        Filter::Forks(cmp) => cmp.matches(repo.forks as f64),
+        // Repositories without a known package never match
+        Filter::Downloads(cmp) => downloads(repo).is_some_and(|d| cmp.matches(d as f64)),
+        Filter::Health(cmp) => cmp.matches(ctx.health.score(repo, ctx.now).round()),
+        Filter::Topic(topic) => repo.topics.iter().any(|t| t.eq_ignore_ascii_case(topic)),
+        Filter::Language(lang) => repo.language.as_deref().is_some_and(|l| l.eq_ignore_ascii_case(lang)),
+        Filter::Archived(archived) => repo.archived == *archived,
+        Filter::License(license) => {
+            license_policy::license_id(repo).is_some_and(|id| id.eq_ignore_ascii_case(license))
+                || contains(repo.license.as_ref().map(|l| l.name.as_str()), license)
+        }
+        Filter::Policy(verdict) => match ctx.license_policy {
+            Some(policy) => policy.classify(repo) == *verdict,
+            None => LicensePolicy::default().classify(repo) == *verdict,
+        },
+        Filter::Source(source) => repo.source == *source,
+        Filter::Host(host) => repo.host().eq_ignore_ascii_case(host),
+    }
+}
+
+/// Checkbox-style filters shown under the search box, applied on top of the query text.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(default)]
+pub struct SearchFilters {
+    pub min_stars: Option<u64>,
+    pub exclude_archived: bool,
+    pub exclude_forks: bool,
+}
+
+impl SearchFilters {
+    pub fn matches(&self, repo: &RepoRecord) -> bool {
+        self.min_stars.is_none_or(|min| repo.stars >= min)
+            && !(self.exclude_archived && repo.archived)
+            && !(self.exclude_forks && repo.fork)
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SortOrder {
+    #[default]
+    Stars,
+    Forks,
+    RecentlyPushed,
+    Name,
+    /// Star velocity over the last 7 days
+    TrendingWeek,
+    /// Star velocity over the last 30 days
+    TrendingMonth,
+    Health,
+    /// Registry downloads (crates.io, PyPI, npm)
+    Downloads,
+    /// Latest package release first
+    RecentlyPublished,
+}
+
+impl SortOrder {
+    pub const ALL: &'static [SortOrder] = &[
+        SortOrder::Stars,
+        SortOrder::TrendingWeek,
+        SortOrder::TrendingMonth,
+        SortOrder::Health,
+        SortOrder::Downloads,
+        SortOrder::RecentlyPublished,
+        SortOrder::Forks,
+        SortOrder::RecentlyPushed,
+        SortOrder::Name,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            SortOrder::Stars => "Stars",
+            SortOrder::Forks => "Forks",
+            SortOrder::RecentlyPushed => "Recently pushed",
+            SortOrder::Name => "Name",
+            SortOrder::TrendingWeek => "Trending (7 days)",
+            SortOrder::TrendingMonth => "Trending (30 days)",
+            SortOrder::Health => "Health score",
+            SortOrder::Downloads => "Package downloads",
+            SortOrder::RecentlyPublished => "Recently published",
+        }
+    }
+
+    /// Window of a trending sort, in days.
+    pub fn trend_days(&self) -> Option<f64> {
+        match self {
+            SortOrder::TrendingWeek => Some(7.0),
+            SortOrder::TrendingMonth => Some(30.0),
+            _ => None,
+        }
+    }
+
+    /// Stars gained per day over the trending window, if there is enough history.
+    pub fn trend(&self, repo: &RepoRecord, ctx: &QueryContext<'_>) -> Option<f64> {
+        let days = self.trend_days()?;
+        let history = ctx.histories?.get(&repo.key())?;
+        history.velocity(Metric::Stars, ctx.now, days)
+    }
+
+    pub fn sort(&self, repos: &mut [RepoRecord], ctx: &QueryContext<'_>) {
+        match self {
+            SortOrder::TrendingWeek | SortOrder::TrendingMonth => {
+                // Repos without enough history sink to the bottom
+                repos.sort_by(|a, b| {
+                    let ta = self.trend(a, ctx).unwrap_or(f64::NEG_INFINITY);
+                    let tb = self.trend(b, ctx).unwrap_or(f64::NEG_INFINITY);
+                    tb.total_cmp(&ta)
+                })
+            }
+            SortOrder::Health => repos.sort_by(|a, b| ctx.health.score(b, ctx.now).total_cmp(&ctx.health.score(a, ctx.now))),
+            SortOrder::Stars => repos.sort_by_key(|r| std::cmp::Reverse(r.stars)),
+            SortOrder::Downloads => repos.sort_by_key(|r| std::cmp::Reverse(downloads(r))),
+            SortOrder::RecentlyPublished => {
+                let published = |r: &RepoRecord| r.package.as_ref().and_then(|p| p.published_at);
+                repos.sort_by(|a, b| published(b).partial_cmp(&published(a)).unwrap_or(std::cmp::Ordering::Equal))
+            }
+            SortOrder::Forks => repos.sort_by_key(|r| std::cmp::Reverse(r.forks)),
+            // Never-pushed repos (None) sort last
+            SortOrder::RecentlyPushed => repos.sort_by(|a, b| b.pushed_at.partial_cmp(&a.pushed_at).unwrap_or(std::cmp::Ordering::Equal)),
+            SortOrder::Name => repos.sort_by_key(|r| r.full_name.to_lowercase()),
+        }
+    }
+}
+
+/// Everything needed to reproduce a result list: query text, filters and ordering.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(default)]
+pub struct SearchSpec {
+    pub query: String,
+    pub filters: SearchFilters,
+    pub sort: SortOrder,
+}
+
+impl SearchSpec {
+    pub fn run(&self, repos: Vec<RepoRecord>, ctx: &QueryContext<'_>) -> Vec<RepoRecord> {
+        let query = Query::parse(&self.query);
+        let mut results = repos
+            .into_iter()
+            .filter(|repo| self.filters.matches(repo) && query.matches(repo, ctx))
+            .collect::<Vec<_>>();
+        self.sort.sort(&mut results, ctx);
+        results
+    }
+}
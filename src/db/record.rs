@@ -0,0 +1,209 @@
+// record.rs - the compact repository record we cache and render, converted from the GitHub wire format
+use serde::{Deserialize, Serialize};
+use crate::db::github::{Repository, PUBLIC_HOST};
+use crate::db::registry::PackageInfo;
+use crate::db::source::{host_of, SourceKind};
+use crate::utility::parse_timestamp;
+
+/// How much of a README is cached per record; the detail pane shows a preview, not the document.
+pub const README_EXCERPT_CHARS: usize = 2000;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Visibility {
+    #[default]
+    Public,
+    Private,
+    /// GitHub Enterprise: visible to members of the enterprise
+    Internal,
+}
+
+impl Visibility {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Visibility::Public => "Public",
+            Visibility::Private => "Private",
+            Visibility::Internal => "Internal",
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(default)]
+pub struct LicenseInfo {
+    pub key: Option<String>,
+    pub name: String,
+    pub spdx_id: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(default)]
+pub struct ReleaseInfo {
+    pub tag: String,
+    pub published_at: Option<f64>,
+    pub url: Option<String>,
+}
+
+/// One cached repository. Timestamps are milliseconds since the epoch.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(default)]
+pub struct RepoRecord {
+    pub id: u64,
+    /// GitHub's global id, which notes are keyed by since it survives renames
+    pub node_id: Option<String>,
+    /// "owner/name", also the store key
+    pub full_name: String,
+    pub name: String,
+    pub owner: String,
+    pub html_url: String,
+    pub description: Option<String>,
+    pub homepage: Option<String>,
+    pub language: Option<String>,
+    /// Languages by size, largest first; only the GraphQL backend fills this
+    pub languages: Vec<String>,
+    pub topics: Vec<String>,
+    pub license: Option<LicenseInfo>,
+    pub stars: u64,
+    pub forks: u64,
+    pub watchers: u64,
+    pub open_issues: u64,
+    /// Kilobytes
+    pub size: u64,
+    pub fork: bool,
+    pub archived: bool,
+    pub disabled: bool,
+    pub is_template: bool,
+    pub has_issues: bool,
+    pub visibility: Visibility,
+    pub default_branch: Option<String>,
+    pub created_at: Option<f64>,
+    pub updated_at: Option<f64>,
+    pub pushed_at: Option<f64>,
+    pub latest_release: Option<ReleaseInfo>,
+    /// Contributors and collaborators; only the GraphQL backend fills this
+    pub contributors: Option<u64>,
+    /// The start of the README, at most README_EXCERPT_CHARS; only the GraphQL backend fills this
+    pub readme: Option<String>,
+    pub source: SourceKind,
+    /// The matching crates.io/PyPI/npm package, once looked up
+    pub package: Option<PackageInfo>,
+    /// When the package was last looked up, found or not
+    pub package_checked_at: Option<f64>,
+}
+
+impl RepoRecord {
+    /// Identity across sources and hosts: the store, history and favorites key. github.com keeps
+    /// the bare full_name it always used; everything else uses host/path so it can't collide with it.
+    pub fn key(&self) -> String {
+        if self.is_public_github() {
+            self.full_name.clone()
+        } else {
+            self.html_url.split_once("://").map_or(self.html_url.as_str(), |(_, rest)| rest).to_string()
+        }
+    }
+
+    /// e.g. github.com, gitlab.com, github.example.com
+    pub fn host(&self) -> &str {
+        host_of(&self.html_url)
+    }
+
+    pub fn is_public_github(&self) -> bool {
+        self.source == SourceKind::GitHub && (self.html_url.is_empty() || self.host() == PUBLIC_HOST)
+    }
+
+    /// Where the repository lives, for link labels: the forge's name on its public instance, the host otherwise.
+    pub fn site_name(&self) -> String {
+        match (self.source, self.host().to_lowercase().as_str()) {
+            (SourceKind::GitHub, _) if self.is_public_github() => "GitHub".to_string(),
+            (SourceKind::GitLab, "gitlab.com") => "GitLab".to_string(),
+            (SourceKind::Gitea, "codeberg.org") => "Codeberg".to_string(),
+            (_, host) => host.to_string(),
+        }
+    }
+
+    /// None when the payload has no full_name to key it by.
+    pub fn from_wire(repo: Repository) -> Option<Self> {
+        let full_name = repo.full_name.filter(|n| !n.is_empty())?;
+        let (owner, short_name) = full_name.split_once('/').unwrap_or(("", &full_name));
+        let time = |s: Option<String>| s.as_deref().and_then(parse_timestamp);
+        let visibility = match repo.visibility.as_deref() {
+            Some("internal") => Visibility::Internal,
+            Some("private") => Visibility::Private,
+            Some(_) => Visibility::Public,
+            None if repo.private.unwrap_or(false) => Visibility::Private,
+            None => Visibility::Public,
+        };
+        Some(Self {
+            id: repo.id.unwrap_or(0),
+            // Enterprise Server ids are only unique per host
+            node_id: match repo.html_url.as_deref().map(host_of) {
+                Some(host) if host != PUBLIC_HOST => repo.node_id.map(|id| format!("{}:{}", host, id)),
+                _ => repo.node_id,
+            },
+            name: repo.name.unwrap_or_else(|| short_name.to_string()),
+            owner: owner.to_string(),
+            html_url: repo.html_url.unwrap_or_else(|| format!("https://github.com/{}", full_name)),
+            description: repo.description.filter(|d| !d.is_empty()),
+            homepage: repo.homepage.filter(|h| !h.is_empty()),
+            language: repo.language,
+            languages: repo.languages.unwrap_or_default(),
+            topics: repo.topics.unwrap_or_default(),
+            license: repo.license.and_then(|l| {
+                Some(LicenseInfo {
+                    name: l.name.or_else(|| l.spdx_id.clone()).or_else(|| l.key.clone())?,
+                    key: l.key,
+                    spdx_id: l.spdx_id,
+                })
+            }),
+            stars: repo.stargazers_count.unwrap_or(0),
+            forks: repo.forks_count.or(repo.forks).unwrap_or(0),
+            watchers: repo.watchers_count.or(repo.watchers).unwrap_or(0),
+            open_issues: repo.open_issues_count.or(repo.open_issues).unwrap_or(0),
+            size: repo.size.unwrap_or(0),
+            fork: repo.fork.unwrap_or(false),
+            archived: repo.archived.unwrap_or(false),
+            disabled: repo.disabled.unwrap_or(false),
+            is_template: repo.is_template.unwrap_or(false),
+            has_issues: repo.has_issues.unwrap_or(false),
+            visibility,
+            default_branch: repo.default_branch,
+            created_at: time(repo.created_at),
+            updated_at: time(repo.updated_at),
+            pushed_at: time(repo.pushed_at),
+            latest_release: repo.latest_release.map(|r| ReleaseInfo {
+                tag: r.tag_name.or(r.name).unwrap_or_default(),
+                published_at: time(r.published_at),
+                url: r.html_url,
+            }),
+            contributors: repo.contributors_count,
+            readme: repo.readme.map(|text| readme_excerpt(&text)).filter(|text| !text.is_empty()),
+            source: SourceKind::GitHub,
+            full_name,
+            ..Default::default()
+        })
+    }
+
+    /// Read a stored or imported value in either layout: a record, or a raw GitHub payload
+    /// (what caches and backups held before records existed).
+    pub fn from_value(value: serde_json::Value) -> Option<Self> {
+        // Records always carry `stars`; GitHub payloads never do
+        if value.get("stars").is_none() {
+            serde_json::from_value::<Repository>(value).ok().and_then(Self::from_wire)
+        } else {
+            serde_json::from_value::<Self>(value).ok().filter(|r| !r.full_name.is_empty())
+        }
+    }
+}
+
+/// Convert a page of wire repositories, dropping any without a name.
+pub fn from_wire_all(repos: Vec<Repository>) -> Vec<RepoRecord> {
+    repos.into_iter().filter_map(RepoRecord::from_wire).collect()
+}
+
+/// The first README_EXCERPT_CHARS characters of a README, trimmed.
+pub fn readme_excerpt(text: &str) -> String {
+    let text = text.trim();
+    match text.char_indices().nth(README_EXCERPT_CHARS) {
+        Some((end, _)) => format!("{}…", text[..end].trim_end()),
+        None => text.to_string(),
+    }
+}
@@ -0,0 +1,314 @@
+// registry.rs - links repositories to their crates.io, PyPI or npm package for versions and download counts
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+#[cfg(target_arch = "wasm32")]
+use ehttp::Mode;
+use serde_json::Value;
+use crate::db::history::DAY_MS;
+use crate::db::cancel::CancelToken;
+use crate::db::http::Http;
+use crate::db::record::RepoRecord;
+use crate::db::sync::check_response;
+use crate::utility::parse_timestamp;
+
+/// Package data older than this is fetched again
+pub const REFRESH_AFTER_MS: f64 = DAY_MS;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Registry {
+    #[default]
+    CratesIo,
+    PyPi,
+    Npm,
+}
+
+impl Registry {
+    /// The registry for one of Waffle's language stores
+    pub fn for_language(language: &str) -> Option<Self> {
+        match language.to_lowercase().as_str() {
+            "rust" => Some(Registry::CratesIo),
+            "python" => Some(Registry::PyPi),
+            "javascript" | "typescript" => Some(Registry::Npm),
+            _ => None,
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Registry::CratesIo => "crates.io",
+            Registry::PyPi => "PyPI",
+            Registry::Npm => "npm",
+        }
+    }
+
+    /// The period `PackageInfo::downloads` covers
+    pub fn downloads_window(&self) -> &'static str {
+        match self {
+            Registry::CratesIo => "90 days",
+            Registry::PyPi | Registry::Npm => "30 days",
+        }
+    }
+
+    pub fn package_url(&self, name: &str) -> String {
+        match self {
+            Registry::CratesIo => format!("https://crates.io/crates/{}", name),
+            Registry::PyPi => format!("https://pypi.org/project/{}/", name),
+            Registry::Npm => format!("https://www.npmjs.com/package/{}", name),
+        }
+    }
+
+    fn metadata_url(&self, name: &str) -> String {
+        match self {
+            Registry::CratesIo => format!("https://crates.io/api/v1/crates/{}", name),
+            Registry::PyPi => format!("https://pypi.org/pypi/{}/json", name),
+            Registry::Npm => format!("https://registry.npmjs.org/{}", name),
+        }
+    }
+
+    /// Separate download-count endpoint; crates.io includes counts in the metadata
+    fn downloads_url(&self, name: &str) -> Option<String> {
+        match self {
+            Registry::CratesIo => None,
+            Registry::PyPi => Some(format!("https://pypistats.org/api/packages/{}/recent", name)),
+            Registry::Npm => Some(format!("https://api.npmjs.org/downloads/point/last-month/{}", name)),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(default)]
+pub struct PackageInfo {
+    pub registry: Registry,
+    pub name: String,
+    pub version: String,
+    /// When `version` was published, in milliseconds
+    pub published_at: Option<f64>,
+    /// Over `Registry::downloads_window`
+    pub downloads: Option<u64>,
+}
+
+/// Registries treat names case-insensitively (PyPI) or require lowercase (npm), so one guess covers all three.
+pub fn candidate_name(repo: &RepoRecord) -> String {
+    repo.name.to_lowercase()
+}
+
+/// Whether a registry's repository/homepage link is this repository, so a same-named
+/// package from someone else is never attached.
+fn points_at(link: &str, repo: &RepoRecord) -> bool {
+    let link = link.to_lowercase();
+    let link = link.trim_start_matches("git+").trim_end_matches('/').trim_end_matches(".git");
+    let target = repo.html_url.to_lowercase();
+    let strip = |url: &str| url.split_once("://").map_or(url.to_string(), |(_, rest)| rest.trim_start_matches("www.").to_string());
+    let (link, target) = (strip(link), strip(&target));
+    // Monorepo packages often link a subdirectory
+    link == target || link.starts_with(&format!("{}/", target)) || link.ends_with(&format!("/{}", repo.full_name.to_lowercase()))
+}
+
+fn strings<'a>(values: impl IntoIterator<Item = &'a Value>) -> Vec<&'a str> {
+    values.into_iter().filter_map(Value::as_str).collect()
+}
+
+/// Package metadata for `repo`, or None when the package doesn't link back to it.
+fn parse_metadata(registry: Registry, name: &str, repo: &RepoRecord, json: &Value) -> Option<PackageInfo> {
+    let (links, version, published) = match registry {
+        Registry::CratesIo => {
+            let krate = &json["crate"];
+            let version = krate["max_stable_version"].as_str().or(krate["max_version"].as_str())?.to_string();
+            let published = json["versions"]
+                .as_array()
+                .and_then(|versions| versions.iter().find(|v| v["num"].as_str() == Some(version.as_str())))
+                .and_then(|v| v["created_at"].as_str())
+                .and_then(parse_timestamp);
+            (strings([&krate["repository"], &krate["homepage"]]), version, published)
+        }
+        Registry::PyPi => {
+            let info = &json["info"];
+            let mut links = strings([&info["home_page"]]);
+            if let Some(urls) = info["project_urls"].as_object() {
+                links.extend(strings(urls.values()));
+            }
+            let published = json["urls"]
+                .as_array()
+                .and_then(|files| files.first())
+                .and_then(|f| f["upload_time_iso_8601"].as_str())
+                .and_then(parse_timestamp);
+            (links, info["version"].as_str()?.to_string(), published)
+        }
+        Registry::Npm => {
+            let version = json["dist-tags"]["latest"].as_str()?.to_string();
+            let published = json["time"][&version].as_str().and_then(parse_timestamp);
+            let links = strings([&json["repository"]["url"], &json["repository"], &json["homepage"], &json["bugs"]["url"]]);
+            (links, version, published)
+        }
+    };
+    if !links.iter().any(|link| points_at(link, repo)) {
+        return None;
+    }
+    let downloads = match registry {
+        Registry::CratesIo => json["crate"]["recent_downloads"].as_u64(),
+        _ => None,
+    };
+    Some(PackageInfo {
+        registry,
+        name: name.to_string(),
+        version,
+        published_at: published,
+        downloads,
+    })
+}
+
+fn parse_downloads(registry: Registry, json: &Value) -> Option<u64> {
+    match registry {
+        Registry::CratesIo => None,
+        Registry::PyPi => json["data"]["last_month"].as_u64(),
+        Registry::Npm => json["downloads"].as_u64(),
+    }
+}
+
+fn get(url: &str) -> ehttp::Request {
+    ehttp::Request {
+        method: String::from("GET"),
+        url: url.to_string(),
+        body: vec![],
+        headers: ehttp::Headers::new(&[("User-Agent", "rust-egui-ehttp-app"), ("Accept", "application/json")]),
+        #[cfg(target_arch = "wasm32")]
+        mode: Mode::Cors,
+    }
+}
+
+/// How a registry lookup came out. Only a definite answer replaces what is stored.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Lookup {
+    Found(PackageInfo),
+    /// The registry has no such package, or it doesn't link back to the repository
+    NoPackage,
+    /// Network error, rate limit, server error or unreadable body: try again next time
+    Failed(String),
+}
+
+/// Ok(None) when the registry says 404, Err when it couldn't answer.
+fn fetch_json<F>(http: &Http, url: &str, done: F)
+where
+    F: FnOnce(Result<Option<Value>, String>) + Send + 'static,
+{
+    http.fetch(get(url), move |result: ehttp::Result<ehttp::Response>| {
+        let response = match result {
+            Ok(response) => response,
+            Err(e) => return done(Err(e)),
+        };
+        if response.status == 404 {
+            return done(Ok(None));
+        }
+        if let Err(e) = check_response(&response) {
+            return done(Err(e));
+        }
+        done(serde_json::from_slice(&response.bytes).map(Some).map_err(|e| format!("Failed to parse JSON: {}", e)));
+    });
+}
+
+/// Look up the package for one repository.
+pub fn fetch_package<F>(http: &Http, registry: Registry, repo: &RepoRecord, done: F)
+where
+    F: FnOnce(Lookup) + Send + 'static,
+{
+    let name = candidate_name(repo);
+    let repo = repo.clone();
+    let downloads_http = http.clone();
+    fetch_json(http, &registry.metadata_url(&name), move |json| {
+        let mut package = match json {
+            Ok(Some(json)) => match parse_metadata(registry, &name, &repo, &json) {
+                Some(package) => package,
+                None => return done(Lookup::NoPackage),
+            },
+            Ok(None) => return done(Lookup::NoPackage),
+            Err(e) => return done(Lookup::Failed(e)),
+        };
+        match registry.downloads_url(&name) {
+            Some(url) => fetch_json(&downloads_http, &url, move |json| match json {
+                Ok(json) => {
+                    package.downloads = json.and_then(|json| parse_downloads(registry, &json));
+                    done(Lookup::Found(package));
+                }
+                Err(e) => done(Lookup::Failed(e)),
+            }),
+            None => done(Lookup::Found(package)),
+        }
+    });
+}
+
+/// Copy package data from the stored records onto freshly synced ones with the same key.
+pub fn carry_over(synced: &mut [RepoRecord], stored: Vec<RepoRecord>) {
+    let stored = stored.into_iter().map(|r| (r.key(), r)).collect::<HashMap<_, _>>();
+    for repo in synced {
+        if let Some(old) = stored.get(&repo.key()) {
+            repo.package = old.package.clone();
+            repo.package_checked_at = old.package_checked_at;
+        }
+    }
+}
+
+/// Whether a record's package data is missing or stale.
+pub fn needs_refresh(repo: &RepoRecord, now: f64) -> bool {
+    repo.package_checked_at.is_none_or(|checked| now - checked > REFRESH_AFTER_MS)
+}
+
+/// Fetch packages for `queue` one at a time (registries ask clients not to burst),
+/// reporting (done, total) through `progress`, then hand the updated records to `done`.
+/// Nothing is handed over once `cancel` fires.
+pub fn enrich<F>(
+    http: Http,
+    cancel: CancelToken,
+    registry: Registry,
+    queue: Vec<RepoRecord>,
+    now: f64,
+    progress: Arc<Mutex<Option<(usize, usize)>>>,
+    done: F,
+) where
+    F: FnOnce(Vec<RepoRecord>) + Send + 'static,
+{
+    let total = queue.len();
+    *progress.lock().unwrap() = Some((0, total));
+    enrich_next(http, cancel, registry, queue.into(), Vec::with_capacity(total), now, progress, done);
+}
+
+#[allow(clippy::too_many_arguments)]
+fn enrich_next<F>(
+    http: Http,
+    cancel: CancelToken,
+    registry: Registry,
+    mut queue: VecDeque<RepoRecord>,
+    mut finished: Vec<RepoRecord>,
+    now: f64,
+    progress: Arc<Mutex<Option<(usize, usize)>>>,
+    done: F,
+) where
+    F: FnOnce(Vec<RepoRecord>) + Send + 'static,
+{
+    // A lookup that failed because of the cancel must not be saved as "no package"
+    if cancel.is_cancelled() {
+        return;
+    }
+    let Some(mut repo) = queue.pop_front() else {
+        *progress.lock().unwrap() = None;
+        return done(finished);
+    };
+    let lookup = repo.clone();
+    fetch_package(&http.clone(), registry, &lookup, move |lookup| {
+        // A failed lookup leaves the record as it was, to be retried on the next run
+        let answer = match lookup {
+            Lookup::Found(package) => Some(Some(package)),
+            Lookup::NoPackage => Some(None),
+            Lookup::Failed(_) => None,
+        };
+        if let Some(package) = answer {
+            repo.package = package;
+            repo.package_checked_at = Some(now);
+            finished.push(repo);
+        }
+        if let Some((count, _)) = progress.lock().unwrap().as_mut() {
+            *count += 1;
+        }
+        enrich_next(http, cancel, registry, queue, finished, now, progress, done);
+    });
+}
@@ -0,0 +1,150 @@
+// saved_search.rs - named searches that can be re-run in one click and report new results
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use crate::db::cloud_sync::{self, SyncDocument, SyncRecord, Tombstone, remote_wins};
+use crate::db::record::RepoRecord;
+use crate::db::idb;
+use crate::db::local::{DocStore, Document};
+use crate::db::query::SearchSpec;
+use crate::utility::now_millis;
+
+pub const SAVED_SEARCH_KIND: &str = "saved_search";
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(default)]
+pub struct SavedSearch {
+    pub name: String,
+    pub spec: SearchSpec,
+    /// Language store the search runs against
+    pub language: String,
+    pub updated_at: f64,
+}
+
+/// What this device last showed for a saved search. Not synced: "new" is relative to
+/// what was seen here.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(default)]
+pub struct SearchView {
+    pub seen: BTreeSet<String>,
+    pub new_count: usize,
+    pub last_viewed_at: f64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(default)]
+pub struct SavedSearches {
+    pub searches: Vec<SavedSearch>,
+    pub views: BTreeMap<String, SearchView>,
+    pub removed: Vec<Tombstone>,
+}
+
+fn result_keys(results: &[RepoRecord]) -> BTreeSet<String> {
+    results.iter().map(RepoRecord::key).collect()
+}
+
+impl SavedSearches {
+    pub fn get(&self, name: &str) -> Option<&SavedSearch> {
+        self.searches.iter().find(|s| s.name == name)
+    }
+
+    /// Save (or overwrite) a search under `name`. Returns false for a blank name.
+    pub fn save(&mut self, name: &str, spec: SearchSpec, language: &str) -> bool {
+        let name = name.trim();
+        if name.is_empty() {
+            return false;
+        }
+        cloud_sync::unbury(&mut self.removed, SAVED_SEARCH_KIND, name);
+        self.searches.retain(|s| s.name != name);
+        self.searches.push(SavedSearch {
+            name: name.to_string(),
+            spec,
+            language: language.to_string(),
+            updated_at: now_millis(),
+        });
+        true
+    }
+
+    pub fn delete(&mut self, name: &str) {
+        if self.get(name).is_some() {
+            self.searches.retain(|s| s.name != name);
+            self.views.remove(name);
+            cloud_sync::bury(&mut self.removed, SAVED_SEARCH_KIND, name, now_millis());
+        }
+    }
+
+    pub fn new_count(&self, name: &str) -> usize {
+        self.views.get(name).map(|v| v.new_count).unwrap_or(0)
+    }
+
+    /// The user just looked at these results: they are no longer new.
+    pub fn mark_viewed(&mut self, name: &str, results: &[RepoRecord]) {
+        self.views.insert(
+            name.to_string(),
+            SearchView {
+                seen: result_keys(results),
+                new_count: 0,
+                last_viewed_at: now_millis(),
+            },
+        );
+    }
+
+    /// Recount results not seen at the last view. A search never viewed on this device
+    /// starts from the current results so the first badge isn't the whole list.
+    pub fn refresh_badge(&mut self, name: &str, results: &[RepoRecord]) {
+        match self.views.get_mut(name) {
+            Some(view) => {
+                view.new_count = result_keys(results).difference(&view.seen).count();
+            }
+            None => self.mark_viewed(name, results),
+        }
+    }
+}
+
+impl Document for SavedSearches {
+    const STORE: &'static str = idb::SAVED_SEARCHES_STORE;
+    const KEY: &'static str = "saved_searches";
+    const LABEL: &'static str = "saved searches";
+}
+
+impl SyncDocument for SavedSearches {
+    const KINDS: &'static [&'static str] = &[SAVED_SEARCH_KIND];
+
+    fn to_sync_records(&self) -> Vec<SyncRecord> {
+        self.searches
+            .iter()
+            .map(|s| SyncRecord {
+                kind: SAVED_SEARCH_KIND.to_string(),
+                key: s.name.clone(),
+                payload: serde_json::to_value(s).unwrap_or_default(),
+                updated_at: s.updated_at,
+                deleted: false,
+                seq: None,
+            })
+            .chain(self.removed.iter().map(Tombstone::to_record))
+            .collect()
+    }
+
+    fn apply_sync_records(&mut self, records: &[SyncRecord]) {
+        for record in records.iter().filter(|r| r.kind == SAVED_SEARCH_KIND) {
+            let key = record.key.as_str();
+            let local = self
+                .get(key)
+                .map(|s| s.updated_at)
+                .or_else(|| cloud_sync::tombstone_at(&self.removed, SAVED_SEARCH_KIND, key));
+            if !remote_wins(local, record) {
+                continue;
+            }
+            self.searches.retain(|s| s.name != key);
+            if record.deleted {
+                self.views.remove(key);
+                cloud_sync::bury(&mut self.removed, SAVED_SEARCH_KIND, key, record.updated_at);
+            } else if let Ok(search) = serde_json::from_value::<SavedSearch>(record.payload.clone()) {
+                cloud_sync::unbury(&mut self.removed, SAVED_SEARCH_KIND, key);
+                self.searches.push(search);
+            }
+        }
+    }
+}
+
+/// Shared handle to the user's saved searches.
+pub type SavedSearchStore = DocStore<SavedSearches>;
@@ -0,0 +1,22 @@
+// settings.rs - device-local preferences that feed into scoring and filtering
+use serde::{Deserialize, Serialize};
+use crate::db::health::HealthWeights;
+use crate::db::idb;
+use crate::db::license_policy::LicensePolicy;
+use crate::db::local::{DocStore, Document};
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(default)]
+pub struct Settings {
+    pub health: HealthWeights,
+    pub license_policy: LicensePolicy,
+}
+
+impl Document for Settings {
+    const STORE: &'static str = idb::SETTINGS_STORE;
+    const KEY: &'static str = "settings";
+    const LABEL: &'static str = "settings";
+}
+
+/// Shared handle to the user's settings.
+pub type SettingsStore = DocStore<Settings>;
@@ -0,0 +1,106 @@
+// source.rs - where repositories come from: one implementation per forge, all producing RepoRecords
+use serde::{Deserialize, Serialize};
+use crate::db::http::Credential;
+use crate::db::record::RepoRecord;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum SourceKind {
+    #[default]
+    GitHub,
+    GitLab,
+    /// Gitea and its forks: Forgejo, Codeberg
+    Gitea,
+}
+
+impl SourceKind {
+    pub const ALL: &'static [SourceKind] = &[SourceKind::GitHub, SourceKind::GitLab, SourceKind::Gitea];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            SourceKind::GitHub => "GitHub",
+            SourceKind::GitLab => "GitLab",
+            SourceKind::Gitea => "Gitea",
+        }
+    }
+
+    /// For `source:` in the search box
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.to_lowercase().as_str() {
+            "forgejo" | "codeberg" => Some(SourceKind::Gitea),
+            s => Self::ALL.iter().copied().find(|k| k.label().eq_ignore_ascii_case(s)),
+        }
+    }
+}
+
+/// Where and how to reach a self-hostable forge. Lives in app storage because of the token.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+#[serde(default)]
+pub struct ForgeConfig {
+    pub base_url: String,
+    /// Personal access token; optional for public repositories
+    pub token: String,
+    /// Only repositories with this topic, when not blank
+    pub topic: String,
+}
+
+impl ForgeConfig {
+    pub fn new(base_url: &str) -> Self {
+        Self {
+            base_url: base_url.to_string(),
+            ..Default::default()
+        }
+    }
+
+    /// The trimmed token, when one is set
+    pub fn token(&self) -> Option<&str> {
+        Some(self.token.trim()).filter(|t| !t.is_empty())
+    }
+}
+
+/// A forge's "most starred repositories in a language" search. Requests and parsing are split
+/// so the sync pipeline can run any source through the same fetch → filter → store path.
+pub trait RepoSource: Send {
+    fn kind(&self) -> SourceKind;
+
+    /// Host the records link to, e.g. github.com or gitlab.example.com
+    fn host(&self) -> String;
+
+    /// Request for the top repositories in `language`, or why this source can't sync right now.
+    fn search_request(&self, language: &str) -> Result<ehttp::Request, String>;
+
+    /// Turn a successful response into records for `language`.
+    fn parse(&self, language: &str, response: &ehttp::Response) -> Result<Vec<RepoRecord>, String>;
+
+    /// The next page to fetch after `response`, given `collected` records kept so far, or None when
+    /// the search is done. Sources whose first page is the whole answer keep the default.
+    fn next_request(&self, _language: &str, _response: &ehttp::Response, _collected: usize) -> Option<ehttp::Request> {
+        None
+    }
+
+    /// Whether records carry license data. The license policy only drops unlicensed
+    /// repositories from sources that report licenses at all.
+    fn reports_licenses(&self) -> bool {
+        true
+    }
+
+    /// The token to send with this source's requests, if one is configured.
+    fn credential(&self) -> Option<Credential> {
+        None
+    }
+}
+
+/// `https://gitlab.example.com/group/project` → `gitlab.example.com`
+pub fn host_of(url: &str) -> &str {
+    let rest = url.split_once("://").map_or(url, |(_, rest)| rest);
+    rest.split('/').next().unwrap_or(rest)
+}
+
+/// `https://host/` or `host` → `https://host`
+pub fn normalize_base_url(url: &str) -> String {
+    let url = url.trim().trim_end_matches('/');
+    if url.starts_with("http://") || url.starts_with("https://") {
+        url.to_string()
+    } else {
+        format!("https://{}", url)
+    }
+}
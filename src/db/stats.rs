@@ -0,0 +1,139 @@
+// stats.rs - per-language aggregates over the cached repositories, for the stats dashboard
+use std::collections::HashMap;
+use crate::db::record::RepoRecord;
+use crate::db::history::DAY_MS;
+
+/// Lower bounds of the star histogram buckets
+pub const STAR_BUCKETS: &[u64] = &[0, 1_000, 5_000, 10_000, 25_000, 50_000, 100_000];
+/// How many licenses get their own pie slice; the rest are folded into "Other"
+pub const TOP_LICENSES: usize = 7;
+pub const TOP_TOPICS: usize = 40;
+pub const TOP_OWNERS: usize = 10;
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Bucket {
+    pub label: String,
+    pub count: usize,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct OwnerStats {
+    pub owner: String,
+    pub repos: usize,
+    pub stars: u64,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct LanguageStats {
+    pub total: usize,
+    pub total_stars: u64,
+    pub star_histogram: Vec<Bucket>,
+    /// (license, repos), largest first
+    pub licenses: Vec<(String, usize)>,
+    /// (topic, repos), largest first
+    pub topics: Vec<(String, usize)>,
+    pub median_age_days: Option<f64>,
+    pub archived_pct: f64,
+    pub fork_pct: f64,
+    pub top_owners: Vec<OwnerStats>,
+}
+
+fn short_stars(n: u64) -> String {
+    if n >= 1_000 {
+        format!("{}k", n / 1_000)
+    } else {
+        n.to_string()
+    }
+}
+
+/// Sort (name, count) pairs by count descending, then name, so ties are stable.
+fn ranked(counts: HashMap<String, usize>) -> Vec<(String, usize)> {
+    let mut ranked = counts.into_iter().collect::<Vec<_>>();
+    ranked.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    ranked
+}
+
+fn percent(part: usize, whole: usize) -> f64 {
+    if whole == 0 {
+        0.0
+    } else {
+        part as f64 * 100.0 / whole as f64
+    }
+}
+
+pub fn median(values: &mut [f64]) -> Option<f64> {
+    if values.is_empty() {
+        return None;
+    }
+    values.sort_by(|a, b| a.total_cmp(b));
+    let mid = values.len() / 2;
+    Some(if values.len() % 2 == 0 { (values[mid - 1] + values[mid]) / 2.0 } else { values[mid] })
+}
+
+impl LanguageStats {
+    pub fn compute(repos: &[RepoRecord], now: f64) -> Self {
+        let total = repos.len();
+        let stars = |r: &RepoRecord| r.stars;
+
+        let star_histogram = STAR_BUCKETS
+            .iter()
+            .enumerate()
+            .map(|(i, &low)| {
+                let high = STAR_BUCKETS.get(i + 1).copied();
+                let count = repos.iter().filter(|r| stars(r) >= low && high.is_none_or(|h| stars(r) < h)).count();
+                let label = match high {
+                    Some(high) => format!("{}-{}", short_stars(low), short_stars(high)),
+                    None => format!("{}+", short_stars(low)),
+                };
+                Bucket { label, count }
+            })
+            .collect();
+
+        let mut licenses = HashMap::new();
+        let mut topics = HashMap::new();
+        let mut owners: HashMap<String, OwnerStats> = HashMap::new();
+        for repo in repos {
+            let license = repo.license.as_ref().map(|l| l.name.clone()).unwrap_or_else(|| "None".to_string());
+            *licenses.entry(license).or_insert(0) += 1;
+            for topic in &repo.topics {
+                *topics.entry(topic.clone()).or_insert(0) += 1;
+            }
+            if !repo.owner.is_empty() {
+                let entry = owners.entry(repo.owner.clone()).or_insert_with(|| OwnerStats {
+                    owner: repo.owner.clone(),
+                    ..Default::default()
+                });
+                entry.repos += 1;
+                entry.stars += stars(repo);
+            }
+        }
+        let mut licenses = ranked(licenses);
+        if licenses.len() > TOP_LICENSES {
+            let other = licenses.split_off(TOP_LICENSES).iter().map(|(_, n)| n).sum();
+            licenses.push(("Other".to_string(), other));
+        }
+        let mut topics = ranked(topics);
+        topics.truncate(TOP_TOPICS);
+        let mut top_owners = owners.into_values().collect::<Vec<_>>();
+        top_owners.sort_by(|a, b| b.repos.cmp(&a.repos).then(b.stars.cmp(&a.stars)).then_with(|| a.owner.cmp(&b.owner)));
+        top_owners.truncate(TOP_OWNERS);
+
+        let mut ages = repos
+            .iter()
+            .filter_map(|r| r.created_at)
+            .map(|created| (now - created) / DAY_MS)
+            .collect::<Vec<_>>();
+
+        LanguageStats {
+            total,
+            total_stars: repos.iter().map(stars).sum(),
+            star_histogram,
+            licenses,
+            topics,
+            median_age_days: median(&mut ages),
+            archived_pct: percent(repos.iter().filter(|r| r.archived).count(), total),
+            fork_pct: percent(repos.iter().filter(|r| r.fork).count(), total),
+            top_owners,
+        }
+    }
+}
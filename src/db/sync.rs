@@ -0,0 +1,292 @@
+// sync.rs - the storage-agnostic half of a sync: fetch from a source, check the response, filter, store
+use crate::db::cancel::{CancelToken, CANCELLED};
+use crate::db::http::Http;
+use crate::db::idb;
+use crate::db::license_policy::LicensePolicy;
+use crate::db::record::RepoRecord;
+use crate::db::registry;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use crate::db::source::{host_of, RepoSource, SourceKind};
+use crate::utility::{format_timestamp, now_millis};
+
+/// Turn a failed response into a readable error; rate limits say when they reset.
+pub fn check_response(response: &ehttp::Response) -> Result<(), String> {
+    if response.ok {
+        return Ok(());
+    }
+    let exhausted = response.headers.get("x-ratelimit-remaining") == Some("0");
+    if exhausted || response.status == 429 {
+        let resets = response
+            .headers
+            .get("x-ratelimit-reset")
+            .and_then(|s| s.trim().parse::<f64>().ok())
+            .map(|secs| format!(", resets at {}", format_timestamp(secs * 1000.0)))
+            .unwrap_or_default();
+        return Err(format!("Rate limited by {}{}", host_of(&response.url), resets));
+    }
+    Err(format!("HTTP Error: {} - {}", response.status, response.status_text))
+}
+
+/// Run one search against `source` through `http`, keeping what the license policy allows.
+/// A 304 is handled like a fresh answer: `ResponseCache` has filled in the body it vouches for,
+/// so the sync is still recorded and today's policy is applied to it.
+pub fn fetch<F>(http: &Http, source: Box<dyn RepoSource>, language: String, policy: LicensePolicy, done: F)
+where
+    F: FnOnce(Result<Vec<RepoRecord>, String>) + Send + 'static,
+{
+    let request = match source.search_request(&language) {
+        Ok(request) => request,
+        Err(e) => return done(Err(e)),
+    };
+    fetch_pages(http.clone(), source, language, policy, request, Vec::new(), done);
+}
+
+/// Fetch `request` and the pages the source asks for after it, adding each page's records to `collected`.
+fn fetch_pages<F>(http: Http, source: Box<dyn RepoSource>, language: String, policy: LicensePolicy, request: ehttp::Request, mut collected: Vec<RepoRecord>, done: F)
+where
+    F: FnOnce(Result<Vec<RepoRecord>, String>) + Send + 'static,
+{
+    http.clone().fetch(request, move |result: ehttp::Result<ehttp::Response>| {
+        let response = match result {
+            Ok(response) => response,
+            Err(e) if e == CANCELLED => return done(Err(e)),
+            Err(e) => return done(Err(format!("Request failed: {}", e))),
+        };
+        if response.status == 304 && response.bytes.is_empty() {
+            return done(Err("Not modified, but there is no cached copy of the results".to_string()));
+        }
+        if let Err(e) = check_response(&response) {
+            return done(Err(e));
+        }
+        let records = match source.parse(&language, &response) {
+            Ok(records) => records,
+            Err(e) => return done(Err(e)),
+        };
+        collected.extend(records.into_iter().filter(|repo| !source.reports_licenses() || policy.keeps(repo)));
+        match source.next_request(&language, &response, collected.len()) {
+            Some(next) => fetch_pages(http, source, language, policy, next, collected, done),
+            None => done(Ok(collected)),
+        }
+    });
+}
+
+/// What the last sync from one source and host wrote to a language store, kept beside its records.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(default)]
+pub struct SyncMeta {
+    pub language: String,
+    pub source: SourceKind,
+    /// Host the records came from, e.g. github.com or gitlab.example.com
+    pub host: String,
+    pub synced_at: f64,
+    /// Records the sync wrote
+    pub count: usize,
+    /// Their keys: the next sync from the same place deletes the ones it no longer returns
+    pub keys: Vec<String>,
+}
+
+impl SyncMeta {
+    /// A sync of `language` from `source`, stamped now; `count` is filled in when it is stored.
+    pub fn new(language: &str, source: &dyn RepoSource) -> Self {
+        Self {
+            language: language.to_string(),
+            source: source.kind(),
+            host: source.host(),
+            synced_at: now_millis(),
+            count: 0,
+            keys: Vec::new(),
+        }
+    }
+
+    /// Where this meta lives in `SYNC_META_STORE`: one per language, source and host, so syncing
+    /// GitLab never forgets what GitHub wrote.
+    pub fn store_key(&self) -> String {
+        format!("{}/{}/{}", self.language, self.source.label(), self.host.to_lowercase())
+    }
+
+    /// Stamp the records this sync is about to write.
+    pub fn written(mut self, records: &[RepoRecord]) -> Self {
+        self.count = records.len();
+        self.keys = records.iter().map(RepoRecord::key).collect();
+        self
+    }
+
+    /// Keys the previous meta for the same place wrote that this one doesn't.
+    /// Anything else in the store (imports, other sources) is not this sync's to delete.
+    pub fn stale(&self, previous: Option<&SyncMeta>) -> Vec<String> {
+        let fresh = self.keys.iter().collect::<HashSet<_>>();
+        previous
+            .map(|p| p.keys.iter().filter(|k| !fresh.contains(k)).cloned().collect())
+            .unwrap_or_default()
+    }
+
+    /// What to leave in place while this sync's records are written: the previous meta, still
+    /// describing the store, but also claiming this sync's keys so that a write cut short between
+    /// the records and the final meta can't orphan them.
+    pub fn pending(&self, previous: Option<&SyncMeta>) -> SyncMeta {
+        let mut pending = previous.cloned().unwrap_or_else(|| SyncMeta { count: 0, keys: Vec::new(), ..self.clone() });
+        let claimed = pending.keys.iter().cloned().collect::<HashSet<_>>();
+        pending.keys.extend(self.keys.iter().filter(|k| !claimed.contains(*k)).cloned());
+        pending
+    }
+
+    /// Whether `repo` came from the same source and host, i.e. is this sync's to replace.
+    pub fn covers(&self, repo: &RepoRecord) -> bool {
+        repo.source == self.source && repo.host().eq_ignore_ascii_case(&self.host)
+    }
+}
+
+/// Where synced records live: IndexedDB in the browser, `NativeStore` files natively.
+#[allow(async_fn_in_trait)] // only ever awaited on the thread that created it
+pub trait RepoCache {
+    /// Every record of a language store
+    async fn records(&self, language: &str) -> Result<Vec<RepoRecord>, String>;
+    /// Write records under their keys, leaving the rest of the store alone
+    async fn put_records(&self, language: &str, records: &[RepoRecord]) -> Result<(), String>;
+    /// Write a whole sync at once: its records, `meta` and a history sample per record, minus the
+    /// records the previous sync from the same source and host wrote that this one no longer has
+    async fn put_snapshot(&self, language: &str, records: &[RepoRecord], meta: &SyncMeta) -> Result<(), String>;
+    /// What the latest sync of a language store wrote, from whichever source, if it has been synced
+    async fn meta(&self, language: &str) -> Result<Option<SyncMeta>, String>;
+    /// Empty a language store and forget its metadata together
+    async fn clear(&self, language: &str) -> Result<(), String>;
+}
+
+impl RepoCache for ::idb::Database {
+    async fn records(&self, language: &str) -> Result<Vec<RepoRecord>, String> {
+        idb::get_all_records(self, language).await.map_err(|e| e.to_string())
+    }
+
+    async fn put_records(&self, language: &str, records: &[RepoRecord]) -> Result<(), String> {
+        let entries = records
+            .iter()
+            .map(|repo| Ok((repo.key(), serde_json::to_value(repo).map_err(|e| e.to_string())?)))
+            .collect::<Result<Vec<_>, String>>()?;
+        idb::put_entries(self, language, &entries, false).await.map_err(|e| e.to_string())
+    }
+
+    async fn put_snapshot(&self, language: &str, records: &[RepoRecord], meta: &SyncMeta) -> Result<(), String> {
+        idb::put_snapshot(self, language, records, meta).await.map_err(|e| e.to_string())
+    }
+
+    async fn meta(&self, language: &str) -> Result<Option<SyncMeta>, String> {
+        let entries = idb::get_all_entries(self, idb::SYNC_META_STORE).await.map_err(|e| e.to_string())?;
+        Ok(latest(language, entries.into_iter().map(|(_, value)| value)))
+    }
+
+    async fn clear(&self, language: &str) -> Result<(), String> {
+        idb::clear_language(self, language).await.map_err(|e| e.to_string())
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl RepoCache for crate::db::native::NativeStore {
+    async fn records(&self, language: &str) -> Result<Vec<RepoRecord>, String> {
+        crate::db::native::NativeStore::records(self, language).map_err(|e| e.to_string())
+    }
+
+    async fn put_records(&self, language: &str, records: &[RepoRecord]) -> Result<(), String> {
+        let entries = records
+            .iter()
+            .map(|repo| Ok((repo.key(), serde_json::to_value(repo).map_err(|e| e.to_string())?)))
+            .collect::<Result<_, String>>()?;
+        self.put_entries(language, &entries).map_err(|e| e.to_string())
+    }
+
+    /// Files are written one at a time, so the meta is written twice: first claiming both the previous
+    /// and the new keys, then, once the records and history are in place, the new keys alone. Whatever
+    /// step is cut short, the next sync still deletes everything it no longer returns.
+    async fn put_snapshot(&self, language: &str, records: &[RepoRecord], meta: &SyncMeta) -> Result<(), String> {
+        let previous = self.get::<SyncMeta>(idb::SYNC_META_STORE, &meta.store_key()).map_err(|e| e.to_string())?;
+        let mut entries = self.entries(language).map_err(|e| e.to_string())?;
+        for key in meta.stale(previous.as_ref()) {
+            entries.remove(&key);
+        }
+        for repo in records {
+            entries.insert(repo.key(), serde_json::to_value(repo).map_err(|e| e.to_string())?);
+        }
+        self.put(idb::SYNC_META_STORE, &meta.store_key(), &meta.pending(previous.as_ref())).map_err(|e| e.to_string())?;
+        self.replace_entries(language, &entries).map_err(|e| e.to_string())?;
+        let mut histories = self.entries(idb::HISTORY_STORE).map_err(|e| e.to_string())?;
+        for repo in records {
+            let stored = histories.remove(&repo.key()).and_then(|value| serde_json::from_value(value).ok());
+            let history = crate::db::history::with_sample(stored, repo, meta.synced_at);
+            histories.insert(repo.key(), serde_json::to_value(history).map_err(|e| e.to_string())?);
+        }
+        self.replace_entries(idb::HISTORY_STORE, &histories).map_err(|e| e.to_string())?;
+        self.put(idb::SYNC_META_STORE, &meta.store_key(), meta).map_err(|e| e.to_string())
+    }
+
+    async fn meta(&self, language: &str) -> Result<Option<SyncMeta>, String> {
+        let entries = self.entries(idb::SYNC_META_STORE).map_err(|e| e.to_string())?;
+        Ok(latest(language, entries.into_values()))
+    }
+
+    async fn clear(&self, language: &str) -> Result<(), String> {
+        crate::db::native::NativeStore::clear(self, language).map_err(|e| e.to_string())?;
+        let mut metas = self.entries(idb::SYNC_META_STORE).map_err(|e| e.to_string())?;
+        metas.retain(|_, value| value.get("language").and_then(|l| l.as_str()) != Some(language));
+        self.replace_entries(idb::SYNC_META_STORE, &metas).map_err(|e| e.to_string())
+    }
+}
+
+/// The most recent of a language's metas, whichever source wrote it.
+fn latest(language: &str, metas: impl Iterator<Item = serde_json::Value>) -> Option<SyncMeta> {
+    metas
+        .filter_map(|value| serde_json::from_value::<SyncMeta>(value).ok())
+        .filter(|meta| meta.language == language)
+        .max_by(|a, b| a.synced_at.total_cmp(&b.synced_at))
+}
+
+/// Write a sync's records as one snapshot, keeping the package data enrichment found for them earlier.
+/// Nothing is written once `cancel` fires.
+pub async fn store<C: RepoCache>(cache: &C, mut meta: SyncMeta, mut records: Vec<RepoRecord>, cancel: &CancelToken) -> Result<Vec<RepoRecord>, String> {
+    // Sources know nothing about packages
+    let stored = cache.records(&meta.language).await.unwrap_or_default();
+    registry::carry_over(&mut records, stored);
+    cancel.check()?;
+    meta = meta.written(&records);
+    cache.put_snapshot(&meta.language, &records, &meta).await?;
+    Ok(records)
+}
+
+/// Write back what enrichment looked up: only `package` and `package_checked_at`, and only onto records
+/// the store still has, so a sync that replaced the store meanwhile keeps its fresh numbers.
+pub async fn store_packages<C: RepoCache>(cache: &C, language: &str, looked_up: Vec<RepoRecord>, cancel: &CancelToken) -> Result<(), String> {
+    let mut looked_up = looked_up.into_iter().map(|repo| (repo.key(), repo)).collect::<HashMap<_, _>>();
+    let merged = cache
+        .records(language)
+        .await?
+        .into_iter()
+        .filter_map(|mut repo| {
+            let found = looked_up.remove(&repo.key())?;
+            repo.package = found.package;
+            repo.package_checked_at = found.package_checked_at;
+            Some(repo)
+        })
+        .collect::<Vec<_>>();
+    cancel.check()?;
+    cache.put_records(language, &merged).await
+}
+
+/// What a language store holds, for the empty check.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct StoreStatus {
+    /// Readable records, whether synced or imported
+    pub count: usize,
+    pub meta: Option<SyncMeta>,
+}
+
+impl StoreStatus {
+    pub fn is_empty(&self) -> bool {
+        self.count == 0
+    }
+}
+
+pub async fn status<C: RepoCache>(cache: &C, language: &str) -> Result<StoreStatus, String> {
+    Ok(StoreStatus {
+        count: cache.records(language).await?.len(),
+        meta: cache.meta(language).await?,
+    })
+}
@@ -0,0 +1,270 @@
+// sync_report.rs - what changed between a language's last two syncs, for the "What changed" panel
+use ::idb::{Database, Error};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use crate::db::record::RepoRecord;
+use crate::db::idb;
+use crate::db::sync::SyncMeta;
+use crate::utility::format_date;
+
+/// Size of the leaderboard whose entries and exits are reported
+pub const TOP_N: usize = 25;
+/// A star change counts as a jump when it is at least this many stars...
+pub const STAR_JUMP_MIN: i64 = 250;
+/// ...or at least this fraction of the previous count (with a floor of 50 stars).
+pub const STAR_JUMP_RATIO: f64 = 0.10;
+
+/// The handful of fields a report compares, kept per sync so the next one has a baseline.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(default)]
+pub struct RepoSnapshot {
+    pub full_name: String,
+    pub html_url: Option<String>,
+    pub stars: u64,
+    pub archived: bool,
+    pub license: Option<String>,
+    pub description: Option<String>,
+}
+
+impl RepoSnapshot {
+    pub fn from_repo(repo: &RepoRecord) -> Self {
+        Self {
+            full_name: repo.key(),
+            html_url: Some(repo.html_url.clone()),
+            stars: repo.stars,
+            archived: repo.archived,
+            license: repo.license.as_ref().map(|l| l.name.clone()),
+            description: repo.description.clone(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(default)]
+pub struct SyncSnapshot {
+    pub at: f64,
+    pub repos: Vec<RepoSnapshot>,
+}
+
+impl SyncSnapshot {
+    pub fn new(repos: &[RepoRecord], at: f64) -> Self {
+        Self {
+            at,
+            repos: repos.iter().map(RepoSnapshot::from_repo).collect(),
+        }
+    }
+
+    /// The top `n` repositories by stars, best first
+    fn top(&self, n: usize) -> Vec<&RepoSnapshot> {
+        let mut ranked = self.repos.iter().collect::<Vec<_>>();
+        ranked.sort_by(|a, b| b.stars.cmp(&a.stars).then_with(|| a.full_name.cmp(&b.full_name)));
+        ranked.truncate(n);
+        ranked
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(default)]
+pub struct RankChange {
+    pub full_name: String,
+    pub html_url: Option<String>,
+    /// 1-based rank in the sync the repository was in the top-N for
+    pub rank: usize,
+    pub stars: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(default)]
+pub struct StarJump {
+    pub full_name: String,
+    pub html_url: Option<String>,
+    pub before: u64,
+    pub after: u64,
+}
+
+impl StarJump {
+    pub fn delta(&self) -> i64 {
+        self.after as i64 - self.before as i64
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(default)]
+pub struct FieldChange {
+    pub full_name: String,
+    pub html_url: Option<String>,
+    pub before: Option<String>,
+    pub after: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(default)]
+pub struct SyncReport {
+    pub language: String,
+    /// When the baseline sync ran; None for a language's first sync
+    pub previous_at: Option<f64>,
+    pub at: f64,
+    pub entered_top: Vec<RankChange>,
+    pub left_top: Vec<RankChange>,
+    pub star_jumps: Vec<StarJump>,
+    pub newly_archived: Vec<String>,
+    pub license_changes: Vec<FieldChange>,
+    pub description_changes: Vec<FieldChange>,
+}
+
+fn is_jump(before: u64, after: u64) -> bool {
+    let delta = (after as i64 - before as i64).abs();
+    delta >= STAR_JUMP_MIN || (delta >= 50 && delta as f64 >= before as f64 * STAR_JUMP_RATIO)
+}
+
+fn rank_changes(from: &[&RepoSnapshot], other: &[&RepoSnapshot]) -> Vec<RankChange> {
+    from.iter()
+        .enumerate()
+        .filter(|(_, r)| other.iter().all(|o| o.full_name != r.full_name))
+        .map(|(i, r)| RankChange {
+            full_name: r.full_name.clone(),
+            html_url: r.html_url.clone(),
+            rank: i + 1,
+            stars: r.stars,
+        })
+        .collect()
+}
+
+impl SyncReport {
+    /// Compare the current sync with the previous one. Without a baseline the report is empty.
+    pub fn compute(language: &str, previous: Option<&SyncSnapshot>, current: &SyncSnapshot) -> Self {
+        let mut report = SyncReport {
+            language: language.to_string(),
+            previous_at: previous.map(|p| p.at),
+            at: current.at,
+            ..Default::default()
+        };
+        let Some(previous) = previous else {
+            return report;
+        };
+        let (old_top, new_top) = (previous.top(TOP_N), current.top(TOP_N));
+        report.entered_top = rank_changes(&new_top, &old_top);
+        report.left_top = rank_changes(&old_top, &new_top);
+
+        let before = previous.repos.iter().map(|r| (r.full_name.as_str(), r)).collect::<HashMap<_, _>>();
+        for now in &current.repos {
+            let Some(then) = before.get(now.full_name.as_str()) else {
+                continue;
+            };
+            let change = |before: &Option<String>, after: &Option<String>| FieldChange {
+                full_name: now.full_name.clone(),
+                html_url: now.html_url.clone(),
+                before: before.clone(),
+                after: after.clone(),
+            };
+            if is_jump(then.stars, now.stars) {
+                report.star_jumps.push(StarJump {
+                    full_name: now.full_name.clone(),
+                    html_url: now.html_url.clone(),
+                    before: then.stars,
+                    after: now.stars,
+                });
+            }
+            if now.archived && !then.archived {
+                report.newly_archived.push(now.full_name.clone());
+            }
+            if now.license != then.license {
+                report.license_changes.push(change(&then.license, &now.license));
+            }
+            if now.description != then.description {
+                report.description_changes.push(change(&then.description, &now.description));
+            }
+        }
+        report.star_jumps.sort_by_key(|j| std::cmp::Reverse(j.delta().abs()));
+        report
+    }
+
+    pub fn change_count(&self) -> usize {
+        self.entered_top.len()
+            + self.left_top.len()
+            + self.star_jumps.len()
+            + self.newly_archived.len()
+            + self.license_changes.len()
+            + self.description_changes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.change_count() == 0
+    }
+
+    /// Newsletter-ready summary
+    pub fn to_markdown(&self) -> String {
+        fn link(name: &str, url: &Option<String>) -> String {
+            match url {
+                Some(url) => format!("[{}]({})", name, url),
+                None => name.to_string(),
+            }
+        }
+        fn text(value: &Option<String>) -> String {
+            value.as_deref().map(|v| format!("\"{}\"", v.replace('\n', " "))).unwrap_or_else(|| "none".to_string())
+        }
+        let mut out = match self.previous_at {
+            Some(previous) => format!("## What changed in {}: {} → {}\n", self.language, format_date(previous), format_date(self.at)),
+            None => format!("## {} first synced {}\n", self.language, format_date(self.at)),
+        };
+        if self.previous_at.is_some() && self.is_empty() {
+            out.push_str("\nNothing changed since the previous sync.\n");
+        }
+        let mut section = |title: &str, lines: Vec<String>| {
+            if !lines.is_empty() {
+                out.push_str(&format!("\n### {}\n\n", title));
+                for line in lines {
+                    out.push_str(&format!("- {}\n", line));
+                }
+            }
+        };
+        section(
+            &format!("New in the top {}", TOP_N),
+            self.entered_top.iter().map(|r| format!("#{} {} (⭐ {})", r.rank, link(&r.full_name, &r.html_url), r.stars)).collect(),
+        );
+        section(
+            &format!("Dropped out of the top {}", TOP_N),
+            self.left_top.iter().map(|r| format!("{} (was #{})", link(&r.full_name, &r.html_url), r.rank)).collect(),
+        );
+        section(
+            "Star jumps",
+            self.star_jumps.iter().map(|j| format!("{} {:+} (⭐ {} → {})", link(&j.full_name, &j.html_url), j.delta(), j.before, j.after)).collect(),
+        );
+        section("Newly archived", self.newly_archived.clone());
+        section(
+            "License changes",
+            self.license_changes.iter().map(|c| format!("{}: {} → {}", link(&c.full_name, &c.html_url), text(&c.before), text(&c.after))).collect(),
+        );
+        section(
+            "Description changes",
+            self.description_changes.iter().map(|c| format!("{}: {} → {}", link(&c.full_name, &c.html_url), text(&c.before), text(&c.after))).collect(),
+        );
+        out
+    }
+}
+
+/// One baseline per source and host: a GitLab sync of the same language diffs against the last
+/// GitLab sync, not against GitHub's records.
+fn snapshot_key(meta: &SyncMeta) -> String {
+    format!("snapshot_{}", meta.store_key().to_lowercase())
+}
+
+fn report_key(language: &str) -> String {
+    format!("report_{}", language.to_lowercase())
+}
+
+/// Diff the records the sync described by `meta` wrote against that source's stored snapshot,
+/// then make them its new baseline. The language keeps one report, the latest.
+pub async fn record_report(db: &Database, meta: &SyncMeta, repos: &[RepoRecord]) -> Result<SyncReport, Error> {
+    let key = snapshot_key(meta);
+    let previous = idb::get_repo::<SyncSnapshot>(db, idb::REPORTS_STORE, &key).await?;
+    let current = SyncSnapshot::new(repos, meta.synced_at);
+    let report = SyncReport::compute(&meta.language, previous.as_ref(), &current);
+    idb::add_repo(db, idb::REPORTS_STORE, &key, &current).await?;
+    idb::add_repo(db, idb::REPORTS_STORE, &report_key(&meta.language), &report).await?;
+    Ok(report)
+}
+
+pub async fn load_report(db: &Database, language: &str) -> Result<Option<SyncReport>, Error> {
+    idb::get_repo::<SyncReport>(db, idb::REPORTS_STORE, &report_key(language)).await
+}
@@ -1,4 +1,5 @@
-use crate::db::github::Repository;
+use std::sync::Arc;
+use crate::db::record::RepoRecord;
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum AppState {
@@ -13,7 +14,8 @@ pub enum AppState {
 #[derive(Debug, Clone)]
 pub struct WaffleState {
     pub app_state: AppState,
-    pub filtered_repos: Vec<Repository>,
+    /// Shared with the search widget and the result list rather than copied every frame
+    pub filtered_repos: Arc<[RepoRecord]>,
     pub log: String,
 }
 
@@ -21,21 +23,21 @@ impl WaffleState {
     pub fn new() -> Self {
         Self {
             app_state: AppState::Init,
-            filtered_repos: Vec::new(),
+            filtered_repos: Arc::default(),
             log: String::new(),
         }
     }
 
     pub fn set_empty(&mut self) {
         self.app_state = AppState::Empty;
-        self.filtered_repos.clear();
+        self.filtered_repos = Arc::default();
     }
 
     pub fn set_syncing(&mut self) {
         self.app_state = AppState::Syncing;
     }
 
-    pub fn set_ready(&mut self, repos: Vec<Repository>) {
+    pub fn set_ready(&mut self, repos: Arc<[RepoRecord]>) {
         if repos.is_empty() {
             self.set_empty();
         } else {
@@ -47,7 +49,7 @@ impl WaffleState {
     pub fn set_error(&mut self, msg: String) {
         self.app_state = AppState::Error(msg.clone());
         self.log.push_str(&format!("Error: {}\n", msg));
-        self.filtered_repos.clear();
+        self.filtered_repos = Arc::default();
     }
 
     pub fn log(&mut self, msg: &str) {
@@ -0,0 +1,175 @@
+// backup.rs - window to download a full backup and restore one by merge or replace
+use egui::{Context, DroppedFile, LayerId};
+use std::sync::{Arc, Mutex};
+use crate::db::backup::{self, Backup, RestoreMode};
+use crate::db::export::{self, ExportFormat};
+use crate::utility::{format_date, now_millis, read_dropped_file};
+
+pub struct BackupWidget {
+    pub open: bool,
+    pub mode: RestoreMode,
+    /// Replace wipes local data, so it needs an explicit confirmation
+    pub confirm_replace: bool,
+    /// Native only: where backups are written and read
+    pub path: String,
+    status: Arc<Mutex<Option<String>>>,
+    restored: Arc<Mutex<bool>>,
+    layer: Option<LayerId>,
+}
+
+impl Default for BackupWidget {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl BackupWidget {
+    pub fn new() -> Self {
+        Self {
+            open: false,
+            mode: RestoreMode::Merge,
+            confirm_replace: false,
+            path: String::new(),
+            status: Arc::new(Mutex::new(None)),
+            restored: Arc::new(Mutex::new(false)),
+            layer: None,
+        }
+    }
+
+    /// True once after a restore wrote to the stores; everything in memory should reload.
+    pub fn take_restored(&self) -> bool {
+        std::mem::take(&mut *self.restored.lock().unwrap())
+    }
+
+    fn target_path(&self, at: f64) -> String {
+        let default = export::file_name(&format!("backup_{}", format_date(at)), ExportFormat::Json);
+        if self.path.trim().is_empty() || cfg!(target_arch = "wasm32") {
+            default
+        } else {
+            self.path.trim().to_string()
+        }
+    }
+
+    fn create(&self) {
+        let at = now_millis();
+        let path = self.target_path(at);
+        let status = Arc::clone(&self.status);
+        let finish = move |result: Result<Backup, String>| {
+            let outcome = result
+                .and_then(|b| b.to_json().map(|json| (b, json)))
+                .and_then(|(b, json)| export::deliver(&path, &json, ExportFormat::Json).map(|done| (b, done)));
+            *status.lock().unwrap() = Some(match outcome {
+                Ok((backup, done)) => {
+                    let records = backup.manifest.stores.iter().map(|s| s.records).sum::<usize>();
+                    format!("{}: {} records from {} stores", done, records, backup.manifest.stores.len())
+                }
+                Err(e) => format!("Backup failed: {}", e),
+            });
+        };
+        #[cfg(target_arch = "wasm32")]
+        wasm_bindgen_futures::spawn_local(async move {
+            let result = match crate::db::idb::open_waffle_db().await {
+                Ok(db) => backup::create(&db, at).await.map_err(|e| e.to_string()),
+                Err(e) => Err(format!("Failed to open IndexedDB: {}", e)),
+            };
+            finish(result);
+        });
+        #[cfg(not(target_arch = "wasm32"))]
+        finish(backup::create(&crate::db::native::NativeStore::open_default(), at).map_err(|e| e.to_string()));
+    }
+
+    fn restore(&self, source: &str, text: &str) {
+        let backup = match Backup::parse(text) {
+            Ok(backup) => backup,
+            Err(e) => {
+                *self.status.lock().unwrap() = Some(format!("{}: {}", source, e));
+                return;
+            }
+        };
+        let mode = self.mode;
+        let status = Arc::clone(&self.status);
+        let restored = Arc::clone(&self.restored);
+        let source = format!("{} (made {})", source, format_date(backup.manifest.created_at));
+        let finish = move |result: Result<backup::RestoreSummary, String>| {
+            *status.lock().unwrap() = Some(match result {
+                Ok(summary) => {
+                    *restored.lock().unwrap() = true;
+                    format!("{}: {}", source, summary.describe())
+                }
+                Err(e) => format!("Restore of {} failed: {}", source, e),
+            });
+        };
+        #[cfg(target_arch = "wasm32")]
+        wasm_bindgen_futures::spawn_local(async move {
+            let result = match crate::db::idb::open_waffle_db().await {
+                Ok(db) => backup::restore(&db, &backup, mode).await.map_err(|e| e.to_string()),
+                Err(e) => Err(format!("Failed to open IndexedDB: {}", e)),
+            };
+            finish(result);
+        });
+        #[cfg(not(target_arch = "wasm32"))]
+        finish(backup::restore(&crate::db::native::NativeStore::open_default(), &backup, mode).map_err(|e| e.to_string()));
+    }
+
+    pub fn show(&mut self, ctx: &Context) {
+        let mut open = self.open;
+        let response = egui::Window::new("Backup & Restore").open(&mut open).resizable(false).show(ctx, |ui| {
+            ui.label("One file with every language cache, favorites, notes, saved searches, settings and sync state.");
+            if !cfg!(target_arch = "wasm32") {
+                ui.horizontal(|ui| {
+                    ui.label("Path:");
+                    ui.text_edit_singleline(&mut self.path)
+                        .on_hover_text("Leave empty to back up to a dated file in the working directory");
+                });
+            }
+            if ui.button("Create backup").clicked() {
+                self.create();
+            }
+            ui.separator();
+            ui.horizontal(|ui| {
+                ui.radio_value(&mut self.mode, RestoreMode::Merge, "Merge")
+                    .on_hover_text("Keep local data; records in the backup win on conflicts");
+                ui.radio_value(&mut self.mode, RestoreMode::Replace, "Replace")
+                    .on_hover_text("Erase local data first so it matches the backup exactly");
+            });
+            let allowed = self.mode == RestoreMode::Merge || self.confirm_replace;
+            if self.mode == RestoreMode::Replace {
+                ui.checkbox(&mut self.confirm_replace, "I understand local data will be erased");
+            }
+            if cfg!(target_arch = "wasm32") {
+                ui.label(if allowed { "Drop a backup file on the window to restore it." } else { "Confirm to enable restoring." });
+            } else if ui.add_enabled(allowed && !self.path.trim().is_empty(), egui::Button::new("Restore from path")).clicked() {
+                let path = self.path.trim().to_string();
+                match std::fs::read_to_string(&path) {
+                    Ok(text) => self.restore(&path, &text),
+                    Err(e) => *self.status.lock().unwrap() = Some(format!("Could not read {}: {}", path, e)),
+                }
+            }
+            if let Some(status) = self.status.lock().unwrap().as_ref() {
+                ui.label(status);
+            }
+        });
+        self.open = open;
+        self.layer = response.map(|r| r.response.layer_id);
+    }
+
+    /// The window's layer while it is open, so the app can tell whether it is on top.
+    pub fn layer(&self) -> Option<LayerId> {
+        self.layer
+    }
+
+    /// Restore from files dropped on the window; the app hands them here when this window owns the drop.
+    pub fn drop_files(&self, files: Vec<DroppedFile>) {
+        if self.mode == RestoreMode::Replace && !self.confirm_replace {
+            *self.status.lock().unwrap() = Some("Confirm replacing local data before restoring.".to_string());
+            return;
+        }
+        for file in files {
+            let (name, text) = read_dropped_file(&file);
+            match text {
+                Ok(text) => self.restore(&name, &text),
+                Err(e) => *self.status.lock().unwrap() = Some(format!("Could not read {}: {}", name, e)),
+            }
+        }
+    }
+}
@@ -0,0 +1,87 @@
+// collections.rs - side panel view of starred repositories and named collections
+use egui::Ui;
+use crate::db::favorites::FavoritesStore;
+
+pub struct CollectionsWidget {
+    pub new_collection: String,
+    pub error: Option<String>,
+}
+
+impl Default for CollectionsWidget {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CollectionsWidget {
+    pub fn new() -> Self {
+        Self {
+            new_collection: String::new(),
+            error: None,
+        }
+    }
+
+    pub fn show(&mut self, ui: &mut Ui, store: &FavoritesStore) {
+        let favorites = store.get();
+        egui::CollapsingHeader::new(format!("Starred ({})", favorites.starred.len()))
+            .id_salt("waffle_starred")
+            .show(ui, |ui| {
+                if favorites.starred.is_empty() {
+                    ui.label("Star a repository to keep it here.");
+                }
+                for fav in &favorites.starred {
+                    ui.horizontal(|ui| {
+                        if ui.small_button("★").on_hover_text("Unstar").clicked() {
+                            store.update(|f| f.unstar(&fav.full_name));
+                        }
+                        ui.hyperlink_to(&fav.full_name, fav.html_url.as_deref().unwrap_or("#"));
+                    });
+                }
+            });
+
+        egui::CollapsingHeader::new(format!("Collections ({})", favorites.collections.len()))
+            .id_salt("waffle_collections")
+            .show(ui, |ui| {
+                ui.horizontal(|ui| {
+                    ui.text_edit_singleline(&mut self.new_collection);
+                    if ui.button("New").clicked() {
+                        let name = self.new_collection.clone();
+                        if store.update(|f| f.create_collection(&name)) {
+                            self.new_collection.clear();
+                            self.error = None;
+                        } else {
+                            self.error = Some("Collection name is empty or already in use".to_string());
+                        }
+                    }
+                });
+                if let Some(err) = &self.error {
+                    ui.colored_label(egui::Color32::RED, err);
+                }
+                for collection in &favorites.collections {
+                    let name = collection.name.clone();
+                    egui::CollapsingHeader::new(format!("{} ({})", name, collection.repos.len()))
+                        .id_salt(("waffle_collection", &name))
+                        .show(ui, |ui| {
+                            let len = collection.repos.len();
+                            for (index, full_name) in collection.repos.iter().enumerate() {
+                                ui.horizontal(|ui| {
+                                    if ui.add_enabled(index > 0, egui::Button::new("⏶").small()).clicked() {
+                                        store.update(|f| f.move_in_collection(&name, index, index - 1));
+                                    }
+                                    if ui.add_enabled(index + 1 < len, egui::Button::new("⏷").small()).clicked() {
+                                        store.update(|f| f.move_in_collection(&name, index, index + 1));
+                                    }
+                                    if ui.small_button("✖").on_hover_text("Remove from collection").clicked() {
+                                        store.update(|f| f.remove_from_collection(&name, full_name));
+                                    }
+                                    ui.label(full_name);
+                                });
+                            }
+                            if ui.button("Delete collection").clicked() {
+                                store.update(|f| f.delete_collection(&name));
+                            }
+                        });
+                }
+            });
+    }
+}
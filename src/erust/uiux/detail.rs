@@ -0,0 +1,225 @@
+// detail.rs - right-hand pane with everything we know about the selected repository
+use egui::{Id, Ui};
+use egui_plot::{Line, Plot, PlotPoints};
+use crate::db::record::{RepoRecord, Visibility};
+use crate::db::history::{self, DAY_MS, Metric, RepoHistory};
+use crate::db::idb;
+use crate::db::notes::NotesStore;
+use crate::utility::{format_age, format_date, now_millis};
+
+pub struct DetailPane {
+    pub note_draft: String,
+    pub tag_draft: String,
+    /// node_id the drafts were loaded for, so switching repos resets them
+    loaded_for: Option<String>,
+    pub metric: Metric,
+    /// full_name whose history was last requested
+    history_for: Option<String>,
+}
+
+impl Default for DetailPane {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DetailPane {
+    pub fn new() -> Self {
+        Self {
+            note_draft: String::new(),
+            tag_draft: String::new(),
+            loaded_for: None,
+            metric: Metric::Stars,
+            history_for: None,
+        }
+    }
+
+    fn show_history(&mut self, ui: &mut Ui, full_name: &str) {
+        let history_id = Id::new("waffle_detail_history");
+        if self.history_for.as_deref() != Some(full_name) {
+            self.history_for = Some(full_name.to_string());
+            let full_name = full_name.to_string();
+            let ctx = ui.ctx().clone();
+            wasm_bindgen_futures::spawn_local(async move {
+                let loaded = match idb::open_waffle_db().await {
+                    Ok(db) => history::load_history(&db, &full_name).await.ok().flatten(),
+                    Err(_) => None,
+                };
+                let loaded = loaded.unwrap_or_else(|| RepoHistory::new(&full_name));
+                ctx.data_mut(|d| d.insert_temp(history_id, loaded));
+                ctx.request_repaint();
+            });
+        }
+        let history = ui
+            .ctx()
+            .data(|d| d.get_temp::<RepoHistory>(history_id))
+            .filter(|h| h.full_name == full_name);
+
+        ui.horizontal(|ui| {
+            for metric in Metric::CHARTED {
+                ui.selectable_value(&mut self.metric, *metric, metric.label());
+            }
+        });
+        let Some(history) = history.filter(|h| !h.samples.is_empty()) else {
+            ui.label("No history yet; every sync adds a data point.");
+            return;
+        };
+        let now = now_millis();
+        ui.horizontal(|ui| {
+            for days in [7.0, 30.0] {
+                match history.change_since(self.metric, now - days * DAY_MS) {
+                    Some(change) => ui.label(format!("{:+} in {} days", change, days)),
+                    None => ui.label(format!("n/a in {} days", days)),
+                };
+            }
+        });
+        let points = history
+            .series(self.metric)
+            .into_iter()
+            .map(|(at, value)| [at / DAY_MS, value])
+            .collect::<Vec<_>>();
+        Plot::new(("waffle_history_plot", full_name))
+            .height(180.0)
+            .allow_scroll(false)
+            .x_axis_formatter(|mark, _range| format_date(mark.value * DAY_MS))
+            .label_formatter(|_name, point| format!("{}\n{:.0}", format_date(point.x * DAY_MS), point.y))
+            .show(ui, |plot_ui| {
+                plot_ui.line(Line::new(self.metric.label(), PlotPoints::from(points)));
+            });
+    }
+
+    pub fn show(&mut self, ui: &mut Ui, repo: &RepoRecord, readme: Option<&str>, notes: &NotesStore) {
+        ui.heading(&repo.full_name);
+        ui.hyperlink_to(format!("Open on {}", repo.site_name()), &repo.html_url);
+        if let Some(desc) = &repo.description {
+            ui.label(desc);
+        }
+        ui.separator();
+        egui::Grid::new("waffle_detail_grid").num_columns(2).show(ui, |ui| {
+            ui.label("Stars");
+            ui.label(repo.stars.to_string());
+            ui.end_row();
+            ui.label("Forks");
+            ui.label(repo.forks.to_string());
+            ui.end_row();
+            ui.label("Open issues");
+            ui.label(repo.open_issues.to_string());
+            ui.end_row();
+            if let Some(contributors) = repo.contributors {
+                ui.label("Contributors");
+                ui.label(contributors.to_string());
+                ui.end_row();
+            }
+            ui.label("License");
+            ui.label(repo.license.as_ref().map(|l| l.name.as_str()).unwrap_or("None"));
+            ui.end_row();
+            ui.label("SPDX");
+            ui.label(crate::db::license_policy::license_id(repo).unwrap_or_else(|| "None".to_string()));
+            ui.end_row();
+            ui.label("Last push");
+            ui.label(repo.pushed_at.map(format_date).unwrap_or_else(|| "Unknown".to_string()));
+            ui.end_row();
+            if repo.visibility != Visibility::Public {
+                ui.label("Visibility");
+                ui.label(repo.visibility.label());
+                ui.end_row();
+            }
+            if repo.archived {
+                ui.label("Status");
+                ui.label("Archived");
+                ui.end_row();
+            }
+            if let Some(package) = &repo.package {
+                ui.label(package.registry.label());
+                ui.hyperlink_to(format!("{} {}", package.name, package.version), package.registry.package_url(&package.name));
+                ui.end_row();
+                if let Some(published) = package.published_at {
+                    ui.label("Published");
+                    ui.label(format!("{} ({})", format_date(published), format_age(published, now_millis())));
+                    ui.end_row();
+                }
+                if let Some(downloads) = package.downloads {
+                    ui.label("Downloads");
+                    ui.label(format!("{} in the last {}", downloads, package.registry.downloads_window()));
+                    ui.end_row();
+                }
+            }
+            if let Some(release) = &repo.latest_release {
+                ui.label("Latest release");
+                let date = release.published_at.map(|d| format!(" ({})", format_date(d))).unwrap_or_default();
+                match &release.url {
+                    Some(url) => ui.hyperlink_to(format!("{}{}", release.tag, date), url),
+                    None => ui.label(format!("{}{}", release.tag, date)),
+                };
+                ui.end_row();
+            }
+        });
+        if !repo.languages.is_empty() {
+            ui.label(format!("Languages: {}", repo.languages.join(", ")));
+        }
+        if !repo.topics.is_empty() {
+            ui.label(format!("Topics: {}", repo.topics.join(", ")));
+        }
+        if let Some(readme) = readme {
+            egui::CollapsingHeader::new("README").id_salt("waffle_detail_readme").show(ui, |ui| {
+                egui::ScrollArea::vertical().max_height(240.0).show(ui, |ui| {
+                    ui.label(egui::RichText::new(readme).monospace());
+                });
+            });
+        }
+        ui.separator();
+        ui.label("History:");
+        self.show_history(ui, &repo.key());
+
+        let Some(node_id) = repo.node_id.clone() else {
+            ui.separator();
+            ui.label("Notes need a repository node_id; sync again to attach notes.");
+            return;
+        };
+        if self.loaded_for.as_deref() != Some(node_id.as_str()) {
+            self.note_draft = notes.with(|n| n.get(&node_id).map(|note| note.text.clone()).unwrap_or_default());
+            self.tag_draft.clear();
+            self.loaded_for = Some(node_id.clone());
+        }
+        let full_name = Some(repo.full_name.as_str());
+
+        ui.separator();
+        ui.label("Notes:");
+        ui.add(egui::TextEdit::multiline(&mut self.note_draft).desired_rows(4));
+        let saved_text = notes.with(|n| n.get(&node_id).map(|note| note.text.clone()).unwrap_or_default());
+        if ui.add_enabled(saved_text != self.note_draft, egui::Button::new("Save note")).clicked() {
+            notes.update(|n| n.set_text(&node_id, full_name, &self.note_draft));
+        }
+
+        ui.separator();
+        ui.label("Tags:");
+        let tags = notes.with(|n| n.get(&node_id).map(|note| note.tags.clone()).unwrap_or_default());
+        ui.horizontal_wrapped(|ui| {
+            for tag in &tags {
+                if ui.small_button(format!("{} ✖", tag)).on_hover_text("Remove tag").clicked() {
+                    notes.update(|n| n.remove_tag(&node_id, tag));
+                }
+            }
+        });
+        ui.horizontal(|ui| {
+            let response = ui.text_edit_singleline(&mut self.tag_draft);
+            let submitted = response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+            if ui.button("Add tag").clicked() || submitted {
+                let tag = std::mem::take(&mut self.tag_draft);
+                notes.update(|n| n.add_tag(&node_id, full_name, &tag));
+            }
+        });
+        let suggestions = notes.with(|n| n.all_tags());
+        let suggestions = suggestions.iter().filter(|t| !tags.contains(t)).collect::<Vec<_>>();
+        if !suggestions.is_empty() {
+            ui.horizontal_wrapped(|ui| {
+                ui.label("Existing:");
+                for tag in suggestions {
+                    if ui.small_button(tag).clicked() {
+                        notes.update(|n| n.add_tag(&node_id, full_name, tag));
+                    }
+                }
+            });
+        }
+    }
+}
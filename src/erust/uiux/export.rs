@@ -0,0 +1,132 @@
+// export.rs - window for exporting the current results or a whole language cache to a file
+use egui::Context;
+use std::sync::{Arc, Mutex};
+use crate::db::export::{self, Column, ExportFormat, ExportOptions};
+use crate::db::record::RepoRecord;
+use crate::db::idb;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportScope {
+    Results,
+    Language,
+}
+
+pub struct ExportWidget {
+    pub open: bool,
+    pub scope: ExportScope,
+    pub options: ExportOptions,
+    /// Native only: where the file is written. Empty means a default name in the working directory.
+    pub path: String,
+    status: Arc<Mutex<Option<String>>>,
+}
+
+impl Default for ExportWidget {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ExportWidget {
+    pub fn new() -> Self {
+        Self {
+            open: false,
+            scope: ExportScope::Results,
+            options: ExportOptions::default(),
+            path: String::new(),
+            status: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    fn run(&self, results: &[RepoRecord], language: &str) {
+        let options = self.options.clone();
+        let status = Arc::clone(&self.status);
+        let scope_name = match self.scope {
+            ExportScope::Results => format!("{}_results", language),
+            ExportScope::Language => language.to_string(),
+        };
+        let path = if self.path.trim().is_empty() || cfg!(target_arch = "wasm32") {
+            export::file_name(&scope_name, options.format)
+        } else {
+            self.path.trim().to_string()
+        };
+        let write = move |repos: &[RepoRecord]| {
+            let outcome = export::export(repos, &options).and_then(|content| export::deliver(&path, &content, options.format));
+            *status.lock().unwrap() = Some(match outcome {
+                Ok(done) => format!("{} ({} repositories)", done, repos.len()),
+                Err(e) => format!("Export failed: {}", e),
+            });
+        };
+        match self.scope {
+            ExportScope::Results => write(results),
+            ExportScope::Language => {
+                let language = language.to_string();
+                *self.status.lock().unwrap() = Some("Exporting...".to_string());
+                wasm_bindgen_futures::spawn_local(async move {
+                    let repos = match idb::open_waffle_db().await {
+                        Ok(db) => idb::get_all_records(&db, &language).await.unwrap_or_default(),
+                        Err(_) => vec![],
+                    };
+                    write(&repos);
+                });
+            }
+        }
+    }
+
+    pub fn show(&mut self, ctx: &Context, results: &[RepoRecord], language: &str) {
+        let mut open = self.open;
+        egui::Window::new("Export").open(&mut open).resizable(false).show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                ui.radio_value(&mut self.scope, ExportScope::Results, format!("Current results ({})", results.len()));
+                ui.radio_value(&mut self.scope, ExportScope::Language, format!("All cached {}", language));
+            });
+            egui::ComboBox::from_label("Format")
+                .selected_text(self.options.format.label())
+                .show_ui(ui, |ui| {
+                    for format in ExportFormat::ALL {
+                        ui.selectable_value(&mut self.options.format, *format, format.label());
+                    }
+                });
+            match self.options.format {
+                ExportFormat::Csv => {
+                    ui.label("Columns:");
+                    ui.horizontal_wrapped(|ui| {
+                        for column in Column::ALL {
+                            let mut included = self.options.columns.contains(column);
+                            if ui.checkbox(&mut included, column.header()).changed() {
+                                if included {
+                                    self.options.columns.push(*column);
+                                    // Keep the file's column order stable regardless of click order
+                                    self.options.columns.sort_by_key(|c| Column::ALL.iter().position(|a| a == c));
+                                } else {
+                                    self.options.columns.retain(|c| c != column);
+                                }
+                            }
+                        }
+                    });
+                }
+                ExportFormat::Markdown => {
+                    ui.horizontal(|ui| {
+                        ui.label("Title:");
+                        ui.text_edit_singleline(&mut self.options.title);
+                    });
+                }
+                ExportFormat::Json | ExportFormat::Ndjson => {}
+            }
+            if !cfg!(target_arch = "wasm32") {
+                ui.horizontal(|ui| {
+                    ui.label("Path:");
+                    ui.text_edit_singleline(&mut self.path)
+                        .on_hover_text("Leave empty to write a default file name into the working directory");
+                });
+            }
+            let ready = self.options.format != ExportFormat::Csv || !self.options.columns.is_empty();
+            if ui.add_enabled(ready, egui::Button::new("Export")).clicked() {
+                self.run(results, language);
+            }
+            if let Some(status) = self.status.lock().unwrap().as_ref() {
+                ui.label(status);
+            }
+        });
+        self.open = open;
+    }
+}
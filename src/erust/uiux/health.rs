@@ -0,0 +1,47 @@
+// health.rs - health score badge for repository rows and the weight editor for settings
+use egui::{Color32, Ui};
+use crate::db::record::RepoRecord;
+use crate::db::health::{Factor, HealthWeights};
+use crate::db::settings::SettingsStore;
+
+pub fn score_color(score: f64) -> Color32 {
+    if score >= 70.0 {
+        Color32::from_rgb(0, 200, 120)
+    } else if score >= 40.0 {
+        Color32::from_rgb(230, 180, 40)
+    } else {
+        Color32::from_rgb(230, 80, 80)
+    }
+}
+
+/// Colored score with a per-factor breakdown on hover.
+pub fn health_badge(ui: &mut Ui, repo: &RepoRecord, weights: &HealthWeights, now: f64) {
+    let score = weights.score(repo, now);
+    let breakdown = Factor::ALL
+        .iter()
+        .map(|f| format!("{}: {:.0}% (weight {:.0})", f.label(), f.value(repo, now) * 100.0, weights.weight(*f)))
+        .collect::<Vec<_>>()
+        .join("\n");
+    ui.colored_label(score_color(score), format!("❤ {:.0}", score))
+        .on_hover_text(format!("Health score\n{}", breakdown));
+}
+
+/// Sliders for each factor's weight. Saves on change.
+pub fn weights_editor(ui: &mut Ui, settings: &SettingsStore) {
+    let mut weights = settings.with(|s| s.health);
+    let mut changed = false;
+    egui::Grid::new("waffle_health_weights").num_columns(2).show(ui, |ui| {
+        for factor in Factor::ALL {
+            ui.label(factor.label());
+            changed |= ui.add(egui::Slider::new(weights.weight_mut(*factor), 0.0..=100.0).integer()).changed();
+            ui.end_row();
+        }
+    });
+    if ui.button("Reset to defaults").clicked() {
+        weights = HealthWeights::default();
+        changed = true;
+    }
+    if changed {
+        settings.update(|s| s.health = weights);
+    }
+}
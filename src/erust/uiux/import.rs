@@ -0,0 +1,110 @@
+// import.rs - window for loading search dumps or exports into a language store
+use egui::{Context, DroppedFile, LayerId};
+use std::sync::{Arc, Mutex};
+use crate::db::idb::{self, LANGUAGES};
+use crate::db::import;
+use crate::utility::read_dropped_file;
+
+pub struct ImportWidget {
+    pub open: bool,
+    pub language: String,
+    /// Pasted JSON or NDJSON
+    pub text: String,
+    status: Arc<Mutex<Option<String>>>,
+    /// Set once an import has written to the store, until the app picks it up
+    finished: Arc<Mutex<bool>>,
+    layer: Option<LayerId>,
+}
+
+impl Default for ImportWidget {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ImportWidget {
+    pub fn new() -> Self {
+        Self {
+            open: false,
+            language: LANGUAGES[0].to_string(),
+            text: String::new(),
+            status: Arc::new(Mutex::new(None)),
+            finished: Arc::new(Mutex::new(false)),
+            layer: None,
+        }
+    }
+
+    /// True once after an import changed a language store.
+    pub fn take_finished(&self) -> bool {
+        std::mem::take(&mut *self.finished.lock().unwrap())
+    }
+
+    fn run(&self, source: &str, text: &str) {
+        let parsed = match import::parse(text) {
+            Ok(parsed) => parsed,
+            Err(e) => {
+                *self.status.lock().unwrap() = Some(format!("{}: {}", source, e));
+                return;
+            }
+        };
+        let language = self.language.clone();
+        let source = source.to_string();
+        let status = Arc::clone(&self.status);
+        let finished = Arc::clone(&self.finished);
+        *status.lock().unwrap() = Some(format!("Importing {}...", source));
+        wasm_bindgen_futures::spawn_local(async move {
+            let result = match idb::open_waffle_db().await {
+                Ok(db) => import::import_into(&db, &language, parsed).await,
+                Err(e) => Err(format!("Failed to open IndexedDB: {}", e)),
+            };
+            *status.lock().unwrap() = Some(match result {
+                Ok(summary) => {
+                    *finished.lock().unwrap() = summary.inserted + summary.updated > 0;
+                    format!("{} → {}: {}", source, language, summary.describe())
+                }
+                Err(e) => format!("Import of {} failed: {}", source, e),
+            });
+        });
+    }
+
+    pub fn show(&mut self, ctx: &Context) {
+        let mut open = self.open;
+        let response = egui::Window::new("Import").open(&mut open).default_width(420.0).show(ctx, |ui| {
+            ui.label("Accepts a GitHub search response, a JSON array of repositories or NDJSON (Waffle's JSON and NDJSON exports).");
+            egui::ComboBox::from_label("Into")
+                .selected_text(&self.language)
+                .show_ui(ui, |ui| {
+                    for lang in LANGUAGES {
+                        ui.selectable_value(&mut self.language, lang.to_string(), *lang);
+                    }
+                });
+            ui.label("Drop a file on the window, or paste below:");
+            ui.add(egui::TextEdit::multiline(&mut self.text).desired_rows(6).code_editor());
+            if ui.add_enabled(!self.text.trim().is_empty(), egui::Button::new("Import pasted text")).clicked() {
+                let text = std::mem::take(&mut self.text);
+                self.run("pasted text", &text);
+            }
+            if let Some(status) = self.status.lock().unwrap().as_ref() {
+                ui.label(status);
+            }
+        });
+        self.open = open;
+        self.layer = response.map(|r| r.response.layer_id);
+    }
+
+    /// The window's layer while it is open, so the app can tell whether it is on top.
+    pub fn layer(&self) -> Option<LayerId> {
+        self.layer
+    }
+
+    /// Import files dropped on the window; the app hands them here when this window owns the drop.
+    pub fn drop_files(&self, files: Vec<DroppedFile>) {
+        for file in files {
+            let (name, text) = read_dropped_file(&file);
+            match text {
+                Ok(text) => self.run(&name, &text),
+                Err(e) => *self.status.lock().unwrap() = Some(format!("Could not read {}: {}", name, e)),
+            }
+        }
+    }
+}
@@ -26,6 +26,14 @@ pub fn send_action_message(action: &str, email: &str, password: &str, captcha_to
     send_jsrust_message(&msg);
 }
 
+/// Helper to send an action with a JSON-encoded payload to JSRust
+pub fn send_payload_message(action: &str, payload_json: &str) {
+    let msg = js_sys::Object::new();
+    js_sys::Reflect::set(&msg, &JsValue::from_str("action"), &JsValue::from_str(action)).ok();
+    js_sys::Reflect::set(&msg, &JsValue::from_str("payload"), &JsValue::from_str(payload_json)).ok();
+    send_jsrust_message(&msg);
+}
+
 /// Register a JS callback handler for responses from JS to Rust
 /// The callback will be called with a JsValue (the response object)
 use wasm_bindgen::prelude::*;
@@ -197,3 +205,28 @@ pub fn supabase_user(user: &JsValue) {
 pub fn take_supabase_user() -> Option<AppUser> {
     LAST_SUPABASE_USER.with(|cell| cell.borrow_mut().take())
 }
+
+use crate::db::cloud_sync::SyncResponse;
+
+thread_local! {
+    static CLOUD_SYNC_RESPONSES: RefCell<Vec<SyncResponse>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Called from JS with the result of a `cloud_sync` action
+#[wasm_bindgen]
+pub fn cloud_sync_response(response: &JsValue) {
+    let parsed: Result<SyncResponse, String> = js_sys::JSON::stringify(response)
+        .ok()
+        .and_then(|js_str| js_str.as_string())
+        .ok_or_else(|| "Failed to stringify JsValue".to_string())
+        .and_then(|json_str| serde_json::from_str(&json_str).map_err(|e| e.to_string()));
+    match parsed {
+        Ok(resp) => CLOUD_SYNC_RESPONSES.with(|cell| cell.borrow_mut().push(resp)),
+        Err(e) => log::error!("[JSInterop] Failed to parse cloud sync response: {}", e),
+    }
+}
+
+/// Retrieve and clear the cloud sync responses received so far
+pub fn take_cloud_sync_responses() -> Vec<SyncResponse> {
+    CLOUD_SYNC_RESPONSES.with(|cell| std::mem::take(&mut *cell.borrow_mut()))
+}
@@ -0,0 +1,111 @@
+// license_policy.rs - policy editor for settings and the compliance flag shown on repository rows
+use egui::{Color32, Ui};
+use crate::db::record::RepoRecord;
+use crate::db::license_policy::{self, LicensePolicy, UnlicensedHandling, Verdict};
+use crate::db::settings::SettingsStore;
+
+pub fn verdict_color(verdict: Verdict) -> Color32 {
+    match verdict {
+        Verdict::Allowed => Color32::from_rgb(0, 200, 120),
+        Verdict::Review | Verdict::Unlisted => Color32::from_rgb(230, 180, 40),
+        Verdict::Denied | Verdict::Unlicensed => Color32::from_rgb(230, 80, 80),
+    }
+}
+
+/// "⚖ GPL-3.0" in the verdict's color, only for repositories the policy flags.
+pub fn license_flag(ui: &mut Ui, repo: &RepoRecord, policy: &LicensePolicy) {
+    let verdict = policy.classify(repo);
+    if !policy.flags(verdict) {
+        return;
+    }
+    let id = license_policy::license_id(repo).unwrap_or_else(|| "No license".to_string());
+    ui.colored_label(verdict_color(verdict), format!("⚖ {}", id))
+        .on_hover_text(format!("License policy: {}", verdict.label()));
+}
+
+fn list_to_text(list: &[String]) -> String {
+    list.join(", ")
+}
+
+fn text_to_list(text: &str) -> Vec<String> {
+    text.split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|s| !s.is_empty())
+        .map(|s| s.to_string())
+        .collect()
+}
+
+pub struct LicensePolicyWidget {
+    pub allowed: String,
+    pub review: String,
+    pub denied: String,
+    /// Settings revision the drafts were filled from
+    loaded_revision: Option<u64>,
+}
+
+impl Default for LicensePolicyWidget {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LicensePolicyWidget {
+    pub fn new() -> Self {
+        Self {
+            allowed: String::new(),
+            review: String::new(),
+            denied: String::new(),
+            loaded_revision: None,
+        }
+    }
+
+    pub fn show(&mut self, ui: &mut Ui, settings: &SettingsStore) {
+        let mut policy = settings.with(|s| s.license_policy.clone());
+        if self.loaded_revision != Some(settings.revision()) {
+            self.allowed = list_to_text(&policy.allowed);
+            self.review = list_to_text(&policy.review);
+            self.denied = list_to_text(&policy.denied);
+            self.loaded_revision = Some(settings.revision());
+        }
+        ui.label("SPDX identifiers, separated by commas:");
+        egui::Grid::new("waffle_license_policy").num_columns(2).show(ui, |ui| {
+            ui.label("Allowed");
+            ui.text_edit_multiline(&mut self.allowed);
+            ui.end_row();
+            ui.label("Review");
+            ui.text_edit_multiline(&mut self.review);
+            ui.end_row();
+            ui.label("Denied");
+            ui.text_edit_multiline(&mut self.denied);
+            ui.end_row();
+        });
+        let mut changed = false;
+        egui::ComboBox::from_label("Unlicensed repositories")
+            .selected_text(policy.unlicensed.label())
+            .show_ui(ui, |ui| {
+                for handling in UnlicensedHandling::ALL {
+                    changed |= ui.selectable_value(&mut policy.unlicensed, *handling, handling.label()).changed();
+                }
+            });
+        let edited = text_to_list(&self.allowed) != policy.allowed
+            || text_to_list(&self.review) != policy.review
+            || text_to_list(&self.denied) != policy.denied;
+        ui.horizontal(|ui| {
+            if ui.add_enabled(edited, egui::Button::new("Save policy")).clicked() {
+                policy.allowed = text_to_list(&self.allowed);
+                policy.review = text_to_list(&self.review);
+                policy.denied = text_to_list(&self.denied);
+                changed = true;
+            }
+            if ui.button("Reset to defaults").clicked() {
+                policy = LicensePolicy::default();
+                changed = true;
+            }
+        });
+        if changed {
+            settings.update(|s| s.license_policy = policy);
+        }
+        if settings.with(|s| s.license_policy.unlicensed) != UnlicensedHandling::Drop {
+            ui.label("Unlicensed repositories are kept from the next sync on.");
+        }
+    }
+}
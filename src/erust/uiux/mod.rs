@@ -3,4 +3,15 @@ pub mod hcaptcha;
 pub mod auth;
 pub mod supabase;
 pub mod javascript_interop;
-pub mod user;
\ No newline at end of file
+pub mod user;
+pub mod collections;
+pub mod detail;
+pub mod saved_searches;
+pub mod what_changed;
+pub mod stats;
+pub mod health;
+pub mod license_policy;
+pub mod export;
+pub mod import;
+pub mod backup;
+pub mod sources;
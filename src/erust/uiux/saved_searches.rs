@@ -0,0 +1,53 @@
+// saved_searches.rs - side panel list of saved searches with new-result badges
+use egui::Ui;
+use crate::db::query::SearchSpec;
+use crate::db::saved_search::{SavedSearch, SavedSearchStore};
+
+pub struct SavedSearchesWidget {
+    pub new_name: String,
+}
+
+impl Default for SavedSearchesWidget {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SavedSearchesWidget {
+    pub fn new() -> Self {
+        Self { new_name: String::new() }
+    }
+
+    /// Draw the list. Returns the saved search the user asked to run, if any.
+    pub fn show(&mut self, ui: &mut Ui, store: &SavedSearchStore, current: &SearchSpec, language: &str) -> Option<SavedSearch> {
+        let mut run = None;
+        ui.horizontal(|ui| {
+            ui.text_edit_singleline(&mut self.new_name);
+            if ui.button("Save search").on_hover_text("Save the current query, filters, sort and language").clicked() {
+                let name = std::mem::take(&mut self.new_name);
+                if store.update(|s| s.save(&name, current.clone(), language)) {
+                    // Run it straight away so the current results count as seen.
+                    run = store.with(|s| s.get(name.trim()).cloned());
+                }
+            }
+        });
+        let saved = store.get();
+        for search in &saved.searches {
+            ui.horizontal(|ui| {
+                let new_count = saved.new_count(&search.name);
+                let label = format!("{} ({})", search.name, search.language);
+                let hover = if search.spec.query.is_empty() { "<all repositories>" } else { search.spec.query.as_str() };
+                if ui.button(label).on_hover_text(hover).clicked() {
+                    run = Some(search.clone());
+                }
+                if new_count > 0 {
+                    ui.colored_label(egui::Color32::from_rgb(0, 200, 120), format!("+{} new", new_count));
+                }
+                if ui.small_button("✖").on_hover_text("Delete saved search").clicked() {
+                    store.update(|s| s.delete(&search.name));
+                }
+            });
+        }
+        run
+    }
+}
@@ -1,41 +1,124 @@
-use crate::db::github::Repository;
-use crate::db::idb;
-use egui::{Context, Id};
+use std::collections::HashMap;
+use std::sync::Arc;
+use crate::db::record::RepoRecord;
+use crate::db::health::HealthWeights;
+use crate::db::license_policy::LicensePolicy;
+use crate::db::{history, idb};
+use crate::db::notes::Notes;
+use crate::db::query::{QueryContext, SearchSpec, SortOrder};
+use egui::{Context, Id, Ui};
 
 pub struct SearchWidget {
-    pub query: String,
-    pub results: Vec<Repository>,
+    pub spec: SearchSpec,
+    pub results: Arc<[RepoRecord]>,
+    /// Stars per day by repository key, filled in when sorting by trend
+    pub trends: HashMap<String, f64>,
+    /// Weights behind the health: qualifier and sort, from settings
+    pub health: HealthWeights,
+    pub license_policy: LicensePolicy,
     pub loading: bool,
 }
 
 impl SearchWidget {
     pub fn new() -> Self {
         Self {
-            query: String::new(),
-            results: Vec::new(),
+            spec: SearchSpec::default(),
+            results: Arc::default(),
+            trends: HashMap::new(),
+            health: HealthWeights::default(),
+            license_policy: LicensePolicy::default(),
             loading: false,
         }
     }
 
-    pub fn search(&mut self, language: &str, ctx: &Context) {
-        let query = self.query.clone();
+    pub fn search(&mut self, language: &str, notes: &Notes, ctx: &Context) {
+        self.search_then(language, notes, ctx, |_| {});
+    }
+
+    /// Run the current search and hand the results to `done` once they are in.
+    pub fn search_then<F>(&mut self, language: &str, notes: &Notes, ctx: &Context, done: F)
+    where
+        F: FnOnce(&[RepoRecord]) + 'static,
+    {
+        let spec = self.spec.clone();
+        let health = self.health;
+        let license_policy = self.license_policy.clone();
+        let notes = notes.clone();
         let language = language.to_string();
         let ctx = ctx.clone();
         self.loading = true;
         wasm_bindgen_futures::spawn_local(async move {
-            let result = match idb::open_waffle_db().await {
-                Ok(db_conn) => idb::filter_repos_in_idb::<Repository>(&db_conn, &language, &query).await.unwrap_or_default(),
-                Err(_) => vec![],
+            let (repos, histories) = match idb::open_waffle_db().await {
+                Ok(db_conn) => {
+                    let repos = idb::get_all_records(&db_conn, &language).await.unwrap_or_default();
+                    let histories = if spec.sort.trend_days().is_some() {
+                        history::load_all_histories(&db_conn).await.unwrap_or_default()
+                    } else {
+                        vec![]
+                    };
+                    (repos, histories)
+                }
+                Err(_) => (vec![], vec![]),
             };
-            ctx.data_mut(|d| d.insert_temp(Id::new("waffle_search_results"), result));
+            let histories = histories.into_iter().map(|h| (h.full_name.clone(), h)).collect::<HashMap<_, _>>();
+            let query_ctx = QueryContext {
+                histories: Some(&histories),
+                health,
+                license_policy: Some(&license_policy),
+                ..QueryContext::new(&notes)
+            };
+            let result = spec.run(repos, &query_ctx);
+            let trends = result
+                .iter()
+                .filter_map(|repo| Some((repo.key(), spec.sort.trend(repo, &query_ctx)?)))
+                .collect::<HashMap<_, _>>();
+            done(&result);
+            ctx.data_mut(|d| {
+                d.insert_temp(Id::new("waffle_search_trends"), trends);
+                d.insert_temp(Id::new("waffle_search_results"), result);
+            });
             ctx.request_repaint();
         });
     }
 
+    /// Query box, sort order and filters. Returns true if anything changed.
+    pub fn show_controls(&mut self, ui: &mut Ui) -> bool {
+        let mut changed = ui
+            .text_edit_singleline(&mut self.spec.query)
+            .on_hover_text("Words match name, description and notes.\nQualifiers: tag:x note:x has:note has:tag stars:>100 forks:10..50 health:>70 topic:x language:x archived:false license:mit policy:denied\nPrefix with - to negate, quote values with spaces.")
+            .changed();
+        egui::ComboBox::from_label("Sort")
+            .selected_text(self.spec.sort.label())
+            .show_ui(ui, |ui| {
+                for order in SortOrder::ALL {
+                    changed |= ui.selectable_value(&mut self.spec.sort, *order, order.label()).changed();
+                }
+            });
+        egui::CollapsingHeader::new("Filters").id_salt("waffle_search_filters").show(ui, |ui| {
+            let filters = &mut self.spec.filters;
+            let mut use_min = filters.min_stars.is_some();
+            ui.horizontal(|ui| {
+                changed |= ui.checkbox(&mut use_min, "Min stars").changed();
+                let mut min = filters.min_stars.unwrap_or(100);
+                if use_min {
+                    changed |= ui.add(egui::DragValue::new(&mut min).speed(10)).changed();
+                }
+                filters.min_stars = use_min.then_some(min);
+            });
+            changed |= ui.checkbox(&mut filters.exclude_archived, "Hide archived").changed();
+            changed |= ui.checkbox(&mut filters.exclude_forks, "Hide forks").changed();
+        });
+        changed
+    }
+
+    /// Take results a finished search left in the context; they are handed over once, not copied every frame.
     pub fn update_results_from_ctx(&mut self, ctx: &Context) {
-        if let Some(results) = ctx.data(|d| d.get_temp::<Vec<Repository>>(Id::new("waffle_search_results"))) {
-            self.results = results.clone();
+        if let Some(results) = ctx.data_mut(|d| d.remove_temp::<Vec<RepoRecord>>(Id::new("waffle_search_results"))) {
+            self.results = results.into();
             self.loading = false;
         }
+        if let Some(trends) = ctx.data_mut(|d| d.remove_temp::<HashMap<String, f64>>(Id::new("waffle_search_trends"))) {
+            self.trends = trends;
+        }
     }
 }
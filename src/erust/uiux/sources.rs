@@ -0,0 +1,20 @@
+// sources.rs - settings fields for the self-hostable repository sources
+use egui::Ui;
+use crate::db::source::ForgeConfig;
+
+/// Base URL, token and topic fields. Returns whether anything changed.
+pub fn forge_settings(ui: &mut Ui, id_salt: &str, config: &mut ForgeConfig) -> bool {
+    let mut changed = false;
+    egui::Grid::new(id_salt).num_columns(2).show(ui, |ui| {
+        ui.label("Base URL");
+        changed |= ui.text_edit_singleline(&mut config.base_url).changed();
+        ui.end_row();
+        ui.label("Token");
+        changed |= ui.add(egui::TextEdit::singleline(&mut config.token).password(true)).changed();
+        ui.end_row();
+        ui.label("Topic");
+        changed |= ui.add(egui::TextEdit::singleline(&mut config.topic).hint_text("any")).changed();
+        ui.end_row();
+    });
+    changed
+}
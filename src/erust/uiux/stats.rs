@@ -0,0 +1,172 @@
+// stats.rs - dashboard tab with charts over every cached repository of the current language
+use egui::{Color32, Context, Id, Ui};
+use egui_plot::{Bar, BarChart, Plot};
+use crate::db::idb;
+use crate::db::stats::LanguageStats;
+use crate::utility::now_millis;
+
+const PIE_COLORS: &[Color32] = &[
+    Color32::from_rgb(0, 200, 220),
+    Color32::from_rgb(180, 140, 255),
+    Color32::from_rgb(255, 170, 60),
+    Color32::from_rgb(90, 200, 120),
+    Color32::from_rgb(240, 100, 120),
+    Color32::from_rgb(120, 150, 255),
+    Color32::from_rgb(230, 220, 90),
+    Color32::from_rgb(150, 150, 150),
+];
+
+pub struct StatsWidget {
+    pub stats: Option<LanguageStats>,
+    /// (language, completed syncs) the stats were computed for
+    loaded_for: Option<(String, u64)>,
+}
+
+impl Default for StatsWidget {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl StatsWidget {
+    pub fn new() -> Self {
+        Self { stats: None, loaded_for: None }
+    }
+
+    /// Recompute when the language changes or a sync lands.
+    fn refresh(&mut self, ctx: &Context, language: &str, syncs: u64) {
+        let key = (language.to_string(), syncs);
+        if self.loaded_for.as_ref() == Some(&key) {
+            return;
+        }
+        self.loaded_for = Some(key);
+        self.stats = None;
+        let language = language.to_string();
+        let ctx = ctx.clone();
+        wasm_bindgen_futures::spawn_local(async move {
+            let repos = match idb::open_waffle_db().await {
+                Ok(db) => idb::get_all_records(&db, &language).await.unwrap_or_default(),
+                Err(_) => vec![],
+            };
+            let stats = LanguageStats::compute(&repos, now_millis());
+            ctx.data_mut(|d| d.insert_temp(Id::new("waffle_language_stats"), stats));
+            ctx.request_repaint();
+        });
+    }
+
+    pub fn show(&mut self, ui: &mut Ui, language: &str, syncs: u64) {
+        self.refresh(ui.ctx(), language, syncs);
+        if let Some(stats) = ui.ctx().data_mut(|d| d.remove_temp::<LanguageStats>(Id::new("waffle_language_stats"))) {
+            self.stats = Some(stats);
+        }
+        let Some(stats) = &self.stats else {
+            ui.spinner();
+            return;
+        };
+        if stats.total == 0 {
+            ui.label(format!("There is no data for {}, please sync.", language));
+            return;
+        }
+        egui::Grid::new("waffle_stats_summary").num_columns(2).show(ui, |ui| {
+            ui.label("Repositories");
+            ui.label(stats.total.to_string());
+            ui.end_row();
+            ui.label("Total stars");
+            ui.label(stats.total_stars.to_string());
+            ui.end_row();
+            ui.label("Median age");
+            ui.label(stats.median_age_days.map(|d| format!("{:.1} years", d / 365.25)).unwrap_or_else(|| "Unknown".to_string()));
+            ui.end_row();
+            ui.label("Archived");
+            ui.label(format!("{:.1}%", stats.archived_pct));
+            ui.end_row();
+            ui.label("Forks");
+            ui.label(format!("{:.1}%", stats.fork_pct));
+            ui.end_row();
+        });
+        ui.separator();
+
+        ui.strong("Star distribution");
+        let labels = stats.star_histogram.iter().map(|b| b.label.clone()).collect::<Vec<_>>();
+        let bars = stats
+            .star_histogram
+            .iter()
+            .enumerate()
+            .map(|(i, bucket)| Bar::new(i as f64, bucket.count as f64).name(&bucket.label).width(0.8))
+            .collect();
+        Plot::new("waffle_star_histogram")
+            .height(160.0)
+            .allow_scroll(false)
+            .allow_drag(false)
+            .allow_zoom(false)
+            .x_axis_formatter(move |mark, _range| {
+                let index = mark.value.round();
+                if (mark.value - index).abs() < f64::EPSILON && index >= 0.0 {
+                    labels.get(index as usize).cloned().unwrap_or_default()
+                } else {
+                    String::new()
+                }
+            })
+            .show(ui, |plot_ui| plot_ui.bar_chart(BarChart::new("Repositories", bars)));
+        ui.separator();
+
+        ui.strong("Licenses");
+        ui.horizontal(|ui| {
+            pie(ui, &stats.licenses, 70.0);
+            ui.vertical(|ui| {
+                for (i, (license, count)) in stats.licenses.iter().enumerate() {
+                    ui.horizontal(|ui| {
+                        ui.colored_label(PIE_COLORS[i % PIE_COLORS.len()], "⏺");
+                        ui.label(format!("{} ({})", license, count));
+                    });
+                }
+            });
+        });
+        ui.separator();
+
+        ui.strong("Topics");
+        let top = stats.topics.first().map(|(_, n)| *n).unwrap_or(1).max(1) as f32;
+        ui.horizontal_wrapped(|ui| {
+            for (topic, count) in &stats.topics {
+                let size = 11.0 + 13.0 * (*count as f32 / top);
+                ui.label(egui::RichText::new(topic).size(size)).on_hover_text(format!("{} repositories", count));
+            }
+        });
+        ui.separator();
+
+        ui.strong("Most active owners");
+        egui::Grid::new("waffle_stats_owners").num_columns(3).striped(true).show(ui, |ui| {
+            ui.label("Owner");
+            ui.label("Repos");
+            ui.label("Stars");
+            ui.end_row();
+            for owner in &stats.top_owners {
+                ui.hyperlink_to(&owner.owner, format!("https://github.com/{}", owner.owner));
+                ui.label(owner.repos.to_string());
+                ui.label(owner.stars.to_string());
+                ui.end_row();
+            }
+        });
+    }
+}
+
+/// egui_plot has no pie chart, so paint the slices as fans of triangles.
+fn pie(ui: &mut Ui, slices: &[(String, usize)], radius: f32) {
+    let (rect, _) = ui.allocate_exact_size(egui::vec2(radius * 2.0, radius * 2.0), egui::Sense::hover());
+    let total = slices.iter().map(|(_, n)| *n).sum::<usize>().max(1) as f32;
+    let center = rect.center();
+    let mut angle = -std::f32::consts::FRAC_PI_2;
+    for (i, (_, count)) in slices.iter().enumerate() {
+        let sweep = *count as f32 / total * std::f32::consts::TAU;
+        let steps = ((sweep / 0.05).ceil() as usize).max(1);
+        let color = PIE_COLORS[i % PIE_COLORS.len()];
+        for step in 0..steps {
+            let a0 = angle + sweep * step as f32 / steps as f32;
+            let a1 = angle + sweep * (step + 1) as f32 / steps as f32;
+            let p0 = center + radius * egui::vec2(a0.cos(), a0.sin());
+            let p1 = center + radius * egui::vec2(a1.cos(), a1.sin());
+            ui.painter().add(egui::Shape::convex_polygon(vec![center, p0, p1], color, egui::Stroke::NONE));
+        }
+        angle += sweep;
+    }
+}
@@ -0,0 +1,163 @@
+// what_changed.rs - window listing the differences between the last two syncs of a language
+use egui::{Context, Id, Ui};
+use crate::db::export::{self, ExportFormat};
+use crate::db::idb;
+use crate::db::sync_report::{self, FieldChange, SyncReport, TOP_N};
+use crate::utility::format_date;
+
+pub struct WhatChangedWidget {
+    pub open: bool,
+    pub report: Option<SyncReport>,
+}
+
+impl Default for WhatChangedWidget {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl WhatChangedWidget {
+    pub fn new() -> Self {
+        Self { open: false, report: None }
+    }
+
+    /// Show a report that was just produced by a sync.
+    pub fn present(&mut self, report: SyncReport) {
+        self.open = true;
+        self.report = Some(report);
+    }
+
+    /// Open the window with the stored report for `language`.
+    pub fn open_for(&mut self, language: &str, ctx: &Context) {
+        self.open = true;
+        if self.report.as_ref().is_some_and(|r| r.language == language) {
+            return;
+        }
+        self.report = None;
+        let language = language.to_string();
+        let ctx = ctx.clone();
+        wasm_bindgen_futures::spawn_local(async move {
+            let report = match idb::open_waffle_db().await {
+                Ok(db) => sync_report::load_report(&db, &language).await.ok().flatten(),
+                Err(_) => None,
+            };
+            let report = report.unwrap_or_else(|| SyncReport { language, ..Default::default() });
+            ctx.data_mut(|d| d.insert_temp(Id::new("waffle_sync_report"), report));
+            ctx.request_repaint();
+        });
+    }
+
+    pub fn update_from_ctx(&mut self, ctx: &Context) {
+        if let Some(report) = ctx.data_mut(|d| d.remove_temp::<SyncReport>(Id::new("waffle_sync_report"))) {
+            self.report = Some(report);
+        }
+    }
+
+    pub fn show(&mut self, ctx: &Context) {
+        self.update_from_ctx(ctx);
+        let mut open = self.open;
+        egui::Window::new("What changed")
+            .open(&mut open)
+            .default_width(420.0)
+            .vscroll(true)
+            .show(ctx, |ui| match &self.report {
+                None => {
+                    ui.spinner();
+                }
+                Some(report) if report.at == 0.0 => {
+                    ui.label(format!("{} has not been synced yet.", report.language));
+                }
+                Some(report) => show_report(ui, report),
+            });
+        self.open = open;
+    }
+}
+
+fn repo_link(ui: &mut Ui, name: &str, url: &Option<String>) {
+    ui.hyperlink_to(name, url.as_deref().unwrap_or("#"));
+}
+
+fn field_changes(ui: &mut Ui, title: &str, changes: &[FieldChange]) {
+    if changes.is_empty() {
+        return;
+    }
+    ui.strong(format!("{} ({})", title, changes.len()));
+    for change in changes {
+        ui.horizontal_wrapped(|ui| {
+            repo_link(ui, &change.full_name, &change.html_url);
+            ui.label(format!(
+                "{} → {}",
+                change.before.as_deref().unwrap_or("none"),
+                change.after.as_deref().unwrap_or("none")
+            ));
+        });
+    }
+    ui.separator();
+}
+
+fn show_report(ui: &mut Ui, report: &SyncReport) {
+    ui.horizontal(|ui| {
+        match report.previous_at {
+            Some(previous) => ui.label(format!("{}: {} → {}", report.language, format_date(previous), format_date(report.at))),
+            None => ui.label(format!("{} first synced {}", report.language, format_date(report.at))),
+        };
+        if ui.button("Copy as Markdown").on_hover_text("Copy a newsletter-ready summary").clicked() {
+            ui.ctx().copy_text(report.to_markdown());
+        }
+        if ui.button("Save as Markdown").clicked() {
+            let name = export::file_name(&format!("{}_changes_{}", report.language, format_date(report.at)), ExportFormat::Markdown);
+            if let Err(e) = export::deliver(&name, &report.to_markdown(), ExportFormat::Markdown) {
+                log::error!("{}", e);
+            }
+        }
+    });
+    ui.separator();
+    if report.previous_at.is_none() {
+        ui.label("This is the first sync, so there is nothing to compare against yet. The next sync will be diffed against it.");
+        return;
+    }
+    if report.is_empty() {
+        ui.label("Nothing changed since the previous sync.");
+        return;
+    }
+    if !report.entered_top.is_empty() {
+        ui.strong(format!("New in the top {}", TOP_N));
+        for entry in &report.entered_top {
+            ui.horizontal(|ui| {
+                ui.label(format!("#{}", entry.rank));
+                repo_link(ui, &entry.full_name, &entry.html_url);
+                ui.label(format!("⭐ {}", entry.stars));
+            });
+        }
+        ui.separator();
+    }
+    if !report.left_top.is_empty() {
+        ui.strong(format!("Dropped out of the top {}", TOP_N));
+        for entry in &report.left_top {
+            ui.horizontal(|ui| {
+                repo_link(ui, &entry.full_name, &entry.html_url);
+                ui.label(format!("was #{}", entry.rank));
+            });
+        }
+        ui.separator();
+    }
+    if !report.star_jumps.is_empty() {
+        ui.strong("Star jumps");
+        for jump in &report.star_jumps {
+            ui.horizontal(|ui| {
+                repo_link(ui, &jump.full_name, &jump.html_url);
+                ui.label(format!("{:+} (⭐ {} → {})", jump.delta(), jump.before, jump.after));
+            });
+        }
+        ui.separator();
+    }
+    if !report.newly_archived.is_empty() {
+        ui.strong("Newly archived");
+        for name in &report.newly_archived {
+            ui.label(name);
+        }
+        ui.separator();
+    }
+    field_changes(ui, "License changes", &report.license_changes);
+    field_changes(ui, "Description changes", &report.description_changes);
+}
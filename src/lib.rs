@@ -5,4 +5,6 @@ mod db;
 pub use app::TemplateApp;
 pub use db::*;
 pub mod utility;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod cli;
 pub mod erust;
\ No newline at end of file
@@ -1,6 +1,27 @@
 #![warn(clippy::all, rust_2018_idioms)]
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")] // hide console window on Windows in release
 
+// Natively, any arguments run a command line tool instead of the UI.
+#[cfg(not(target_arch = "wasm32"))]
+fn main() -> eframe::Result {
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    if !args.is_empty() {
+        match waffle_search::cli::run(&args) {
+            Ok(message) => println!("{}", message),
+            Err(e) => {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            }
+        }
+        return Ok(());
+    }
+    eframe::run_native(
+        "Waffle",
+        eframe::NativeOptions::default(),
+        Box::new(|cc| Ok(Box::new(waffle_search::TemplateApp::new(cc)))),
+    )
+}
+
 #[cfg(target_arch = "wasm32")]
 fn main() {
     use eframe::wasm_bindgen::JsCast as _;
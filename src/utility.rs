@@ -122,3 +122,97 @@ pub fn show_loading_spinner_custom(ui: &mut egui::Ui, message: &str, size: Optio
     ));
     response
 }
+
+/// Current wall-clock time in milliseconds since the Unix epoch.
+pub fn now_millis() -> f64 {
+    #[cfg(target_arch = "wasm32")]
+    {
+        web_sys::js_sys::Date::now()
+    }
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs_f64() * 1000.0)
+            .unwrap_or(0.0)
+    }
+}
+
+/// Format milliseconds since the Unix epoch as a `YYYY-MM-DD` UTC date.
+pub fn format_date(ms: f64) -> String {
+    // Days-to-civil conversion from Howard Hinnant's date algorithms.
+    let z = (ms / 86_400_000.0).floor() as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+/// "today", "2 days ago", "3 months ago" for a past time, relative to `now` (both in milliseconds).
+pub fn format_age(ms: f64, now: f64) -> String {
+    let days = ((now - ms) / 86_400_000.0).floor().max(0.0) as u64;
+    let plural = |n: u64, unit: &str| format!("{} {}{} ago", n, unit, if n == 1 { "" } else { "s" });
+    match days {
+        0 => "today".to_string(),
+        1..=59 => plural(days, "day"),
+        60..=729 => plural(days / 30, "month"),
+        _ => plural(days / 365, "year"),
+    }
+}
+
+/// Format milliseconds since the Unix epoch as a `YYYY-MM-DDTHH:MM:SSZ` UTC timestamp, the inverse of `parse_timestamp`.
+pub fn format_timestamp(ms: f64) -> String {
+    let secs = (ms / 1000.0).floor() as i64;
+    let of_day = secs.rem_euclid(86_400);
+    format!("{}T{:02}:{:02}:{:02}Z", format_date(ms), of_day / 3600, of_day % 3600 / 60, of_day % 60)
+}
+
+/// Parse a GitHub `YYYY-MM-DDTHH:MM:SSZ` timestamp into milliseconds since the Unix epoch.
+pub fn parse_timestamp(s: &str) -> Option<f64> {
+    let num = |range: std::ops::Range<usize>| s.get(range)?.parse::<i64>().ok();
+    let (year, month, day) = (num(0..4)?, num(5..7)?, num(8..10)?);
+    let (hour, minute, second) = (num(11..13).unwrap_or(0), num(14..16).unwrap_or(0), num(17..19).unwrap_or(0));
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+        return None;
+    }
+    // Inverse of the conversion in format_date.
+    let y = if month <= 2 { year - 1 } else { year };
+    let era = y.div_euclid(400);
+    let yoe = y - era * 400;
+    let mp = if month > 2 { month - 3 } else { month + 9 };
+    let doy = (153 * mp + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    let days = era * 146_097 + doe - 719_468;
+    Some(((days * 86_400 + hour * 3600 + minute * 60 + second) * 1000) as f64)
+}
+
+/// Percent-encode a query parameter value: everything but RFC 3986 unreserved characters.
+pub fn encode_query(value: &str) -> String {
+    value
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => (b as char).to_string(),
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+/// A dropped file's display name and text: the browser hands over bytes, native platforms a path.
+pub fn read_dropped_file(file: &egui::DroppedFile) -> (String, Result<String, String>) {
+    let name = if file.name.is_empty() {
+        file.path.as_ref().map(|p| p.display().to_string()).unwrap_or_default()
+    } else {
+        file.name.clone()
+    };
+    let text = match (&file.bytes, &file.path) {
+        (Some(bytes), _) => String::from_utf8(bytes.to_vec()).map_err(|e| e.to_string()),
+        (None, Some(path)) => std::fs::read_to_string(path).map_err(|e| e.to_string()),
+        (None, None) => Err("no contents".to_string()),
+    };
+    (name, text)
+}
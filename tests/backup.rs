@@ -0,0 +1,85 @@
+// backup.rs - the versioned backup archive: validation on the way in, merge and replace on restore
+mod support;
+
+use std::collections::BTreeMap;
+use support::temp_store_dir;
+use waffle_search::backup::{self, Backup, RestoreMode, BACKUP_VERSION};
+use waffle_search::idb::NOTES_STORE;
+use waffle_search::native::NativeStore;
+
+const AT: f64 = 1_700_000_000_000.0;
+
+fn store(name: &str, notes: &[(&str, &str)]) -> NativeStore {
+    let store = NativeStore::at(temp_store_dir(name));
+    for (key, text) in notes {
+        store.put(NOTES_STORE, key, text).unwrap();
+    }
+    store
+}
+
+fn note(store: &NativeStore, key: &str) -> Option<String> {
+    store.get::<String>(NOTES_STORE, key).unwrap()
+}
+
+/// A valid archive text with its manifest edited by `edit`
+fn edited(name: &str, edit: impl FnOnce(&mut serde_json::Value)) -> String {
+    let backup = backup::create(&store(name, &[("a", "one")]), AT).unwrap();
+    let mut value = serde_json::to_value(&backup).unwrap();
+    edit(&mut value);
+    value.to_string()
+}
+
+#[test]
+fn a_backup_restores_into_an_empty_store() {
+    let source = store("backup_source", &[("rust-lang/rust", "compiler"), ("tokio-rs/tokio", "runtime")]);
+    let text = backup::create(&source, AT).unwrap().to_json().unwrap();
+
+    let target = store("backup_target", &[]);
+    let summary = backup::restore(&target, &Backup::parse(&text).unwrap(), RestoreMode::Merge).unwrap();
+    assert_eq!(summary.records, 2);
+    assert_eq!(note(&target, "tokio-rs/tokio").as_deref(), Some("runtime"));
+}
+
+#[test]
+fn merge_keeps_local_only_records_and_replace_drops_them() {
+    let archive = backup::create(&store("backup_archive", &[("shared", "from backup")]), AT).unwrap();
+
+    let merged = store("backup_merge", &[("shared", "local"), ("local-only", "keep")]);
+    backup::restore(&merged, &archive, RestoreMode::Merge).unwrap();
+    assert_eq!(note(&merged, "shared").as_deref(), Some("from backup"));
+    assert_eq!(note(&merged, "local-only").as_deref(), Some("keep"));
+
+    let replaced = store("backup_replace", &[("shared", "local"), ("local-only", "keep")]);
+    backup::restore(&replaced, &archive, RestoreMode::Replace).unwrap();
+    assert_eq!(note(&replaced, "shared").as_deref(), Some("from backup"));
+    assert_eq!(note(&replaced, "local-only"), None);
+}
+
+#[test]
+fn newer_or_foreign_archives_are_refused() {
+    let newer = edited("backup_newer", |v| v["manifest"]["version"] = (BACKUP_VERSION + 1).into());
+    let err = Backup::parse(&newer).unwrap_err();
+    assert!(err.starts_with(&format!("Backup version {} is newer", BACKUP_VERSION + 1)), "{}", err);
+
+    let foreign = edited("backup_foreign", |v| v["manifest"]["format"] = "something-else".into());
+    assert!(Backup::parse(&foreign).unwrap_err().starts_with("Not a Waffle backup"));
+    assert!(Backup::parse("{}").unwrap_err().starts_with("Not a Waffle backup"));
+}
+
+#[test]
+fn a_manifest_that_disagrees_with_the_contents_is_damage() {
+    let damaged = edited("backup_damaged", |v| v["stores"][NOTES_STORE] = serde_json::json!({}));
+    let err = Backup::parse(&damaged).unwrap_err();
+    assert_eq!(err, format!("Backup is damaged: {} should have 1 records, found 0", NOTES_STORE));
+}
+
+#[test]
+fn unknown_stores_are_reported_not_written() {
+    let mut stores = BTreeMap::new();
+    stores.insert("from_the_future".to_string(), BTreeMap::from([("k".to_string(), serde_json::json!(1))]));
+    stores.insert(NOTES_STORE.to_string(), BTreeMap::from([("a".to_string(), serde_json::json!("one"))]));
+    let target = store("backup_unknown", &[]);
+    let summary = backup::restore(&target, &Backup::new(stores, AT), RestoreMode::Merge).unwrap();
+    assert_eq!((summary.stores, summary.ignored.as_slice()), (1, ["from_the_future".to_string()].as_slice()));
+    assert!(summary.describe().ends_with("(ignored unknown stores: from_the_future)"));
+}
@@ -0,0 +1,124 @@
+// cache.rs - the language store layout: what a sync writes is what load, the empty check and Clear Cache read
+mod support;
+
+use support::{block_on, repo, temp_store_dir};
+use waffle_search::cancel::CancelToken;
+use waffle_search::history::{Metric, RepoHistory};
+use waffle_search::idb::{HISTORY_STORE, NOTES_STORE, SYNC_META_STORE};
+use waffle_search::native::NativeStore;
+use waffle_search::record::RepoRecord;
+use waffle_search::source::SourceKind;
+use waffle_search::sync::{self, RepoCache, SyncMeta};
+
+fn meta(language: &str) -> SyncMeta {
+    SyncMeta {
+        language: language.to_string(),
+        source: SourceKind::GitHub,
+        host: "github.com".to_string(),
+        synced_at: 1_700_000_000_000.0,
+        count: 0,
+        keys: Vec::new(),
+    }
+}
+
+fn synced(name: &str) -> NativeStore {
+    let cache = NativeStore::at(temp_store_dir(name));
+    let records = vec![RepoRecord { stars: 100, ..repo("rust-lang/rust") }, RepoRecord { stars: 50, ..repo("tokio-rs/tokio") }];
+    block_on(sync::store(&cache, meta("Rust"), records, &CancelToken::default())).unwrap();
+    cache
+}
+
+#[test]
+fn a_synced_store_is_not_empty() {
+    let cache = synced("cache_not_empty");
+    let status = block_on(sync::status(&cache, "Rust")).unwrap();
+    assert_eq!(status.count, 2);
+    let keys = vec!["rust-lang/rust".to_string(), "tokio-rs/tokio".to_string()];
+    assert_eq!(status.meta, Some(SyncMeta { count: 2, keys, ..meta("Rust") }));
+    assert!(!status.is_empty());
+    // Records sit one per key, under the same key everything else uses
+    let mut keys = cache.entries("Rust").unwrap().into_keys().collect::<Vec<_>>();
+    keys.sort();
+    assert_eq!(keys, ["rust-lang/rust", "tokio-rs/tokio"]);
+}
+
+#[test]
+fn load_reads_what_sync_wrote() {
+    let cache = synced("cache_load");
+    let mut loaded = block_on(RepoCache::records(&cache, "Rust")).unwrap();
+    loaded.sort_by(|a, b| b.stars.cmp(&a.stars));
+    assert_eq!(loaded.iter().map(|r| r.full_name.as_str()).collect::<Vec<_>>(), ["rust-lang/rust", "tokio-rs/tokio"]);
+}
+
+#[test]
+fn clear_empties_the_store_and_its_metadata() {
+    let cache = synced("cache_clear");
+    block_on(sync::store(&cache, meta("Python"), vec![RepoRecord { stars: 10, ..repo("psf/requests") }], &CancelToken::default())).unwrap();
+    cache.put(NOTES_STORE, "rust-lang/rust", &"keep me").unwrap();
+
+    block_on(RepoCache::clear(&cache, "Rust")).unwrap();
+    let status = block_on(sync::status(&cache, "Rust")).unwrap();
+    assert!(status.is_empty());
+    assert_eq!(status.meta, None);
+    // Other languages and user data are untouched
+    assert_eq!(block_on(sync::status(&cache, "Python")).unwrap().count, 1);
+    assert!(cache.get::<SyncMeta>(SYNC_META_STORE, &meta("Python").store_key()).unwrap().is_some());
+    assert_eq!(cache.get::<String>(NOTES_STORE, "rust-lang/rust").unwrap().as_deref(), Some("keep me"));
+}
+
+#[test]
+fn the_latest_sync_of_any_source_describes_the_store() {
+    let cache = synced("cache_latest_meta");
+    let gitlab = SyncMeta { source: SourceKind::GitLab, host: "gitlab.com".to_string(), synced_at: 1_800_000_000_000.0, ..meta("Rust") };
+    block_on(sync::store(&cache, gitlab.clone(), Vec::new(), &CancelToken::default())).unwrap();
+    assert_eq!(block_on(sync::status(&cache, "Rust")).unwrap().meta, Some(gitlab.written(&[])));
+    // Both slices are remembered separately, and clearing forgets both
+    assert!(cache.get::<SyncMeta>(SYNC_META_STORE, &meta("Rust").store_key()).unwrap().is_some());
+    block_on(RepoCache::clear(&cache, "Rust")).unwrap();
+    assert!(cache.entries(SYNC_META_STORE).unwrap().is_empty());
+}
+
+#[test]
+fn an_old_single_key_blob_is_not_counted_as_repositories() {
+    let cache = NativeStore::at(temp_store_dir("cache_legacy_blob"));
+    cache.put("Rust", "latest_rust", &serde_json::json!([{ "full_name": "rust-lang/rust" }])).unwrap();
+    assert!(block_on(sync::status(&cache, "Rust")).unwrap().is_empty());
+
+    block_on(RepoCache::clear(&cache, "Rust")).unwrap();
+    assert!(cache.entries("Rust").unwrap().is_empty());
+}
+
+#[test]
+fn every_sync_appends_a_history_sample_per_record() {
+    let cache = synced("cache_history");
+    let later = SyncMeta { synced_at: 1_700_086_400_000.0, ..meta("Rust") };
+    block_on(sync::store(&cache, later, vec![RepoRecord { stars: 130, ..repo("rust-lang/rust") }], &CancelToken::default())).unwrap();
+
+    let rust = cache.get::<RepoHistory>(HISTORY_STORE, "rust-lang/rust").unwrap().unwrap();
+    assert_eq!(rust.series(Metric::Stars), [(1_700_000_000_000.0, 100.0), (1_700_086_400_000.0, 130.0)]);
+    let tokio = cache.get::<RepoHistory>(HISTORY_STORE, "tokio-rs/tokio").unwrap().unwrap();
+    assert_eq!(tokio.samples.len(), 1);
+}
+
+#[test]
+fn a_sync_cut_short_after_its_records_still_cleans_up_next_time() {
+    let dir = temp_store_dir("cache_cut_short");
+    let cache = NativeStore::at(&dir);
+    block_on(sync::store(&cache, meta("Rust"), vec![repo("rust-lang/rust"), repo("tokio-rs/tokio")], &CancelToken::default())).unwrap();
+
+    // An unreadable history store fails the snapshot after the records are written, before the final meta
+    let history = dir.join(format!("{}.json", HISTORY_STORE));
+    std::fs::remove_file(&history).unwrap();
+    std::fs::create_dir(&history).unwrap();
+    let second = vec![repo("rust-lang/rust"), repo("serde-rs/serde")];
+    assert!(block_on(sync::store(&cache, meta("Rust"), second, &CancelToken::default())).is_err());
+    let mut keys = cache.entries("Rust").unwrap().into_keys().collect::<Vec<_>>();
+    keys.sort();
+    assert_eq!(keys, ["rust-lang/rust", "serde-rs/serde"]);
+
+    // The next sync still knows serde was written by this source, and drops it
+    std::fs::remove_dir(&history).unwrap();
+    block_on(sync::store(&cache, meta("Rust"), vec![repo("rust-lang/rust")], &CancelToken::default())).unwrap();
+    assert_eq!(cache.entries("Rust").unwrap().into_keys().collect::<Vec<_>>(), ["rust-lang/rust"]);
+    assert_eq!(block_on(sync::status(&cache, "Rust")).unwrap().count, 1);
+}
@@ -0,0 +1,169 @@
+// cloud_sync.rs - pull/push round trips through the in-process LocalBridge, and how pulled records merge
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use waffle_search::cloud_sync::{remote_wins, CloudSync, LocalBridge, SyncDocument, SyncRecord, SyncStatus, SyncTarget};
+use waffle_search::favorites::{Favorite, Favorites, FAVORITE_KIND};
+
+const USER: &str = "user-1";
+
+/// One device's notes: last writer wins per key, like the real documents.
+#[derive(Default)]
+struct Device {
+    records: Mutex<Vec<SyncRecord>>,
+    revision: AtomicU64,
+}
+
+impl Device {
+    fn edit(&self, key: &str, text: &str, at: f64) {
+        let mut records = self.records.lock().unwrap();
+        records.retain(|r| r.key != key);
+        records.push(record(key, text, at));
+        self.revision.fetch_add(1, Ordering::SeqCst);
+    }
+
+    fn text(&self, key: &str) -> Option<String> {
+        let records = self.records.lock().unwrap();
+        records.iter().find(|r| r.key == key).and_then(|r| r.payload.as_str().map(str::to_string))
+    }
+}
+
+impl SyncTarget for Device {
+    fn kinds(&self) -> &'static [&'static str] {
+        &["note"]
+    }
+
+    fn local_records(&self) -> Vec<SyncRecord> {
+        self.records.lock().unwrap().clone()
+    }
+
+    fn apply_remote(&self, remote: &[SyncRecord]) {
+        let mut records = self.records.lock().unwrap();
+        for incoming in remote {
+            let local = records.iter().find(|r| r.key == incoming.key).map(|r| r.updated_at);
+            if remote_wins(local, incoming) {
+                records.retain(|r| r.key != incoming.key);
+                records.push(SyncRecord { seq: None, ..incoming.clone() });
+            }
+        }
+    }
+
+    fn revision(&self) -> u64 {
+        self.revision.load(Ordering::SeqCst)
+    }
+}
+
+fn record(key: &str, text: &str, at: f64) -> SyncRecord {
+    SyncRecord {
+        kind: "note".to_string(),
+        key: key.to_string(),
+        payload: serde_json::json!(text),
+        updated_at: at,
+        deleted: false,
+        seq: None,
+    }
+}
+
+/// Sync and let the bridge's answers (pull, then push) play out.
+fn round_trip(sync: &CloudSync, device: &Device) {
+    sync.sync(USER);
+    for _ in 0..3 {
+        sync.poll(&[device]);
+    }
+}
+
+#[test]
+fn offline_edits_pushed_later_still_reach_other_devices() {
+    let server = LocalBridge::new();
+    let (laptop, phone) = (Device::default(), Device::default());
+    let laptop_sync = CloudSync::with_bridge(Arc::new(server.clone()));
+    let phone_sync = CloudSync::with_bridge(Arc::new(server.clone()));
+
+    // The phone edits while offline; its clock says 100
+    server.set_offline(true);
+    phone.edit("rust-lang/rust", "from the train", 100.0);
+    round_trip(&phone_sync, &phone);
+    assert!(matches!(phone_sync.status(), SyncStatus::Offline(_)));
+    server.set_offline(false);
+
+    // Meanwhile the laptop pushes a later edit and pulls past it
+    laptop.edit("tokio-rs/tokio", "at my desk", 200.0);
+    round_trip(&laptop_sync, &laptop);
+    round_trip(&laptop_sync, &laptop);
+    assert_eq!(server.rows(USER).len(), 1);
+
+    // The phone reconnects: its older-stamped edit lands after the laptop's pull
+    round_trip(&phone_sync, &phone);
+    assert_eq!(phone.text("tokio-rs/tokio").as_deref(), Some("at my desk"));
+    assert_eq!(phone_sync.pending_count(&[&phone]), 0);
+
+    round_trip(&laptop_sync, &laptop);
+    assert_eq!(laptop.text("rust-lang/rust").as_deref(), Some("from the train"));
+    assert!(matches!(laptop_sync.status(), SyncStatus::Synced { .. }));
+}
+
+#[test]
+fn pulls_only_return_rows_written_after_the_cursor() {
+    let server = LocalBridge::new();
+    let device = Device::default();
+    let sync = CloudSync::with_bridge(Arc::new(server.clone()));
+    device.edit("a", "one", 1.0);
+    round_trip(&sync, &device);
+    device.edit("b", "two", 2.0);
+    round_trip(&sync, &device);
+    let mut seqs = server.rows(USER).iter().map(|r| r.seq).collect::<Vec<_>>();
+    seqs.sort();
+    assert_eq!(seqs, [Some(1), Some(2)]);
+}
+
+fn favorite(full_name: &str, at: f64) -> SyncRecord {
+    let favorite = Favorite {
+        full_name: full_name.to_string(),
+        html_url: None,
+        description: None,
+        language: None,
+        starred_at: at,
+    };
+    SyncRecord {
+        kind: FAVORITE_KIND.to_string(),
+        key: full_name.to_string(),
+        payload: serde_json::to_value(favorite).unwrap(),
+        updated_at: at,
+        deleted: false,
+        seq: None,
+    }
+}
+
+fn deletion(full_name: &str, at: f64) -> SyncRecord {
+    SyncRecord { deleted: true, payload: serde_json::Value::Null, ..favorite(full_name, at) }
+}
+
+#[test]
+fn the_newer_side_wins_each_favorite() {
+    let mut favorites = Favorites::default();
+    favorites.apply_sync_records(&[favorite("rust-lang/rust", 100.0), favorite("tokio-rs/tokio", 100.0)]);
+    assert_eq!(favorites.starred.len(), 2);
+
+    // An older copy never replaces what is here; an equal one doesn't either
+    favorites.apply_sync_records(&[favorite("rust-lang/rust", 50.0), favorite("tokio-rs/tokio", 100.0)]);
+    assert!(favorites.starred.iter().all(|f| f.starred_at == 100.0));
+    favorites.apply_sync_records(&[favorite("rust-lang/rust", 150.0)]);
+    assert_eq!(favorites.starred.iter().find(|f| f.full_name == "rust-lang/rust").unwrap().starred_at, 150.0);
+    assert_eq!(favorites.starred.len(), 2);
+}
+
+#[test]
+fn deletions_sync_as_tombstones_that_outrank_older_copies() {
+    let mut favorites = Favorites::default();
+    favorites.apply_sync_records(&[favorite("rust-lang/rust", 100.0)]);
+    favorites.apply_sync_records(&[deletion("rust-lang/rust", 200.0)]);
+    assert!(!favorites.is_starred("rust-lang/rust"));
+    assert_eq!(favorites.to_sync_records(), [deletion("rust-lang/rust", 200.0)]);
+
+    // A stale copy from a device that hasn't seen the deletion stays deleted
+    favorites.apply_sync_records(&[favorite("rust-lang/rust", 150.0)]);
+    assert!(!favorites.is_starred("rust-lang/rust"));
+    // Starring again later brings it back and clears the tombstone
+    favorites.apply_sync_records(&[favorite("rust-lang/rust", 300.0)]);
+    assert!(favorites.is_starred("rust-lang/rust"));
+    assert!(favorites.removed.is_empty());
+}
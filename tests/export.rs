@@ -0,0 +1,68 @@
+// export.rs - CSV, JSON, NDJSON and Markdown renderings of a result list
+mod support;
+
+use support::repo;
+use waffle_search::export::{self, Column, ExportFormat, ExportOptions, UNCATEGORIZED};
+use waffle_search::import;
+use waffle_search::record::{LicenseInfo, RepoRecord};
+
+fn topics(names: &[&str]) -> Vec<String> {
+    names.iter().map(|t| t.to_string()).collect()
+}
+
+#[test]
+fn csv_quotes_only_fields_that_need_it() {
+    let serde = RepoRecord {
+        stars: 9000,
+        topics: topics(&["serde", "serialization"]),
+        description: Some("Serialize, \"fast\"".to_string()),
+        license: Some(LicenseInfo { name: "MIT License".to_string(), key: Some("mit".to_string()), spdx_id: Some("MIT".to_string()) }),
+        ..repo("serde-rs/serde")
+    };
+    let csv = export::to_csv(&[serde], &[Column::FullName, Column::Description, Column::Stars, Column::License, Column::Topics]);
+    assert_eq!(csv, "full_name,description,stars,license,topics\nserde-rs/serde,\"Serialize, \"\"fast\"\"\",9000,MIT,serde;serialization\n");
+}
+
+#[test]
+fn markdown_groups_by_first_topic_best_starred_first() {
+    let repos = [
+        RepoRecord { stars: 4000, topics: topics(&["async"]), ..repo("smol-rs/smol") },
+        RepoRecord {
+            stars: 25000,
+            topics: topics(&["async", "runtime"]),
+            description: Some("An async runtime\nfor Rust".to_string()),
+            ..repo("tokio-rs/tokio")
+        },
+        RepoRecord { stars: 10, ..repo("someone/misc") },
+    ];
+    let markdown = export::to_markdown(&repos, "Awesome Rust");
+    // Sections sort by name, so the capitalised catch-all comes before lowercase topics
+    let expected = format!(
+        "# Awesome Rust\n\n- [{0}](#{1})\n- [async](#async)\n\n## {0}\n\n\
+         - [someone/misc](https://github.com/someone/misc) ⭐ 10\n\n## async\n\n\
+         - [tokio-rs/tokio](https://github.com/tokio-rs/tokio) ⭐ 25000 - An async runtime for Rust\n\
+         - [smol-rs/smol](https://github.com/smol-rs/smol) ⭐ 4000\n",
+        UNCATEGORIZED,
+        UNCATEGORIZED.to_lowercase()
+    );
+    assert_eq!(markdown, expected);
+}
+
+#[test]
+fn json_and_ndjson_exports_import_back_unchanged() {
+    let serde = RepoRecord { stars: 9000, topics: topics(&["serde"]), description: Some("Serialization".to_string()), ..repo("serde-rs/serde") };
+    let repos = vec![serde, RepoRecord { stars: 25000, ..repo("tokio-rs/tokio") }];
+    for format in [ExportFormat::Json, ExportFormat::Ndjson] {
+        let text = export::export(&repos, &ExportOptions { format, ..Default::default() }).unwrap();
+        assert_eq!(import::parse(&text).unwrap().repos, repos, "{:?}", format);
+    }
+}
+
+#[test]
+fn formats_parse_from_extension_or_label() {
+    assert_eq!(ExportFormat::parse("md"), Some(ExportFormat::Markdown));
+    assert_eq!(ExportFormat::parse("Markdown"), Some(ExportFormat::Markdown));
+    assert_eq!(ExportFormat::parse("NDJSON"), Some(ExportFormat::Ndjson));
+    assert_eq!(ExportFormat::parse("xlsx"), None);
+    assert_eq!(export::file_name("My Stars", ExportFormat::Csv), "waffle_my_stars.csv");
+}
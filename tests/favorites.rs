@@ -0,0 +1,83 @@
+// favorites.rs - starring and named collections, keyed the way records are
+use waffle_search::favorites::Favorites;
+use waffle_search::record::RepoRecord;
+use waffle_search::source::SourceKind;
+
+fn repo(url: &str, source: SourceKind) -> RepoRecord {
+    let full_name = url.splitn(4, '/').nth(3).unwrap_or_default().to_string();
+    RepoRecord {
+        html_url: url.to_string(),
+        full_name,
+        source,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn toggling_stars_and_unstars_by_record_key() {
+    let mut favorites = Favorites::default();
+    let rust = repo("https://github.com/rust-lang/rust", SourceKind::GitHub);
+    let gitlab = repo("https://gitlab.com/rust-lang/rust", SourceKind::GitLab);
+
+    assert!(favorites.toggle_star(&rust));
+    assert!(favorites.toggle_star(&gitlab));
+    // Same full_name on two forges stays two favorites
+    assert!(favorites.is_starred("rust-lang/rust"));
+    assert!(favorites.is_starred("gitlab.com/rust-lang/rust"));
+    assert_eq!(favorites.starred.len(), 2);
+
+    assert!(!favorites.toggle_star(&rust));
+    assert!(!favorites.is_starred("rust-lang/rust"));
+    // The removal is remembered so it can sync
+    assert_eq!(favorites.removed.len(), 1);
+    favorites.star(&rust);
+    assert!(favorites.removed.is_empty());
+}
+
+#[test]
+fn collection_names_are_unique_and_not_blank() {
+    let mut favorites = Favorites::default();
+    assert!(favorites.create_collection(" Reading list "));
+    assert!(!favorites.create_collection("Reading list"));
+    assert!(!favorites.create_collection("   "));
+    assert_eq!(favorites.collections.len(), 1);
+    assert_eq!(favorites.collections[0].name, "Reading list");
+}
+
+#[test]
+fn collections_keep_the_users_order() {
+    let mut favorites = Favorites::default();
+    favorites.create_collection("Async");
+    for name in ["tokio-rs/tokio", "async-rs/async-std", "smol-rs/smol"] {
+        assert!(favorites.add_to_collection("Async", name));
+    }
+    assert!(!favorites.add_to_collection("Async", "tokio-rs/tokio"));
+    assert!(!favorites.add_to_collection("Missing", "tokio-rs/tokio"));
+
+    favorites.move_in_collection("Async", 2, 0);
+    favorites.move_in_collection("Async", 0, 9);
+    assert_eq!(favorites.collection("Async").unwrap().repos, ["smol-rs/smol", "tokio-rs/tokio", "async-rs/async-std"]);
+
+    assert!(favorites.remove_from_collection("Async", "tokio-rs/tokio"));
+    assert_eq!(favorites.collections_containing("smol-rs/smol"), ["Async"]);
+    assert!(favorites.collections_containing("tokio-rs/tokio").is_empty());
+
+    // Removing what isn't there is no edit, so it can't outrank a real one from another device
+    favorites.collections[0].updated_at = 1.0;
+    assert!(!favorites.remove_from_collection("Async", "tokio-rs/tokio"));
+    assert!(!favorites.remove_from_collection("Missing", "smol-rs/smol"));
+    assert_eq!(favorites.collection("Async").unwrap().updated_at, 1.0);
+}
+
+#[test]
+fn set_collection_creates_and_reports_changes() {
+    let mut favorites = Favorites::default();
+    let stars = vec!["serde-rs/serde".to_string(), "tokio-rs/tokio".to_string()];
+    assert!(favorites.set_collection("My Stars", stars.clone()));
+    assert!(!favorites.set_collection("My Stars", stars.clone()));
+    assert_eq!(favorites.collection("My Stars").unwrap().repos, stars);
+
+    favorites.delete_collection("My Stars");
+    assert!(favorites.collection("My Stars").is_none());
+    assert_eq!(favorites.removed.len(), 1);
+}
@@ -0,0 +1,70 @@
+// graphql.rs - the GraphQL search mapping: the same numbers a REST sync would store
+use waffle_search::github::GithubSource;
+use waffle_search::graphql::{self, SyncBackend};
+use waffle_search::record::{self, README_EXCERPT_CHARS};
+use waffle_search::source::RepoSource;
+
+fn fixture() -> Vec<record::RepoRecord> {
+    let bytes = std::fs::read("tests/fixtures/graphql_search.json").unwrap();
+    record::from_wire_all(graphql::parse_search(&bytes).unwrap())
+}
+
+#[test]
+fn open_issues_count_pull_requests_like_rest() {
+    let records = fixture();
+    assert_eq!(records.len(), 2, "non-repository nodes are dropped");
+    let rust = &records[0];
+    assert_eq!(rust.full_name, "rust-lang/rust");
+    assert_eq!(rust.open_issues, 9500 + 700);
+    assert_eq!(rust.contributors, Some(4200));
+    assert_eq!(rust.languages, ["Rust", "Python"]);
+    assert_eq!(rust.latest_release.as_ref().map(|r| r.tag.as_str()), Some("1.78.0"));
+}
+
+#[test]
+fn the_readme_is_kept_as_a_trimmed_excerpt() {
+    let records = fixture();
+    assert_eq!(
+        records[0].readme.as_deref(),
+        Some("# The Rust Programming Language\n\nThis is the main source code repository for Rust.")
+    );
+    assert_eq!(records[1].readme, None);
+
+    let long = "word ".repeat(README_EXCERPT_CHARS);
+    let excerpt = record::readme_excerpt(&long);
+    assert_eq!(excerpt.chars().count(), README_EXCERPT_CHARS);
+    assert!(excerpt.ends_with('…'));
+}
+
+#[test]
+fn graphql_errors_are_reported() {
+    let err = graphql::parse_search(br#"{"errors":[{"message":"Bad credentials"}]}"#).unwrap_err();
+    assert_eq!(err, "GraphQL error: Bad credentials");
+}
+
+fn source(base_url: &str, token: Option<&str>) -> GithubSource {
+    GithubSource {
+        backend: SyncBackend::GraphQl,
+        base_url: base_url.to_string(),
+        token: token.map(str::to_string),
+        topic: "cli".to_string(),
+    }
+}
+
+#[test]
+fn searches_post_the_query_with_a_star_sort() {
+    let request = source("https://github.com", Some("ghp_test")).search_request("Rust").unwrap();
+    assert_eq!((request.method.as_str(), request.url.as_str()), ("POST", "https://api.github.com/graphql"));
+    let body = serde_json::from_slice::<serde_json::Value>(&request.body).unwrap();
+    assert_eq!(body["variables"]["q"], "language:Rust topic:cli sort:stars-desc");
+    assert_eq!(body["query"], graphql::SEARCH_QUERY);
+
+    let enterprise = source("https://github.example.com/", Some("ghp_test")).search_request("Rust").unwrap();
+    assert_eq!(enterprise.url, "https://github.example.com/api/graphql");
+}
+
+#[test]
+fn graphql_needs_a_token() {
+    let err = source("https://github.com", None).search_request("Rust").unwrap_err();
+    assert_eq!(err, "GraphQL sync needs a token for github.com");
+}
@@ -0,0 +1,65 @@
+// health.rs - the 0-100 health score and how its weights combine the factors
+use waffle_search::health::{Factor, HealthWeights, FRESH_DAYS, STALE_DAYS};
+use waffle_search::history::DAY_MS;
+use waffle_search::notes::Notes;
+use waffle_search::query::{Query, QueryContext};
+use waffle_search::record::{LicenseInfo, RepoRecord};
+
+const NOW: f64 = 1_700_000_000_000.0;
+
+fn healthy() -> RepoRecord {
+    RepoRecord {
+        full_name: "tokio-rs/tokio".to_string(),
+        stars: 1_000,
+        open_issues: 0,
+        pushed_at: Some(NOW - DAY_MS),
+        has_issues: true,
+        license: Some(LicenseInfo { name: "MIT License".to_string(), key: Some("mit".to_string()), spdx_id: Some("MIT".to_string()) }),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn recency_is_full_when_fresh_and_zero_when_stale() {
+    let pushed = |days: f64| RepoRecord { pushed_at: Some(NOW - days * DAY_MS), ..Default::default() };
+    assert_eq!(Factor::Recency.value(&pushed(FRESH_DAYS), NOW), 1.0);
+    assert_eq!(Factor::Recency.value(&pushed(STALE_DAYS), NOW), 0.0);
+    let midway = Factor::Recency.value(&pushed((FRESH_DAYS + STALE_DAYS) / 2.0), NOW);
+    assert!((midway - 0.5).abs() < 1e-9);
+    assert_eq!(Factor::Recency.value(&RepoRecord::default(), NOW), 0.0, "never pushed");
+}
+
+#[test]
+fn issues_per_star_bottom_out_at_the_limit() {
+    let issues = |open_issues: u64| RepoRecord { stars: 1_000, open_issues, ..Default::default() };
+    assert_eq!(Factor::Issues.value(&issues(0), NOW), 1.0);
+    assert_eq!(Factor::Issues.value(&issues(50), NOW), 0.5);
+    assert_eq!(Factor::Issues.value(&issues(5_000), NOW), 0.0);
+}
+
+#[test]
+fn the_score_is_a_weighted_average() {
+    let weights = HealthWeights::default();
+    assert_eq!(weights.score(&healthy(), NOW), 100.0);
+
+    let archived = RepoRecord { archived: true, ..healthy() };
+    assert_eq!(weights.score(&archived, NOW), 80.0);
+    // Only the ratios matter, and negative weights count as zero
+    let only_maintained = HealthWeights { recency: 0.0, issues: 0.0, maintained: 2.0, license: -5.0, issue_tracker: 0.0, original: 0.0 };
+    assert_eq!(only_maintained.score(&archived, NOW), 0.0);
+    assert_eq!(only_maintained.score(&healthy(), NOW), 100.0);
+    let nothing = HealthWeights { recency: 0.0, issues: 0.0, maintained: 0.0, license: 0.0, issue_tracker: 0.0, original: 0.0 };
+    assert_eq!(nothing.score(&healthy(), NOW), 0.0);
+}
+
+#[test]
+fn the_health_qualifier_uses_the_configured_weights() {
+    let notes = Notes::default();
+    let archived = RepoRecord { archived: true, ..healthy() };
+    let query = Query::parse("health:>=90");
+    let ctx = QueryContext { now: NOW, ..QueryContext::new(&notes) };
+    assert!(query.matches(&healthy(), &ctx));
+    assert!(!query.matches(&archived, &ctx));
+    let lenient = QueryContext { health: HealthWeights { maintained: 0.0, ..HealthWeights::default() }, ..ctx };
+    assert!(query.matches(&archived, &lenient));
+}
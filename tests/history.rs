@@ -0,0 +1,100 @@
+// history.rs - per-sync samples: ordering, the size cap, and the changes and trends read back from them
+mod support;
+
+use std::collections::HashMap;
+use support::repo;
+use waffle_search::history::{self, Metric, RepoHistory, Sample, DAY_MS, MAX_SAMPLES};
+use waffle_search::notes::Notes;
+use waffle_search::query::{QueryContext, SortOrder};
+use waffle_search::record::RepoRecord;
+
+const T0: f64 = 1_700_000_000_000.0;
+
+fn stars(at_day: f64, stars: u64) -> Sample {
+    Sample(T0 + at_day * DAY_MS, stars, 0, 0, 0)
+}
+
+#[test]
+fn samples_stay_ordered_and_bounded() {
+    let mut history = RepoHistory::new("rust-lang/rust");
+    history.push(stars(2.0, 120));
+    history.push(stars(0.0, 100));
+    history.push(stars(1.0, 110));
+    assert_eq!(history.series(Metric::Stars).iter().map(|p| p.1).collect::<Vec<_>>(), [100.0, 110.0, 120.0]);
+
+    for day in 3..(MAX_SAMPLES as u64 + 10) {
+        history.push(stars(day as f64, 100 + day));
+    }
+    assert_eq!(history.samples.len(), MAX_SAMPLES);
+    // The oldest samples go first
+    assert_eq!(history.samples[0], stars(10.0, 110));
+}
+
+#[test]
+fn changes_are_measured_from_the_sample_before_the_window() {
+    let mut history = RepoHistory::new("rust-lang/rust");
+    history.push(stars(0.0, 100));
+    assert_eq!(history.change_since(Metric::Stars, T0), None, "one sample is not a change");
+    history.push(stars(5.0, 150));
+    history.push(stars(10.0, 190));
+
+    let now = T0 + 10.0 * DAY_MS;
+    assert_eq!(history.stars_gained(now, 7.0), Some(190 - 100));
+    assert_eq!(history.stars_gained(now, 4.0), Some(190 - 150));
+    // Windows older than the history fall back to the oldest sample
+    assert_eq!(history.stars_gained(now, 365.0), Some(90));
+}
+
+#[test]
+fn samples_store_as_bare_arrays() {
+    let sample = Sample(T0, 10, 2, 3, 4);
+    assert_eq!(serde_json::to_string(&sample).unwrap(), "[1700000000000.0,10,2,3,4]");
+}
+
+#[test]
+fn a_sync_sample_starts_a_history_under_the_record_key() {
+    let repo = RepoRecord {
+        full_name: "group/project".to_string(),
+        html_url: "https://gitlab.com/group/project".to_string(),
+        stars: 7,
+        forks: 1,
+        open_issues: 2,
+        ..Default::default()
+    };
+    let history = history::with_sample(None, &repo, T0);
+    assert_eq!(history.full_name, "gitlab.com/group/project");
+    assert_eq!(history.samples, [Sample(T0, 7, 1, 2, 0)]);
+}
+
+#[test]
+fn velocity_is_per_day_over_the_span_actually_covered() {
+    let mut history = RepoHistory::new("rust-lang/rust");
+    history.push(stars(0.0, 100));
+    let now = T0 + 2.0 * DAY_MS;
+    assert_eq!(history.velocity(Metric::Stars, now, 7.0), None, "needs a span of time");
+    history.push(stars(2.0, 140));
+    // Only two days of history inside a seven day window
+    assert_eq!(history.velocity(Metric::Stars, now, 7.0), Some(20.0));
+}
+
+#[test]
+fn trending_sorts_by_velocity_and_sinks_repos_without_history() {
+    let trend = |name: &str, from: u64, to: u64| {
+        let mut history = RepoHistory::new(name);
+        history.push(stars(0.0, from));
+        history.push(stars(7.0, to));
+        (name.to_string(), history)
+    };
+    let histories = HashMap::from([trend("big/steady", 90_000, 90_070), trend("small/rising", 100, 800)]);
+    let notes = Notes::default();
+    let ctx = QueryContext { histories: Some(&histories), now: T0 + 7.0 * DAY_MS, ..QueryContext::new(&notes) };
+
+    let mut repos = vec![
+        RepoRecord { stars: 5, ..repo("new/unknown") },
+        RepoRecord { stars: 90_070, ..repo("big/steady") },
+        RepoRecord { stars: 800, ..repo("small/rising") },
+    ];
+    SortOrder::TrendingWeek.sort(&mut repos, &ctx);
+    assert_eq!(repos.iter().map(|r| r.full_name.as_str()).collect::<Vec<_>>(), ["small/rising", "big/steady", "new/unknown"]);
+    assert_eq!(SortOrder::TrendingWeek.trend(&repos[0], &ctx), Some(100.0));
+}
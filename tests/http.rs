@@ -0,0 +1,93 @@
+// http.rs - middleware behaviour, with a canned in-process client in place of the network
+mod support;
+
+use std::sync::{Arc, Mutex};
+use support::wait_for;
+use waffle_search::http::{Auth, Callback, Credential, Http, HttpClient, RateLimits, ResponseCache};
+use waffle_search::utility::now_millis;
+
+/// Answers every request with the next queued response and remembers what was sent.
+#[derive(Default)]
+struct Canned {
+    responses: Mutex<Vec<ehttp::Response>>,
+    sent: Mutex<Vec<ehttp::Request>>,
+}
+
+impl Canned {
+    fn new(responses: Vec<ehttp::Response>) -> Arc<Self> {
+        Arc::new(Self { responses: Mutex::new(responses), ..Default::default() })
+    }
+
+    fn sent(&self) -> Vec<ehttp::Request> {
+        self.sent.lock().unwrap().clone()
+    }
+}
+
+impl HttpClient for Canned {
+    fn fetch(&self, request: ehttp::Request, done: Callback) {
+        let mut response = self.responses.lock().unwrap().remove(0);
+        response.url = request.url.clone();
+        self.sent.lock().unwrap().push(request);
+        done(Ok(response));
+    }
+}
+
+fn response(status: u16, headers: &[(&str, &str)], body: &str) -> ehttp::Response {
+    ehttp::Response {
+        url: String::new(),
+        ok: (200..300).contains(&status),
+        status,
+        status_text: String::new(),
+        headers: ehttp::Headers::new(headers),
+        bytes: body.as_bytes().to_vec(),
+    }
+}
+
+fn get(http: &Http, url: &str) -> ehttp::Result<ehttp::Response> {
+    let request = ehttp::Request::get(url);
+    wait_for(|done| http.fetch(request, done))
+}
+
+#[test]
+fn credentials_only_go_to_their_host() {
+    let client = Canned::new(vec![response(200, &[], "{}"), response(200, &[], "{}")]);
+    let http = Http::new(client.clone()).with(Auth(vec![Credential::bearer("api.github.com", "ghp_secret")]));
+    get(&http, "https://api.github.com/user/starred").unwrap();
+    get(&http, "https://crates.io/api/v1/crates/serde").unwrap();
+    let sent = client.sent();
+    assert_eq!(sent[0].headers.get("authorization"), Some("Bearer ghp_secret"));
+    assert_eq!(sent[1].headers.get("authorization"), None);
+}
+
+#[test]
+fn exhausted_hosts_are_not_asked_again_until_reset() {
+    let reset = ((now_millis() / 1000.0) as u64 + 600).to_string();
+    let client = Canned::new(vec![
+        response(403, &[("x-ratelimit-limit", "60"), ("x-ratelimit-remaining", "0"), ("x-ratelimit-reset", &reset)], ""),
+        response(200, &[], "{}"),
+    ]);
+    let limits = RateLimits::default();
+    let http = Http::new(client.clone()).with(limits.clone());
+    get(&http, "https://api.github.com/search/repositories").unwrap();
+    let limit = limits.get("api.github.com").unwrap();
+    assert_eq!((limit.limit, limit.remaining), (Some(60), 0));
+
+    let err = get(&http, "https://api.github.com/search/repositories").unwrap_err();
+    assert!(err.starts_with("Rate limited by api.github.com"), "{}", err);
+    assert_eq!(client.sent().len(), 1);
+    // Other hosts are unaffected
+    get(&http, "https://gitlab.com/api/v4/projects").unwrap();
+}
+
+#[test]
+fn not_modified_gets_the_remembered_body() {
+    let client = Canned::new(vec![response(200, &[("etag", "\"v1\"")], "[1,2,3]"), response(304, &[], "")]);
+    let http = Http::new(client.clone()).with(ResponseCache::default());
+    let url = "https://registry.npmjs.org/left-pad";
+    get(&http, url).unwrap();
+    let second = get(&http, url).unwrap();
+    assert_eq!(second.status, 304);
+    assert!(second.ok);
+    assert_eq!(second.bytes, b"[1,2,3]");
+    assert_eq!(client.sent()[1].headers.get("if-none-match"), Some("\"v1\""));
+}
@@ -0,0 +1,84 @@
+// import.rs - parsing import files and merging them into a language store
+mod support;
+
+use support::{block_on, repo, temp_store_dir};
+use waffle_search::idb::language_named;
+use waffle_search::import::{self, ImportSummary};
+use waffle_search::native::NativeStore;
+use waffle_search::record::RepoRecord;
+use waffle_search::registry::PackageInfo;
+use waffle_search::sync::RepoCache;
+
+#[test]
+fn import_writes_new_and_changed_records_and_keeps_package_data() {
+    let cache = NativeStore::at(temp_store_dir("import_into"));
+    let mut enriched = RepoRecord { stars: 9000, ..repo("serde-rs/serde") };
+    enriched.package = Some(PackageInfo { name: "serde".to_string(), version: "1.0.0".to_string(), ..Default::default() });
+    enriched.package_checked_at = Some(1.0);
+    let tokio = RepoRecord { stars: 25000, ..repo("tokio-rs/tokio") };
+    block_on(cache.put_records("Rust", &[enriched.clone(), tokio.clone()])).unwrap();
+
+    let incoming = [RepoRecord { stars: 9500, ..repo("serde-rs/serde") }, tokio, RepoRecord { stars: 5, ..repo("new/crate") }];
+    let text = serde_json::to_string(&incoming).unwrap();
+    let summary = block_on(import::import_into(&cache, "Rust", import::parse(&text).unwrap())).unwrap();
+    assert_eq!(summary, ImportSummary { inserted: 1, updated: 1, skipped: 1, problems: vec![] });
+
+    let stored = block_on(RepoCache::records(&cache, "Rust")).unwrap();
+    let serde = stored.iter().find(|r| r.full_name == "serde-rs/serde").unwrap();
+    assert_eq!(serde.stars, 9500);
+    assert_eq!(serde.package, enriched.package);
+    assert_eq!(serde.package_checked_at, Some(1.0));
+    assert_eq!(stored.len(), 3);
+}
+
+fn names(parsed: &import::Parsed) -> Vec<&str> {
+    parsed.repos.iter().map(|r| r.full_name.as_str()).collect()
+}
+
+#[test]
+fn github_search_dumps_import_every_item() {
+    let parsed = import::parse(support::SEARCH_RUST).unwrap();
+    assert_eq!(names(&parsed), ["rust-lang/rust", "denoland/deno", "example/unlicensed"]);
+    assert_eq!(parsed.repos[0].stars, 101000);
+    assert_eq!(parsed.summary, ImportSummary::default());
+}
+
+#[test]
+fn bad_lines_and_items_are_skipped_with_their_position() {
+    let ndjson = format!("{}\n\nnot json\n{{\"name\":\"no-full-name\"}}\n", serde_json::to_string(&repo("serde-rs/serde")).unwrap());
+    let parsed = import::parse(&ndjson).unwrap();
+    assert_eq!(names(&parsed), ["serde-rs/serde"]);
+    assert_eq!(parsed.summary.skipped, 2);
+    assert!(parsed.summary.problems[0].starts_with("line 3: "), "{:?}", parsed.summary.problems);
+    assert_eq!(parsed.summary.problems[1], "line 4: not a repository with a full_name");
+
+    let array = import::parse(r#"[{"full_name":"a/b"}, 42]"#).unwrap();
+    assert_eq!(names(&array), ["a/b"]);
+    assert_eq!(array.summary.problems, ["item 2: not a repository with a full_name"]);
+}
+
+#[test]
+fn files_that_are_not_repositories_are_rejected() {
+    let error = |text: &str| import::parse(text).err().unwrap_or_default();
+    assert!(error("hello world").starts_with("Not NDJSON of repositories"));
+    assert!(error(r#"{"items": 3}"#).starts_with("Not a GitHub search response"));
+    assert!(error("[1, 2").starts_with("Not a JSON array of repositories"));
+}
+
+#[test]
+fn duplicates_in_a_file_count_once() {
+    let text = serde_json::to_string(&[RepoRecord { stars: 1, ..repo("a/b") }, RepoRecord { stars: 2, ..repo("a/b") }]).unwrap();
+    let (writes, summary) = import::merge(&Default::default(), import::parse(&text).unwrap());
+    assert_eq!(writes.len(), 1);
+    assert_eq!(writes[0].1.stars, 1, "the first copy wins");
+    assert_eq!((summary.inserted, summary.skipped), (1, 1));
+    assert_eq!(summary.problems, ["a/b appears more than once"]);
+}
+
+#[test]
+fn languages_are_matched_to_their_store() {
+    assert_eq!(language_named("rust"), Ok("Rust"));
+    assert_eq!(language_named(" my stars "), Ok("My Stars"));
+    let err = language_named("Cobol").unwrap_err();
+    assert!(err.starts_with("Unknown language Cobol; expected one of Rust, Python"), "{}", err);
+}
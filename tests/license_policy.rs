@@ -0,0 +1,61 @@
+// license_policy.rs - classifying repositories against allowed / review / denied license lists
+use waffle_search::license_policy::{license_id, LicensePolicy, UnlicensedHandling, Verdict, NOASSERTION};
+use waffle_search::notes::Notes;
+use waffle_search::query::{Query, QueryContext};
+use waffle_search::record::{LicenseInfo, RepoRecord};
+
+fn licensed(name: &str, key: Option<&str>, spdx_id: Option<&str>) -> RepoRecord {
+    RepoRecord {
+        full_name: format!("someone/{}", name.to_lowercase().replace(' ', "-")),
+        license: Some(LicenseInfo { name: name.to_string(), key: key.map(str::to_string), spdx_id: spdx_id.map(str::to_string) }),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn the_spdx_id_is_preferred_then_key_then_name() {
+    assert_eq!(license_id(&licensed("MIT License", Some("mit"), Some("MIT"))).as_deref(), Some("MIT"));
+    assert_eq!(license_id(&licensed("MIT License", Some("mit"), None)).as_deref(), Some("mit"));
+    assert_eq!(license_id(&licensed("Custom", None, None)).as_deref(), Some("Custom"));
+    assert_eq!(license_id(&licensed("", None, None)).as_deref(), Some(NOASSERTION));
+    assert_eq!(license_id(&RepoRecord::default()), None);
+}
+
+#[test]
+fn lists_match_case_insensitively_and_denied_wins() {
+    let mut policy = LicensePolicy::default();
+    assert_eq!(policy.classify(&licensed("MIT License", Some("mit"), None)), Verdict::Allowed);
+    assert_eq!(policy.classify(&licensed("GNU GPL v3", None, Some("GPL-3.0"))), Verdict::Review);
+    assert_eq!(policy.classify(&licensed("GNU AGPL v3", None, Some("AGPL-3.0"))), Verdict::Denied);
+    assert_eq!(policy.classify(&licensed("Other", Some("other"), Some(NOASSERTION))), Verdict::Unlisted);
+    assert_eq!(policy.classify(&RepoRecord::default()), Verdict::Unlicensed);
+
+    policy.denied.push("mit".to_string());
+    assert_eq!(policy.classify(&licensed("MIT License", None, Some("MIT"))), Verdict::Denied);
+}
+
+#[test]
+fn unlicensed_handling_decides_keeping_and_flagging() {
+    let unlicensed = RepoRecord::default();
+    let policy = |unlicensed| LicensePolicy { unlicensed, ..LicensePolicy::default() };
+    assert!(!policy(UnlicensedHandling::Drop).keeps(&unlicensed));
+    assert!(policy(UnlicensedHandling::Keep).keeps(&unlicensed));
+    assert!(!policy(UnlicensedHandling::Keep).flags(Verdict::Unlicensed));
+    assert!(policy(UnlicensedHandling::Flag).flags(Verdict::Unlicensed));
+    // Everything but allowed licenses is called out
+    assert!(!LicensePolicy::default().flags(Verdict::Allowed));
+    assert!(LicensePolicy::default().flags(Verdict::Unlisted));
+}
+
+#[test]
+fn policy_and_license_qualifiers() {
+    let repos = [licensed("MIT License", Some("mit"), Some("MIT")), licensed("GNU AGPL v3", None, Some("AGPL-3.0")), RepoRecord::default()];
+    let notes = Notes::default();
+    let ctx = QueryContext::new(&notes);
+    let count = |query: &str| repos.iter().filter(|r| Query::parse(query).matches(r, &ctx)).count();
+    assert_eq!(count("policy:denied"), 1);
+    assert_eq!(count("-policy:allowed"), 2);
+    assert_eq!(count("license:mit"), 1);
+    assert_eq!(count("license:agpl"), 1, "license names match by substring");
+    assert_eq!(Verdict::parse("REVIEW"), Some(Verdict::Review));
+}
@@ -0,0 +1,71 @@
+// notes.rs - private notes and tags, and the query qualifiers that search them
+use waffle_search::notes::Notes;
+use waffle_search::query::{Clause, Filter, Query, QueryContext};
+use waffle_search::record::RepoRecord;
+
+fn repo(node_id: &str, full_name: &str) -> RepoRecord {
+    RepoRecord {
+        node_id: Some(node_id.to_string()),
+        full_name: full_name.to_string(),
+        ..Default::default()
+    }
+}
+
+fn matching<'a>(query: &str, repos: &'a [RepoRecord], notes: &Notes) -> Vec<&'a str> {
+    let query = Query::parse(query);
+    let ctx = QueryContext::new(notes);
+    repos.iter().filter(|r| query.matches(r, &ctx)).map(|r| r.full_name.as_str()).collect()
+}
+
+#[test]
+fn tags_are_trimmed_and_deduplicated_ignoring_case() {
+    let mut notes = Notes::default();
+    assert!(notes.add_tag("R_1", Some("rust-lang/rust"), " License risk "));
+    assert!(!notes.add_tag("R_1", None, "license RISK"));
+    assert!(!notes.add_tag("R_1", None, "  "));
+    let note = notes.get("R_1").unwrap();
+    assert_eq!(note.tags, ["License risk"]);
+    assert_eq!(note.full_name.as_deref(), Some("rust-lang/rust"));
+}
+
+#[test]
+fn emptied_notes_are_removed_and_remembered_for_sync() {
+    let mut notes = Notes::default();
+    notes.set_text("R_1", Some("rust-lang/rust"), "compiler");
+    notes.add_tag("R_1", None, "core");
+    notes.set_text("R_1", None, "");
+    assert!(notes.get("R_1").is_some(), "a tag keeps the note alive");
+    notes.remove_tag("R_1", "CORE");
+    assert!(notes.get("R_1").is_none());
+    assert_eq!(notes.removed.len(), 1);
+    assert_eq!(notes.all_tags().len(), 0);
+}
+
+#[test]
+fn quoted_values_and_negation_parse_into_clauses() {
+    let query = Query::parse(r#"tag:"license risk" -has:note async"#);
+    assert_eq!(
+        query.clauses,
+        [
+            Clause { negated: false, filter: Filter::Tag("license risk".to_string()) },
+            Clause { negated: true, filter: Filter::HasNote },
+            Clause { negated: false, filter: Filter::Text("async".to_string()) },
+        ]
+    );
+    // Unknown qualifiers are searched as text rather than dropped
+    assert_eq!(Query::parse("has:wiki").clauses[0].filter, Filter::Text("has:wiki".to_string()));
+}
+
+#[test]
+fn note_and_tag_qualifiers_search_private_data() {
+    let mut notes = Notes::default();
+    notes.set_text("R_1", None, "Deprecated in favour of tokio");
+    notes.add_tag("R_2", None, "license risk");
+    let repos = [repo("R_1", "async-rs/async-std"), repo("R_2", "someone/gpl-lib"), repo("R_3", "serde-rs/serde")];
+
+    assert_eq!(matching("note:deprecated", &repos, &notes), ["async-rs/async-std"]);
+    assert_eq!(matching(r#"tag:"License Risk""#, &repos, &notes), ["someone/gpl-lib"]);
+    assert_eq!(matching("-has:tag -has:note", &repos, &notes), ["serde-rs/serde"]);
+    // Free text also looks in notes and tags
+    assert_eq!(matching("tokio", &repos, &notes), ["async-rs/async-std"]);
+}
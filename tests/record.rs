@@ -0,0 +1,72 @@
+// record.rs - the RepoRecord domain type: keys, hosts and labels across sources, and the GitHub wire mapping
+use waffle_search::github::{Repository, SearchResponse};
+use waffle_search::record::{self, RepoRecord, Visibility};
+use waffle_search::source::SourceKind;
+use waffle_search::utility::parse_timestamp;
+
+const SEARCH_RUST: &str = include_str!("fixtures/search_rust.json");
+
+fn record(source: SourceKind, url: &str) -> RepoRecord {
+    RepoRecord {
+        html_url: url.to_string(),
+        source,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn links_are_labelled_with_the_site_they_open() {
+    let label = |source, url| record(source, url).site_name();
+    assert_eq!(label(SourceKind::GitHub, "https://github.com/rust-lang/rust"), "GitHub");
+    assert_eq!(label(SourceKind::GitHub, "https://github.example.com/team/tool"), "github.example.com");
+    assert_eq!(label(SourceKind::GitLab, "https://gitlab.com/group/project"), "GitLab");
+    assert_eq!(label(SourceKind::GitLab, "https://gitlab.example.com/group/project"), "gitlab.example.com");
+    assert_eq!(label(SourceKind::Gitea, "https://codeberg.org/forgejo/forgejo"), "Codeberg");
+    assert_eq!(label(SourceKind::Gitea, "https://git.example.org/me/thing"), "git.example.org");
+}
+
+#[test]
+fn rest_payloads_map_onto_records() {
+    let items = serde_json::from_str::<SearchResponse>(SEARCH_RUST).unwrap().items;
+    let records = record::from_wire_all(items);
+    let rust = &records[0];
+    assert_eq!((rust.full_name.as_str(), rust.owner.as_str(), rust.name.as_str()), ("rust-lang/rust", "rust-lang", "rust"));
+    assert_eq!(rust.node_id.as_deref(), Some("R_kgDO1001"));
+    assert_eq!((rust.stars, rust.forks, rust.open_issues), (101000, 10100, 42));
+    assert_eq!(rust.license.as_ref().and_then(|l| l.spdx_id.as_deref()), Some("MIT"));
+    assert_eq!(rust.pushed_at, parse_timestamp("2025-09-29T21:03:11Z"));
+    assert_eq!(rust.topics, ["compiler", "language"]);
+    assert_eq!((rust.source, rust.visibility), (SourceKind::GitHub, Visibility::Public));
+}
+
+#[test]
+fn wire_details_are_normalised() {
+    let wire = |json: serde_json::Value| RepoRecord::from_wire(serde_json::from_value::<Repository>(json).unwrap());
+    assert_eq!(wire(serde_json::json!({ "name": "nameless" })), None);
+
+    let private = wire(serde_json::json!({ "full_name": "me/secret", "private": true, "description": "" })).unwrap();
+    assert_eq!(private.visibility, Visibility::Private);
+    assert_eq!(private.description, None);
+    assert_eq!(private.html_url, "https://github.com/me/secret");
+
+    // Enterprise node ids are only unique per host, so they carry it
+    let enterprise = wire(serde_json::json!({
+        "full_name": "team/tool",
+        "node_id": "R_1",
+        "html_url": "https://github.example.com/team/tool",
+        "visibility": "internal",
+    }))
+    .unwrap();
+    assert_eq!(enterprise.node_id.as_deref(), Some("github.example.com:R_1"));
+    assert_eq!(enterprise.visibility, Visibility::Internal);
+    assert_eq!(enterprise.key(), "github.example.com/team/tool");
+}
+
+#[test]
+fn stored_values_read_in_either_layout() {
+    let raw = serde_json::json!({ "full_name": "rust-lang/rust", "stargazers_count": 5 });
+    assert_eq!(RepoRecord::from_value(raw).unwrap().stars, 5);
+    let record = RepoRecord { full_name: "rust-lang/rust".to_string(), stars: 7, ..Default::default() };
+    assert_eq!(RepoRecord::from_value(serde_json::to_value(&record).unwrap()), Some(record));
+    assert_eq!(RepoRecord::from_value(serde_json::json!({ "stars": 1 })), None);
+}
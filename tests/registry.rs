@@ -0,0 +1,85 @@
+// registry.rs - package lookups: which answers replace stored package data and which leave it alone
+mod support;
+
+use std::sync::{Arc, Mutex};
+use support::{repo, wait_for, Routes};
+use waffle_search::cancel::CancelToken;
+use waffle_search::registry::{self, Lookup, PackageInfo, Registry};
+
+fn krate(repository: &str) -> String {
+    format!(
+        r#"{{"crate":{{"max_stable_version":"1.0.200","repository":"{}","recent_downloads":42}},
+            "versions":[{{"num":"1.0.200","created_at":"2024-05-01T00:00:00Z"}}]}}"#,
+        repository
+    )
+}
+
+fn lookup(status: u16, body: &str, full_name: &str) -> Lookup {
+    let body = body.to_string();
+    let http = Routes::http(move |_| (status, body.clone()));
+    wait_for(|done| registry::fetch_package(&http, Registry::CratesIo, &repo(full_name), done))
+}
+
+#[test]
+fn a_package_linking_back_is_found() {
+    match lookup(200, &krate("https://github.com/serde-rs/serde"), "serde-rs/serde") {
+        Lookup::Found(package) => {
+            assert_eq!((package.name.as_str(), package.version.as_str()), ("serde", "1.0.200"));
+            assert_eq!(package.downloads, Some(42));
+            assert!(package.published_at.is_some());
+        }
+        other => panic!("expected a package, got {:?}", other),
+    }
+}
+
+#[test]
+fn missing_or_unrelated_packages_are_no_package() {
+    assert_eq!(lookup(404, r#"{"errors":[]}"#, "serde-rs/serde"), Lookup::NoPackage);
+    // Same name, someone else's repository
+    assert_eq!(lookup(200, &krate("https://github.com/someone/serde"), "serde-rs/serde"), Lookup::NoPackage);
+}
+
+#[test]
+fn repository_links_match_in_any_of_their_usual_forms() {
+    let links = [
+        "git+https://github.com/Serde-rs/serde.git",
+        "https://www.github.com/serde-rs/serde/",
+        "https://github.com/serde-rs/serde/tree/master/serde_derive",
+        "git://github.com/serde-rs/serde",
+    ];
+    for link in links {
+        assert!(matches!(lookup(200, &krate(link), "serde-rs/serde"), Lookup::Found(_)), "{}", link);
+    }
+    // A sibling repository sharing the prefix is not a subdirectory
+    assert_eq!(lookup(200, &krate("https://github.com/serde-rs/serde-json"), "serde-rs/serde"), Lookup::NoPackage);
+}
+
+#[test]
+fn errors_and_rate_limits_are_failures() {
+    for status in [429, 500, 503] {
+        assert!(matches!(lookup(status, "", "serde-rs/serde"), Lookup::Failed(_)), "{}", status);
+    }
+    assert!(matches!(lookup(200, "<html>", "serde-rs/serde"), Lookup::Failed(_)));
+}
+
+#[test]
+fn failed_lookups_keep_the_stored_package_unstamped() {
+    let http = Routes::http(|url: &str| match url.rsplit('/').next().unwrap() {
+        "serde" => (200, krate("https://github.com/serde-rs/serde")),
+        "flaky" => (503, String::new()),
+        _ => (404, String::new()),
+    });
+    let mut flaky = repo("someone/flaky");
+    flaky.package = Some(PackageInfo { name: "flaky".to_string(), version: "0.1.0".to_string(), ..Default::default() });
+    flaky.package_checked_at = Some(1.0);
+    let queue = vec![repo("serde-rs/serde"), flaky, repo("someone/gone")];
+    let progress = Arc::new(Mutex::new(None));
+    let updated = wait_for(|done| registry::enrich(http, CancelToken::default(), Registry::CratesIo, queue, 5.0, progress.clone(), done));
+
+    let names = updated.iter().map(|r| r.full_name.as_str()).collect::<Vec<_>>();
+    assert_eq!(names, ["serde-rs/serde", "someone/gone"]);
+    assert!(updated[0].package.is_some());
+    assert_eq!(updated[1].package, None);
+    assert!(updated.iter().all(|r| r.package_checked_at == Some(5.0)));
+    assert_eq!(*progress.lock().unwrap(), None);
+}
@@ -0,0 +1,67 @@
+// saved_search.rs - saved searches: re-running a spec and counting results that are new since the last view
+mod support;
+
+use support::repo;
+use waffle_search::cloud_sync::SyncDocument;
+use waffle_search::notes::Notes;
+use waffle_search::query::{QueryContext, SearchFilters, SearchSpec, SortOrder};
+use waffle_search::record::RepoRecord;
+use waffle_search::saved_search::SavedSearches;
+
+fn names(results: &[RepoRecord]) -> Vec<&str> {
+    results.iter().map(|r| r.full_name.as_str()).collect()
+}
+
+#[test]
+fn a_spec_reruns_its_query_filters_and_sort() {
+    let spec = SearchSpec {
+        query: "stars:>10".to_string(),
+        filters: SearchFilters { exclude_archived: true, ..Default::default() },
+        sort: SortOrder::Name,
+    };
+    let repos = vec![
+        RepoRecord { stars: 500, ..repo("tokio-rs/tokio") },
+        RepoRecord { stars: 900, archived: true, ..repo("old/thing") },
+        RepoRecord { stars: 50, ..repo("async-rs/async-std") },
+        RepoRecord { stars: 5, ..repo("tiny/crate") },
+    ];
+    let notes = Notes::default();
+    let results = spec.run(repos, &QueryContext::new(&notes));
+    assert_eq!(names(&results), ["async-rs/async-std", "tokio-rs/tokio"]);
+}
+
+#[test]
+fn badges_count_results_not_seen_at_the_last_view() {
+    let mut saved = SavedSearches::default();
+    assert!(saved.save("Async", SearchSpec::default(), "Rust"));
+    assert!(!saved.save("  ", SearchSpec::default(), "Rust"));
+
+    let first = [repo("tokio-rs/tokio")];
+    // Never viewed here: the current results are the baseline, not a wall of new ones
+    saved.refresh_badge("Async", &first);
+    assert_eq!(saved.new_count("Async"), 0);
+
+    let later = [repo("tokio-rs/tokio"), repo("smol-rs/smol"), repo("async-rs/async-std")];
+    saved.refresh_badge("Async", &later);
+    assert_eq!(saved.new_count("Async"), 2);
+    saved.mark_viewed("Async", &later);
+    assert_eq!(saved.new_count("Async"), 0);
+}
+
+#[test]
+fn saving_again_overwrites_and_only_searches_sync() {
+    let mut saved = SavedSearches::default();
+    saved.save("Popular", SearchSpec { query: "stars:>100".to_string(), ..Default::default() }, "Rust");
+    saved.save("Popular", SearchSpec { query: "stars:>1000".to_string(), ..Default::default() }, "Python");
+    assert_eq!(saved.searches.len(), 1);
+    assert_eq!(saved.get("Popular").unwrap().language, "Python");
+
+    saved.mark_viewed("Popular", &[repo("psf/requests")]);
+    let records = saved.to_sync_records();
+    assert_eq!(records.len(), 1);
+    assert!(records[0].payload.get("seen").is_none(), "what this device has seen stays local");
+
+    saved.delete("Popular");
+    assert!(saved.views.is_empty());
+    assert!(saved.to_sync_records()[0].deleted);
+}
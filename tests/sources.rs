@@ -0,0 +1,201 @@
+// sources.rs - the GitLab, Gitea and GitHub Enterprise sources: the requests they build and how responses map to records
+mod support;
+
+use support::{repo, wait_for, MockServer, Response};
+use waffle_search::gitea::{GiteaSource, LANGUAGE_QUOTA};
+use waffle_search::github::{api_base, graphql_url, GithubSource};
+use waffle_search::gitlab::GitlabSource;
+use waffle_search::graphql::SyncBackend;
+use waffle_search::http::{Auth, Http};
+use waffle_search::license_policy::LicensePolicy;
+use waffle_search::record::{RepoRecord, Visibility};
+use waffle_search::source::{ForgeConfig, RepoSource, SourceKind};
+use waffle_search::sync;
+
+fn gitlab(topic: &str) -> GitlabSource {
+    GitlabSource {
+        config: ForgeConfig { topic: topic.to_string(), ..ForgeConfig::new("https://gitlab.example.com/") },
+    }
+}
+
+#[test]
+fn gitlab_query_values_are_percent_encoded() {
+    let request = gitlab("web & cli").search_request("C++").unwrap();
+    assert_eq!(
+        request.url,
+        "https://gitlab.example.com/api/v4/projects?order_by=star_count&sort=desc&per_page=100\
+         &with_programming_language=C%2B%2B&topic=web%20%26%20cli"
+    );
+}
+
+const GITLAB_PROJECTS: &str = r#"[
+  {
+    "id": 278964, "name": "GitLab", "path_with_namespace": "gitlab-org/gitlab",
+    "namespace": { "full_path": "gitlab-org" }, "web_url": "https://gitlab.com/gitlab-org/gitlab",
+    "description": "GitLab is an open source end-to-end software development platform",
+    "star_count": 4500, "forks_count": 9800, "open_issues_count": 50000, "topics": ["devops"],
+    "created_at": "2015-05-20T10:10:00.000Z", "last_activity_at": "2025-09-30T08:00:00.000Z",
+    "archived": false, "visibility": "public", "default_branch": "master", "issues_enabled": true,
+    "license": { "key": "other", "name": "Other" }
+  },
+  {
+    "id": 7, "path_with_namespace": "group/sub/fork", "web_url": "https://gitlab.com/group/sub/fork",
+    "description": "", "star_count": 3, "visibility": "internal", "forked_from_project": { "id": 1 }
+  },
+  { "id": 8, "name": "no path" }
+]"#;
+
+#[test]
+fn gitlab_projects_map_onto_records() {
+    let server = MockServer::start(|_, _| Response::json(200, GITLAB_PROJECTS));
+    let source = Box::new(GitlabSource {
+        config: ForgeConfig { token: "glpat-test".to_string(), ..ForgeConfig::new(&server.url) },
+    });
+    let credential = source.credential().unwrap();
+    let http = Http::default().with(Auth(vec![credential]));
+    let records = wait_for(|done| sync::fetch(&http, source, "Rust".to_string(), LicensePolicy::default(), done)).unwrap();
+    assert_eq!(server.requests()[0].header("PRIVATE-TOKEN"), Some("glpat-test"));
+
+    // The projects list reports no licenses reliably, so the unlicensed fork is kept
+    assert_eq!(records.len(), 2);
+    let gitlab = &records[0];
+    assert_eq!((gitlab.full_name.as_str(), gitlab.owner.as_str(), gitlab.name.as_str()), ("gitlab-org/gitlab", "gitlab-org", "GitLab"));
+    assert_eq!(gitlab.node_id.as_deref(), Some("gitlab:gitlab.com:278964"));
+    assert_eq!((gitlab.stars, gitlab.forks, gitlab.open_issues), (4500, 9800, 50000));
+    assert_eq!(gitlab.license.as_ref().map(|l| l.name.as_str()), Some("Other"));
+    assert_eq!((gitlab.source, gitlab.key()), (SourceKind::GitLab, "gitlab.com/gitlab-org/gitlab".to_string()));
+    assert_eq!(gitlab.pushed_at, gitlab.updated_at);
+
+    let fork = &records[1];
+    assert_eq!((fork.owner.as_str(), fork.name.as_str()), ("group/sub", "fork"));
+    assert!(fork.fork);
+    assert_eq!(fork.visibility, Visibility::Internal);
+    assert_eq!(fork.description, None);
+}
+
+/// `total` repositories by descending stars, every `rust_every`th one in Rust, 50 to a page.
+fn gitea_server(total: usize, rust_every: usize, send_total: bool) -> MockServer {
+    MockServer::start(move |request, base| {
+        let page = request.target.split(['?', '&']).find_map(|p| p.strip_prefix("page=")).unwrap().parse::<usize>().unwrap();
+        let data = ((page - 1) * 50..(page * 50).min(total))
+            .map(|i| {
+                let language = if i % rust_every == 0 { "Rust" } else { "Go" };
+                serde_json::json!({
+                    "id": i, "full_name": format!("owner/repo{}", i), "html_url": format!("{}/owner/repo{}", base, i),
+                    "stars_count": 10_000 - i, "language": language,
+                })
+            })
+            .collect::<Vec<_>>();
+        let body = serde_json::json!({ "ok": true, "data": data }).to_string();
+        let response = Response::json(200, &body);
+        if send_total { response.header("X-Total-Count", &total.to_string()) } else { response }
+    })
+}
+
+fn gitea_sync(server: &MockServer, topic: &str) -> Vec<RepoRecord> {
+    let source = Box::new(GiteaSource {
+        config: ForgeConfig { topic: topic.to_string(), ..ForgeConfig::new(&server.url) },
+    });
+    wait_for(|done| sync::fetch(&Http::default(), source, "Rust".to_string(), LicensePolicy::default(), done)).unwrap()
+}
+
+#[test]
+fn gitea_pages_until_the_results_run_out() {
+    let server = gitea_server(120, 4, true);
+    let records = gitea_sync(&server, "cli tools");
+    assert_eq!(records.len(), 30);
+    assert!(records.iter().all(|r| r.language.as_deref() == Some("Rust")));
+    let requests = server.requests();
+    assert_eq!(requests.len(), 3);
+    assert!(requests[0].target.ends_with("&page=1&q=cli%20tools&topic=true"), "{}", requests[0].target);
+}
+
+#[test]
+fn gitea_stops_once_the_language_quota_is_met() {
+    let server = gitea_server(1000, 1, false);
+    let records = gitea_sync(&server, "");
+    assert_eq!(records.len(), LANGUAGE_QUOTA);
+    assert_eq!(server.requests().len(), 2);
+}
+
+#[test]
+fn gitea_stops_at_a_short_page_without_a_total() {
+    let server = gitea_server(60, 2, false);
+    assert_eq!(gitea_sync(&server, "").len(), 30);
+    assert_eq!(server.requests().len(), 2);
+}
+
+const GITEA_SEARCH: &str = r#"{ "ok": true, "data": [
+  {
+    "id": 42, "owner": { "login": "forgejo" }, "name": "runner", "full_name": "forgejo/runner",
+    "html_url": "https://codeberg.org/forgejo/runner", "website": "", "description": "Actions runner",
+    "stars_count": 250, "forks_count": 40, "watchers_count": 30, "open_issues_count": 12, "size": 2048,
+    "private": true, "template": true, "has_issues": true, "default_branch": "main", "language": "rust",
+    "topics": ["ci"], "licenses": ["MIT"], "updated_at": "2025-09-30T08:00:00Z"
+  },
+  { "id": 43, "full_name": "someone/tool", "html_url": "https://codeberg.org/someone/tool", "language": "Go" },
+  { "id": 44, "full_name": "no/url", "language": "Rust" }
+] }"#;
+
+#[test]
+fn gitea_repositories_map_onto_records() {
+    let server = MockServer::start(|_, _| Response::json(200, GITEA_SEARCH));
+    let source = Box::new(GiteaSource {
+        config: ForgeConfig { token: "gitea-test".to_string(), ..ForgeConfig::new(&server.url) },
+    });
+    let credential = source.credential().unwrap();
+    let http = Http::default().with(Auth(vec![credential]));
+    let records = wait_for(|done| sync::fetch(&http, source, "Rust".to_string(), LicensePolicy::default(), done)).unwrap();
+    assert_eq!(server.requests()[0].header("Authorization"), Some("token gitea-test"));
+
+    // Languages match regardless of case; other languages and entries without a URL are dropped
+    assert_eq!(records.len(), 1);
+    let runner = &records[0];
+    assert_eq!((runner.full_name.as_str(), runner.owner.as_str(), runner.name.as_str()), ("forgejo/runner", "forgejo", "runner"));
+    assert_eq!(runner.node_id.as_deref(), Some("gitea:codeberg.org:42"));
+    assert_eq!((runner.stars, runner.forks, runner.watchers, runner.open_issues, runner.size), (250, 40, 30, 12, 2048));
+    assert_eq!(runner.license.as_ref().and_then(|l| l.key.as_deref()), Some("mit"));
+    assert_eq!((runner.visibility, runner.is_template, runner.homepage.as_deref()), (Visibility::Private, true, None));
+    assert_eq!((runner.source, runner.key()), (SourceKind::Gitea, "codeberg.org/forgejo/runner".to_string()));
+    assert!(runner.pushed_at.is_some() && runner.pushed_at == runner.updated_at);
+}
+
+#[test]
+fn enterprise_hosts_get_their_own_api_roots() {
+    assert_eq!(api_base("https://github.com/"), "https://api.github.com");
+    assert_eq!(graphql_url("https://github.com"), "https://api.github.com/graphql");
+    assert_eq!(api_base("https://github.example.com/"), "https://github.example.com/api/v3");
+    assert_eq!(graphql_url("https://github.example.com"), "https://github.example.com/api/graphql");
+}
+
+#[test]
+fn enterprise_syncs_search_and_authenticate_against_their_host() {
+    let source = GithubSource {
+        backend: SyncBackend::Rest,
+        base_url: "https://github.example.com/".to_string(),
+        token: Some("ghe_secret".to_string()),
+        topic: String::new(),
+    };
+    assert_eq!(source.host(), "github.example.com");
+    assert_eq!(
+        source.search_request("Rust").unwrap().url,
+        "https://github.example.com/api/v3/search/repositories?q=language%3ARust&sort=stars&order=desc&per_page=100"
+    );
+    // The token goes to the Enterprise host only, never to api.github.com
+    let credential = source.credential().unwrap();
+    assert_eq!((credential.host.as_str(), credential.value.as_str()), ("github.example.com", "Bearer ghe_secret"));
+
+    // Enterprise records are kept apart from github.com ones of the same name
+    let internal = RepoRecord { html_url: "https://github.example.com/team/tool".to_string(), ..repo("team/tool") };
+    let public = repo("team/tool");
+    assert_eq!((internal.key(), internal.host()), ("github.example.com/team/tool".to_string(), "github.example.com"));
+    assert_eq!(public.key(), "team/tool");
+
+    // User-entered topics can't break out of the q= value
+    let topical = GithubSource { topic: "web & cli".to_string(), ..source };
+    assert_eq!(
+        topical.search_request("C++").unwrap().url,
+        "https://github.example.com/api/v3/search/repositories?q=language%3AC%2B%2B%20topic%3Aweb%20%26%20cli\
+         &sort=stars&order=desc&per_page=100"
+    );
+}
@@ -0,0 +1,59 @@
+// stats.rs - the per-language aggregates behind the stats dashboard
+use waffle_search::history::DAY_MS;
+use waffle_search::record::{LicenseInfo, RepoRecord};
+use waffle_search::stats::{median, LanguageStats, TOP_LICENSES};
+
+const NOW: f64 = 1_700_000_000_000.0;
+
+fn repo(owner: &str, stars: u64, license: Option<&str>) -> RepoRecord {
+    RepoRecord {
+        full_name: format!("{}/r{}", owner, stars),
+        owner: owner.to_string(),
+        stars,
+        license: license.map(|name| LicenseInfo { name: name.to_string(), key: None, spdx_id: None }),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn an_empty_store_has_zeroes_not_nans() {
+    let stats = LanguageStats::compute(&[], NOW);
+    assert_eq!(stats.total, 0);
+    assert_eq!(stats.archived_pct, 0.0);
+    assert_eq!(stats.median_age_days, None);
+    assert!(stats.star_histogram.iter().all(|b| b.count == 0));
+}
+
+#[test]
+fn stars_fall_into_half_open_buckets() {
+    let repos = [repo("a", 999, None), repo("a", 1_000, None), repo("b", 150_000, None)];
+    let stats = LanguageStats::compute(&repos, NOW);
+    let counts = stats.star_histogram.iter().map(|b| (b.label.as_str(), b.count)).collect::<Vec<_>>();
+    assert_eq!(counts[0], ("0-1k", 1));
+    assert_eq!(counts[1], ("1k-5k", 1));
+    assert_eq!(counts.last(), Some(&("100k+", 1)));
+    assert_eq!(stats.total_stars, 151_999);
+}
+
+#[test]
+fn small_licenses_fold_into_other_and_owners_rank_by_repos() {
+    let mut repos = (0..TOP_LICENSES + 2).map(|i| repo("many", i as u64, Some(&format!("License {}", i)))).collect::<Vec<_>>();
+    repos.push(repo("many", 100, Some("License 0")));
+    repos.push(repo("few", 50_000, None));
+    let stats = LanguageStats::compute(&repos, NOW);
+
+    assert_eq!(stats.licenses.len(), TOP_LICENSES + 1);
+    assert_eq!(stats.licenses[0], ("License 0".to_string(), 2));
+    // The two smallest named licenses and "None" (ties sort by name)
+    assert_eq!(stats.licenses.last(), Some(&("Other".to_string(), 3)));
+    assert_eq!(stats.top_owners[0].owner, "many");
+    assert_eq!(stats.top_owners[1].stars, 50_000);
+}
+
+#[test]
+fn median_age_is_in_days() {
+    let aged = |days: f64| RepoRecord { created_at: Some(NOW - days * DAY_MS), ..Default::default() };
+    let stats = LanguageStats::compute(&[aged(10.0), aged(30.0), RepoRecord::default()], NOW);
+    assert_eq!(stats.median_age_days, Some(20.0));
+    assert_eq!(median(&mut [3.0, 1.0, 2.0]), Some(2.0));
+}
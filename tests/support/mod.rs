@@ -0,0 +1,204 @@
+// support/mod.rs - a local HTTP server that replays recorded responses, helpers for driving syncs, and a record builder for tests
+#![allow(dead_code)] // each test binary uses a different slice of this
+
+use std::future::Future;
+use std::io::{BufRead, BufReader, Write};
+use std::net::TcpListener;
+use std::path::PathBuf;
+use std::sync::{mpsc, Arc, Mutex};
+use std::task::{Context, Poll, Waker};
+use std::time::Duration;
+use waffle_search::http::{Callback, Http, HttpClient};
+use waffle_search::record::RepoRecord;
+
+pub const SEARCH_RUST: &str = include_str!("../fixtures/search_rust.json");
+pub const MALFORMED_SEARCH: &str = include_str!("../fixtures/malformed_search.json");
+pub const STARRED_PAGE1: &str = include_str!("../fixtures/starred_page1.json");
+pub const STARRED_PAGE2: &str = include_str!("../fixtures/starred_page2.json");
+
+/// A request as the server saw it.
+#[derive(Clone, Debug)]
+pub struct Request {
+    pub method: String,
+    /// Path and query, e.g. /api/v3/search/repositories?q=language%3ARust
+    pub target: String,
+    pub headers: Vec<(String, String)>,
+}
+
+impl Request {
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers.iter().find(|(k, _)| k.eq_ignore_ascii_case(name)).map(|(_, v)| v.as_str())
+    }
+
+    pub fn path(&self) -> &str {
+        self.target.split('?').next().unwrap_or_default()
+    }
+}
+
+/// What the server answers with.
+pub struct Response {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: String,
+}
+
+impl Response {
+    pub fn json(status: u16, body: &str) -> Self {
+        Self {
+            status,
+            headers: vec![("Content-Type".to_string(), "application/json; charset=utf-8".to_string())],
+            body: body.to_string(),
+        }
+    }
+
+    pub fn header(mut self, name: &str, value: &str) -> Self {
+        self.headers.push((name.to_string(), value.to_string()));
+        self
+    }
+}
+
+/// Serves `handler`'s responses on an ephemeral localhost port until the test process exits.
+pub struct MockServer {
+    pub url: String,
+    requests: Arc<Mutex<Vec<Request>>>,
+}
+
+impl MockServer {
+    pub fn start<H>(handler: H) -> Self
+    where
+        H: Fn(&Request, &str) -> Response + Send + 'static,
+    {
+        let listener = TcpListener::bind("127.0.0.1:0").expect("bind mock server");
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let seen = Arc::clone(&requests);
+        let base = url.clone();
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let Ok(mut stream) = stream else { continue };
+                let Some(request) = read_request(&mut BufReader::new(&stream)) else { continue };
+                seen.lock().unwrap().push(request.clone());
+                let response = handler(&request, &base);
+                let _ = write_response(&mut stream, &response);
+            }
+        });
+        Self { url, requests }
+    }
+
+    /// Every request served so far, oldest first.
+    pub fn requests(&self) -> Vec<Request> {
+        self.requests.lock().unwrap().clone()
+    }
+}
+
+fn read_request(reader: &mut impl BufRead) -> Option<Request> {
+    let mut line = String::new();
+    reader.read_line(&mut line).ok()?;
+    let mut parts = line.split_whitespace();
+    let method = parts.next()?.to_string();
+    let target = parts.next()?.to_string();
+    let mut headers = Vec::new();
+    loop {
+        line.clear();
+        reader.read_line(&mut line).ok()?;
+        let header = line.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some((k, v)) = header.split_once(':') {
+            headers.push((k.trim().to_string(), v.trim().to_string()));
+        }
+    }
+    Some(Request { method, target, headers })
+}
+
+fn write_response(stream: &mut impl Write, response: &Response) -> std::io::Result<()> {
+    write!(stream, "HTTP/1.1 {} {}\r\n", response.status, reason(response.status))?;
+    for (k, v) in &response.headers {
+        write!(stream, "{}: {}\r\n", k, v)?;
+    }
+    write!(stream, "Content-Length: {}\r\nConnection: close\r\n\r\n", response.body.len())?;
+    stream.write_all(response.body.as_bytes())?;
+    stream.flush()
+}
+
+fn reason(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        304 => "Not Modified",
+        403 => "Forbidden",
+        404 => "Not Found",
+        429 => "Too Many Requests",
+        500 => "Internal Server Error",
+        503 => "Service Unavailable",
+        _ => "Unknown",
+    }
+}
+
+/// Answers in-process by URL, for hosts the mock server can't stand in for (registries live at fixed URLs).
+pub struct Routes<H>(pub H);
+
+impl<H> Routes<H>
+where
+    H: Fn(&str) -> (u16, String) + Send + Sync + 'static,
+{
+    pub fn http(handler: H) -> Http {
+        Http::new(Arc::new(Routes(handler)))
+    }
+}
+
+impl<H> HttpClient for Routes<H>
+where
+    H: Fn(&str) -> (u16, String) + Send + Sync,
+{
+    fn fetch(&self, request: ehttp::Request, done: Callback) {
+        let (status, body) = (self.0)(&request.url);
+        done(Ok(ehttp::Response {
+            url: request.url,
+            ok: (200..300).contains(&status),
+            status,
+            status_text: reason(status).to_string(),
+            headers: ehttp::Headers::new(&[]),
+            bytes: body.into_bytes(),
+        }));
+    }
+}
+
+/// Wait for a callback-style fetch to call back.
+pub fn wait_for<T: Send + 'static>(start: impl FnOnce(Box<dyn FnOnce(T) + Send>)) -> T {
+    let (tx, rx) = mpsc::channel();
+    start(Box::new(move |value| {
+        let _ = tx.send(value);
+    }));
+    rx.recv_timeout(Duration::from_secs(10)).expect("fetch never called back")
+}
+
+/// Drive a future that never actually waits, like the `NativeStore` cache's.
+pub fn block_on<F: Future>(future: F) -> F::Output {
+    let mut future = std::pin::pin!(future);
+    let mut cx = Context::from_waker(Waker::noop());
+    loop {
+        if let Poll::Ready(value) = future.as_mut().poll(&mut cx) {
+            return value;
+        }
+    }
+}
+
+/// An empty directory for one test's stores.
+pub fn temp_store_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("waffle_test_{}_{}", std::process::id(), name));
+    let _ = std::fs::remove_dir_all(&dir);
+    dir
+}
+
+/// A github.com repository with nothing else set; tests fill in the rest with `..repo(full_name)`.
+pub fn repo(full_name: &str) -> RepoRecord {
+    let (owner, name) = full_name.split_once('/').unwrap_or_default();
+    RepoRecord {
+        full_name: full_name.to_string(),
+        owner: owner.to_string(),
+        name: name.to_string(),
+        html_url: format!("https://github.com/{}", full_name),
+        ..Default::default()
+    }
+}
@@ -0,0 +1,329 @@
+// sync.rs - the sync path (fetch, license filter, store, reload) against recorded GitHub responses
+mod support;
+
+use support::{block_on, repo, temp_store_dir, wait_for, MockServer, Response};
+use waffle_search::cancel::{CancelToken, Slot, CANCELLED};
+use waffle_search::github::{fetch_readme, fetch_starred, GithubSource};
+use waffle_search::graphql::SyncBackend;
+use waffle_search::http::{Auth, Credential, Http, ResponseCache};
+use waffle_search::idb::STARS_STORE;
+use waffle_search::license_policy::{LicensePolicy, UnlicensedHandling};
+use waffle_search::native::NativeStore;
+use waffle_search::record::RepoRecord;
+use waffle_search::registry::{PackageInfo, Registry};
+use waffle_search::source::SourceKind;
+use waffle_search::sync::{self, RepoCache, SyncMeta};
+
+const ETAG: &str = "W/\"5c0f0a\"";
+
+/// The real ehttp transport, authenticated against the mock server.
+fn http(server: &MockServer) -> Http {
+    let host = server.url.trim_start_matches("http://");
+    Http::default().with(Auth(vec![Credential::bearer(host, "ghp_test")]))
+}
+
+fn source(server: &MockServer) -> Box<GithubSource> {
+    Box::new(GithubSource {
+        backend: SyncBackend::Rest,
+        base_url: server.url.clone(),
+        token: Some("ghp_test".to_string()),
+        topic: String::new(),
+    })
+}
+
+fn fetch_with(http: &Http, server: &MockServer, policy: LicensePolicy) -> Result<Vec<RepoRecord>, String> {
+    let source = source(server);
+    wait_for(|done| sync::fetch(http, source, "Rust".to_string(), policy, done))
+}
+
+fn fetch(server: &MockServer, policy: LicensePolicy) -> Result<Vec<RepoRecord>, String> {
+    fetch_with(&http(server), server, policy)
+}
+
+/// The fixtures link to github.com, whichever server handed them out.
+fn meta() -> SyncMeta {
+    SyncMeta {
+        language: "Rust".to_string(),
+        source: SourceKind::GitHub,
+        host: "github.com".to_string(),
+        synced_at: 1_700_000_000_000.0,
+        count: 0,
+        keys: Vec::new(),
+    }
+}
+
+fn fresh(outcome: Result<Vec<RepoRecord>, String>) -> Vec<RepoRecord> {
+    outcome.unwrap_or_else(|e| panic!("expected results, got {}", e))
+}
+
+/// Answers the search with the recorded page, or 304 when asked with its ETag.
+fn search_server() -> MockServer {
+    MockServer::start(|request, _| {
+        if request.path() != "/api/v3/search/repositories" {
+            return Response::json(404, r#"{"message":"Not Found"}"#);
+        }
+        if request.header("If-None-Match") == Some(ETAG) {
+            return Response::json(304, "");
+        }
+        Response::json(200, support::SEARCH_RUST).header("ETag", ETAG)
+    })
+}
+
+#[test]
+fn sync_filters_stores_and_reloads() {
+    let server = search_server();
+    let records = fresh(fetch(&server, LicensePolicy::default()));
+    // The default policy drops the unlicensed repository
+    let names = records.iter().map(|r| r.full_name.as_str()).collect::<Vec<_>>();
+    assert_eq!(names, ["rust-lang/rust", "denoland/deno"]);
+
+    let request = &server.requests()[0];
+    assert!(request.target.contains("q=language%3ARust"), "{}", request.target);
+    assert_eq!(request.header("Authorization"), Some("Bearer ghp_test"));
+
+    let cache = NativeStore::at(temp_store_dir("sync_reload"));
+    block_on(sync::store(&cache, meta(), records.clone(), &CancelToken::default())).unwrap();
+    let mut reloaded = block_on(RepoCache::records(&cache, "Rust")).unwrap();
+    reloaded.sort_by(|a, b| b.stars.cmp(&a.stars));
+    assert_eq!(reloaded, records);
+    assert_eq!(reloaded[0].license.as_ref().and_then(|l| l.spdx_id.as_deref()), Some("MIT"));
+    assert_eq!(reloaded[0].topics, ["compiler", "language"]);
+}
+
+#[test]
+fn unlicensed_repositories_are_kept_when_the_policy_says_so() {
+    let server = search_server();
+    let policy = LicensePolicy { unlicensed: UnlicensedHandling::Keep, ..LicensePolicy::default() };
+    let records = fresh(fetch(&server, policy));
+    assert_eq!(records.len(), 3);
+    assert!(records.iter().any(|r| r.full_name == "example/unlicensed" && r.license.is_none()));
+}
+
+#[test]
+fn resync_keeps_package_data() {
+    let server = search_server();
+    let cache = NativeStore::at(temp_store_dir("sync_carry_over"));
+    let records = fresh(fetch(&server, LicensePolicy::default()));
+    let mut enriched = records.clone();
+    enriched[0].package = Some(PackageInfo {
+        registry: Registry::CratesIo,
+        name: "rust".to_string(),
+        version: "1.0.0".to_string(),
+        published_at: None,
+        downloads: Some(10),
+    });
+    enriched[0].package_checked_at = Some(1.0);
+    block_on(cache.put_records("Rust", &enriched)).unwrap();
+
+    let stored = block_on(sync::store(&cache, meta(), records, &CancelToken::default())).unwrap();
+    assert_eq!(stored[0].package, enriched[0].package);
+    assert_eq!(stored[0].package_checked_at, Some(1.0));
+}
+
+#[test]
+fn unchanged_results_are_replayed_under_the_current_policy() {
+    let server = search_server();
+    let http = http(&server).with(ResponseCache::default());
+    let strict = fresh(fetch_with(&http, &server, LicensePolicy::default()));
+    // The server answers 304, the cached page stands in, and the policy that changed meanwhile applies to it
+    let policy = LicensePolicy { unlicensed: UnlicensedHandling::Keep, ..LicensePolicy::default() };
+    let replayed = fresh(fetch_with(&http, &server, policy));
+    assert_eq!(strict.len(), 2);
+    assert_eq!(replayed.len(), 3);
+    let requests = server.requests();
+    assert_eq!(requests[0].header("If-None-Match"), None);
+    assert_eq!(requests[1].header("If-None-Match"), Some(ETAG));
+}
+
+#[test]
+fn not_modified_without_a_cached_copy_is_an_error() {
+    let server = MockServer::start(|_, _| Response::json(304, ""));
+    let err = fetch(&server, LicensePolicy::default()).unwrap_err();
+    assert!(err.starts_with("Not modified"), "{}", err);
+}
+
+#[test]
+fn rate_limit_reports_the_reset_time() {
+    let server = MockServer::start(|_, _| {
+        Response::json(403, r#"{"message":"API rate limit exceeded for 127.0.0.1."}"#)
+            .header("X-RateLimit-Limit", "60")
+            .header("X-RateLimit-Remaining", "0")
+            .header("X-RateLimit-Reset", "1760000000")
+    });
+    let err = fetch(&server, LicensePolicy::default()).unwrap_err();
+    assert!(err.starts_with("Rate limited by 127.0.0.1"), "{}", err);
+    assert!(err.contains("2025-10-09"), "{}", err);
+}
+
+#[test]
+fn forbidden_without_rate_limit_is_an_http_error() {
+    let server = MockServer::start(|_, _| Response::json(403, r#"{"message":"Resource not accessible"}"#).header("X-RateLimit-Remaining", "4999"));
+    let err = fetch(&server, LicensePolicy::default()).unwrap_err();
+    assert_eq!(err, "HTTP Error: 403 - Forbidden");
+}
+
+#[test]
+fn malformed_json_is_a_parse_error() {
+    let server = MockServer::start(|_, _| Response::json(200, support::MALFORMED_SEARCH));
+    let err = fetch(&server, LicensePolicy::default()).unwrap_err();
+    assert!(err.starts_with("Failed to parse JSON"), "{}", err);
+}
+
+#[test]
+fn starred_follows_pagination() {
+    let server = MockServer::start(|request, base| {
+        if request.target.ends_with("page=2") {
+            Response::json(200, support::STARRED_PAGE2)
+        } else {
+            let link = format!(
+                "<{base}/user/starred?per_page=100&page=2>; rel=\"next\", <{base}/user/starred?per_page=100&page=2>; rel=\"last\""
+            );
+            Response::json(200, support::STARRED_PAGE1).header("Link", &link)
+        }
+    });
+    let url = format!("{}/user/starred?per_page=100", server.url);
+    let repos = wait_for(|done| fetch_starred(http(&server), url, done)).unwrap();
+    let names = repos.iter().filter_map(|r| r.full_name.as_deref()).collect::<Vec<_>>();
+    assert_eq!(names, ["serde-rs/serde", "tokio-rs/tokio", "psf/requests"]);
+    let requests = server.requests();
+    assert_eq!(requests.len(), 2);
+    assert!(requests.iter().all(|r| r.header("Authorization") == Some("Bearer ghp_test")));
+}
+
+#[test]
+fn a_failing_starred_page_fails_the_whole_import() {
+    let server = MockServer::start(|request, base| {
+        if request.target.ends_with("page=2") {
+            Response::json(401, r#"{"message":"Bad credentials"}"#)
+        } else {
+            Response::json(200, support::STARRED_PAGE1).header("Link", &format!("<{base}/user/starred?per_page=100&page=2>; rel=\"next\""))
+        }
+    });
+    let url = format!("{}/user/starred?per_page=100", server.url);
+    let err = wait_for(|done| fetch_starred(http(&server), url, done)).unwrap_err();
+    assert!(err.contains("401"), "{}", err);
+}
+
+#[test]
+fn a_stars_sync_replaces_my_stars() {
+    let cache = NativeStore::at(temp_store_dir("sync_stars"));
+    let meta = |at: f64| SyncMeta { language: STARS_STORE.to_string(), synced_at: at, ..Default::default() };
+    block_on(sync::store(&cache, meta(1.0), vec![repo("serde-rs/serde"), repo("psf/requests")], &CancelToken::default())).unwrap();
+    // Unstarred since: gone from the store
+    block_on(sync::store(&cache, meta(2.0), vec![repo("serde-rs/serde")], &CancelToken::default())).unwrap();
+    let stored = block_on(RepoCache::records(&cache, STARS_STORE)).unwrap();
+    assert_eq!(stored.iter().map(|r| r.full_name.as_str()).collect::<Vec<_>>(), ["serde-rs/serde"]);
+}
+
+#[test]
+fn readmes_come_raw_through_the_shared_stack() {
+    let server = MockServer::start(|request, _| match request.path() {
+        "/repos/rust-lang/rust/readme" => Response { status: 200, headers: Vec::new(), body: "\n# Rust\n".to_string() },
+        _ => Response::json(404, r#"{"message":"Not Found"}"#),
+    });
+    let readme = wait_for(|done| fetch_readme(&http(&server), &server.url, "rust-lang/rust", done)).unwrap();
+    assert_eq!(readme.as_deref(), Some("# Rust"));
+    let missing = wait_for(|done| fetch_readme(&http(&server), &server.url, "someone/empty", done)).unwrap();
+    assert_eq!(missing, None);
+
+    let requests = server.requests();
+    assert_eq!(requests[0].header("Accept"), Some("application/vnd.github.raw"));
+    assert_eq!(requests[0].header("Authorization"), Some("Bearer ghp_test"));
+}
+
+#[test]
+fn cancelled_syncs_send_nothing() {
+    let server = search_server();
+    let cancel = CancelToken::default();
+    cancel.cancel();
+    let err = fetch_with(&http(&server).with(cancel), &server, LicensePolicy::default()).unwrap_err();
+    assert_eq!(err, CANCELLED);
+    assert!(server.requests().is_empty());
+}
+
+#[test]
+fn results_arriving_after_cancel_are_discarded() {
+    let cancel = CancelToken::default();
+    let in_flight = cancel.clone();
+    // The user switches language while the server is still answering
+    let server = MockServer::start(move |_, _| {
+        in_flight.cancel();
+        Response::json(200, support::SEARCH_RUST)
+    });
+    let err = fetch_with(&http(&server).with(cancel.clone()), &server, LicensePolicy::default()).unwrap_err();
+    assert_eq!(err, CANCELLED);
+
+    let cache = NativeStore::at(temp_store_dir("sync_cancelled_store"));
+    let records = fresh(fetch(&search_server(), LicensePolicy::default()));
+    assert_eq!(block_on(sync::store(&cache, meta(), records, &cancel)).unwrap_err(), CANCELLED);
+    assert!(block_on(RepoCache::records(&cache, "Rust")).unwrap().is_empty());
+}
+
+#[test]
+fn a_new_sync_supersedes_the_running_one() {
+    let slot = Slot::default();
+    let first = slot.start();
+    let second = slot.start();
+    assert!(first.is_cancelled());
+    assert!(!second.is_cancelled());
+    // The superseded sync finishing late must not end the current one
+    slot.finish(&first);
+    assert!(slot.is_running());
+    slot.finish(&second);
+    assert!(!slot.is_running());
+}
+
+#[test]
+fn resync_replaces_its_own_records_only() {
+    let server = search_server();
+    let cache = NativeStore::at(temp_store_dir("sync_snapshot"));
+    let records = fresh(fetch(&server, LicensePolicy::default()));
+    let elsewhere = RepoRecord {
+        full_name: "group/project".to_string(),
+        html_url: "https://gitlab.com/group/project".to_string(),
+        source: SourceKind::GitLab,
+        ..Default::default()
+    };
+    // The previous sync returned old/unstarred; imported/tool came from an import
+    let mut previous = records.clone();
+    previous.push(repo("old/unstarred"));
+    block_on(sync::store(&cache, meta(), previous, &CancelToken::default())).unwrap();
+    block_on(cache.put_records("Rust", &[repo("imported/tool"), elsewhere])).unwrap();
+
+    block_on(sync::store(&cache, meta(), records, &CancelToken::default())).unwrap();
+    let mut keys = block_on(RepoCache::records(&cache, "Rust")).unwrap().iter().map(RepoRecord::key).collect::<Vec<_>>();
+    keys.sort();
+    // GitHub dropped old/unstarred; the import and the GitLab record are no sync's to delete
+    assert_eq!(keys, ["denoland/deno", "gitlab.com/group/project", "imported/tool", "rust-lang/rust"]);
+    let stored_meta = cache.get::<SyncMeta>(waffle_search::idb::SYNC_META_STORE, &meta().store_key()).unwrap().unwrap();
+    let keys = vec!["rust-lang/rust".to_string(), "denoland/deno".to_string()];
+    assert_eq!(stored_meta, SyncMeta { count: 2, keys, ..meta() });
+}
+
+#[test]
+fn enrichment_write_back_only_touches_package_fields() {
+    let server = search_server();
+    let cache = NativeStore::at(temp_store_dir("sync_store_packages"));
+    let records = fresh(fetch(&server, LicensePolicy::default()));
+    // Enrichment started from the old snapshot, which still had old/removed
+    let mut looked_up = records.clone();
+    looked_up[0].stars = 1;
+    looked_up[0].package = Some(PackageInfo {
+        registry: Registry::CratesIo,
+        name: "rust".to_string(),
+        version: "1.0.0".to_string(),
+        published_at: None,
+        downloads: None,
+    });
+    looked_up[0].package_checked_at = Some(2.0);
+    looked_up.push(repo("old/removed"));
+    block_on(sync::store(&cache, meta(), records.clone(), &CancelToken::default())).unwrap();
+
+    block_on(sync::store_packages(&cache, "Rust", looked_up.clone(), &CancelToken::default())).unwrap();
+    let mut stored = block_on(RepoCache::records(&cache, "Rust")).unwrap();
+    stored.sort_by(|a, b| b.stars.cmp(&a.stars));
+    assert_eq!(stored.len(), 2);
+    assert_eq!(stored[0].stars, records[0].stars);
+    assert_eq!(stored[0].package, looked_up[0].package);
+    assert_eq!(stored[0].package_checked_at, Some(2.0));
+}
@@ -0,0 +1,59 @@
+// sync_report.rs - the "What changed" diff between two syncs of a language
+mod support;
+
+use support::repo;
+use waffle_search::record::{LicenseInfo, RepoRecord};
+use waffle_search::sync_report::{SyncReport, SyncSnapshot, TOP_N};
+
+const BEFORE: f64 = 1_700_000_000_000.0;
+const AFTER: f64 = 1_700_086_400_000.0;
+
+/// TOP_N repositories with 10_000 down to 10_000 - TOP_N + 1 stars
+fn leaderboard() -> Vec<RepoRecord> {
+    (0..TOP_N).map(|i| RepoRecord { stars: 10_000 - i as u64, ..repo(&format!("top/r{}", i)) }).collect()
+}
+
+#[test]
+fn a_first_sync_has_no_baseline_and_no_changes() {
+    let report = SyncReport::compute("Rust", None, &SyncSnapshot::new(&leaderboard(), AFTER));
+    assert_eq!(report.previous_at, None);
+    assert!(report.is_empty());
+    assert!(report.to_markdown().starts_with("## Rust first synced"));
+}
+
+#[test]
+fn leaderboard_entries_and_exits_are_reported_with_ranks() {
+    let before = leaderboard();
+    let mut after = before.clone();
+    after.push(RepoRecord { stars: 20_000, ..repo("new/star") });
+    let report = SyncReport::compute("Rust", Some(&SyncSnapshot::new(&before, BEFORE)), &SyncSnapshot::new(&after, AFTER));
+
+    assert_eq!(report.entered_top.len(), 1);
+    assert_eq!((report.entered_top[0].full_name.as_str(), report.entered_top[0].rank), ("new/star", 1));
+    assert_eq!(report.left_top.len(), 1);
+    assert_eq!((report.left_top[0].full_name.as_str(), report.left_top[0].rank), (format!("top/r{}", TOP_N - 1).as_str(), TOP_N));
+}
+
+#[test]
+fn jumps_archives_and_field_changes_compare_the_same_repository() {
+    let small = RepoRecord { stars: 100, ..repo("small/rising") };
+    let old = RepoRecord { stars: 500, ..repo("old/thing") };
+    let before = vec![RepoRecord { stars: 90_000, ..repo("big/steady") }, small.clone(), old.clone()];
+    let license = LicenseInfo { name: "MIT License".to_string(), key: Some("mit".to_string()), spdx_id: Some("MIT".to_string()) };
+    let after = vec![
+        RepoRecord { stars: 90_200, ..repo("big/steady") },
+        RepoRecord { stars: 160, ..small },
+        RepoRecord { archived: true, license: Some(license), ..old },
+    ];
+
+    let report = SyncReport::compute("Rust", Some(&SyncSnapshot::new(&before, BEFORE)), &SyncSnapshot::new(&after, AFTER));
+    // +60 on 100 is a jump; +200 on 90,000 is noise
+    assert_eq!(report.star_jumps.iter().map(|j| (j.full_name.as_str(), j.delta())).collect::<Vec<_>>(), [("small/rising", 60)]);
+    assert_eq!(report.newly_archived, ["old/thing"]);
+    assert_eq!(report.license_changes[0].after.as_deref(), Some("MIT License"));
+    assert_eq!(report.change_count(), 3);
+
+    let markdown = report.to_markdown();
+    assert!(markdown.contains("- [small/rising](https://github.com/small/rising) +60 (⭐ 100 → 160)"), "{}", markdown);
+    assert!(markdown.contains("### Newly archived\n\n- old/thing\n"), "{}", markdown);
+}